@@ -1,19 +1,27 @@
+use exec_plugin::{ExecPluginConfig, ExecPluginMeta};
 use file_plugin::{FilePluginConfig, FilePluginMeta};
-use filter_plugin::{FilterPluginConfig, FilterPluginMeta};
+use filter_plugin::{FilterActions, FilterPluginConfig, FilterPluginMeta};
+use http_client::{signature::SignatureConfig, RetryConfig};
 use mongo_plugin::{MongoPluginConfig, MongoPluginMeta};
+use plugins_filter::ast::Expr;
 use postgres_plugin::{PostgresPluginConfig, PostgresPluginMeta};
 use postgres_sea_plugin::{PostgresSeaPluginConfig, PostgresSeaPluginMeta};
 use postgres_x_plugin::{PostgresXPluginConfig, PostgresXPluginMeta};
 use print_plugin::{PrintPluginConfig, PrintPluginMeta};
+use push_definitions::Delivery;
 use schemars::JsonSchema;
+use script_plugin::{ScriptConfig, ScriptPluginConfig, ScriptPluginMeta};
 use serde::{Deserialize, Serialize};
 use sqlite_plugin::{SqlitePluginConfig, SqlitePluginMeta};
 use std::{
+    collections::BTreeMap,
     net::{Ipv4Addr, SocketAddr},
     path::Path,
     str::FromStr,
 };
 use thiserror::Error as ThisError;
+use wasm_plugin::{WasmPluginConfig, WasmPluginMeta};
+use webhook_plugin::{WebhookPluginConfig, WebhookPluginMeta};
 
 #[derive(ThisError, Debug)]
 pub enum ConfigFromYamlFileError {
@@ -35,6 +43,12 @@ pub enum ConfigFromYamlFileError {
 pub struct Config {
     pub server: ServerConfig,
     pub plugins: Option<PluginsConfig>,
+    /// Transforms applied in-process before pushing to the plugins
+    pub pipeline: Option<PipelineConfig>,
+    /// Pipelines served at `/push/{name}`, each with its own plugins.
+    /// `plugins` and `pipeline` above make up the default pipeline served at `/push`
+    #[serde(default)]
+    pub pipelines: BTreeMap<String, NamedPipelineConfig>,
 }
 
 impl Config {
@@ -63,6 +77,43 @@ impl Config {
 pub struct ServerConfig {
     pub host: LocalhostOrIpv4Addr,
     pub port: u16,
+    /// Requires pushes to be signed
+    pub push_signature: Option<SignatureConfig>,
+    /// Time for in-flight pushes to finish once the server stopped accepting pushes.
+    /// Plugin pushes still running afterwards are cancelled
+    #[serde(default = "default_drain_timeout_seconds")]
+    pub drain_timeout_seconds: u64,
+    /// Time for the plugins to shut down, after the drain
+    #[serde(default = "default_plugin_shutdown_timeout_seconds")]
+    pub plugin_shutdown_timeout_seconds: u64,
+    /// Retries transient plugin failures, only with the alerts that failed.
+    /// Not applied to plugins that retry themselves, like HTTP plugins with `http_client.retry`.
+    /// `max_retry_after_seconds` is not used, plugin errors have no `Retry-After`
+    #[serde(default)]
+    pub push_retry: RetryConfig,
+    /// Largest accepted request body. Larger requests are rejected with 413
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Largest accepted request body of `/push/batch` and `/push/{pipeline}/batch`
+    #[serde(default = "default_max_batch_body_bytes")]
+    pub max_batch_body_bytes: usize,
+}
+
+fn default_drain_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_plugin_shutdown_timeout_seconds() -> u64 {
+    10
+}
+
+/// Same as axum's default
+fn default_max_body_bytes() -> usize {
+    2 * 1024 * 1024
+}
+
+fn default_max_batch_body_bytes() -> usize {
+    16 * 1024 * 1024
 }
 
 #[derive(JsonSchema)]
@@ -103,6 +154,103 @@ impl From<LocalhostOrIpv4Addr> for Ipv4Addr {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NamedPipelineConfig {
+    /// Requires pushes to the pipeline to be signed. The server's `push_signature` if not set.
+    /// `none` accepts unsigned pushes, even if the server requires signatures
+    pub push_signature: Option<PipelineSignatureConfig>,
+    pub plugins: Option<PluginsConfig>,
+    /// Transforms applied in-process before pushing to the plugins of the pipeline
+    pub pipeline: Option<PipelineConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum PipelineSignatureConfig {
+    /// Pushes don't need to be signed
+    None(NoSignature),
+    /// Pushes must be signed with this configuration
+    Required(SignatureConfig),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NoSignature {
+    None,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PipelineConfig {
+    /// Stages applied in order to every push
+    #[serde(default)]
+    pub stages: Vec<StageConfig>,
+    /// Plugins receiving the transformed push. All plugins if not set
+    pub targets: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StageConfig {
+    /// Applies the same actions as the Filter plugin
+    Filter(FilterActions),
+    /// Adds static labels and annotations
+    Enrich(EnrichStageConfig),
+    /// Drops alerts that were already seen with the same status
+    Dedup(DedupStageConfig),
+    /// Runs the `transform` world of a WASI component
+    Wasm(WasmStageConfig),
+    /// Runs a Rhai script that can modify, drop or route the push
+    Script(ScriptStageConfig),
+}
+
+/// What happens to a push if a stage fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnStageError {
+    /// Stop the pipeline and fail the push, so it is not delivered unprocessed
+    #[default]
+    Fail,
+    /// Keep the push unchanged and run the next stage
+    Continue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WasmStageConfig {
+    #[serde(flatten)]
+    pub config: WasmPluginConfig,
+    #[serde(default)]
+    pub on_error: OnStageError,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ScriptStageConfig {
+    #[serde(flatten)]
+    pub config: ScriptConfig,
+    #[serde(default)]
+    pub on_error: OnStageError,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct EnrichStageConfig {
+    #[serde(default)]
+    pub common_labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub common_annotations: BTreeMap<String, String>,
+    #[serde(default)]
+    pub alerts_labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub alerts_annotations: BTreeMap<String, String>,
+    /// Replace existing values instead of keeping them
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DedupStageConfig {
+    /// How long an alert is remembered
+    pub window_seconds: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PluginsConfig {
     pub file_plugin: Option<Vec<FilePluginFromFileConfig>>,
@@ -113,54 +261,114 @@ pub struct PluginsConfig {
     pub postgres_x_plugin: Option<Vec<PostgresXPluginFromFileConfig>>,
     pub print_plugin: Option<Vec<PrintPluginFromFileConfig>>,
     pub sqlite_plugin: Option<Vec<SqlitePluginFromFileConfig>>,
+    pub webhook_plugin: Option<Vec<WebhookPluginFromFileConfig>>,
+    pub wasm_plugin: Option<Vec<WasmPluginFromFileConfig>>,
+    pub script_plugin: Option<Vec<ScriptPluginFromFileConfig>>,
+    pub exec_plugin: Option<Vec<ExecPluginFromFileConfig>>,
+}
+
+/// Options shared by all plugins, next to their `meta` and `config`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PluginOptions {
+    /// How pushes are handed to the plugin
+    #[serde(default)]
+    pub delivery: Delivery,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FilterPluginFromFileConfig {
     pub meta: FilterPluginMeta,
     pub config: FilterPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FilePluginFromFileConfig {
     pub meta: FilePluginMeta,
     pub config: FilePluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MongoPluginFromFileConfig {
     pub meta: MongoPluginMeta,
     pub config: MongoPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PostgresPluginFromFileConfig {
     pub meta: PostgresPluginMeta,
     pub config: PostgresPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PostgresSeaPluginFromFileConfig {
     pub meta: PostgresSeaPluginMeta,
     pub config: PostgresSeaPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PostgresXPluginFromFileConfig {
     pub meta: PostgresXPluginMeta,
     pub config: PostgresXPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PrintPluginFromFileConfig {
     pub meta: PrintPluginMeta,
     pub config: PrintPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SqlitePluginFromFileConfig {
     pub meta: SqlitePluginMeta,
     pub config: SqlitePluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookPluginFromFileConfig {
+    pub meta: WebhookPluginMeta,
+    pub config: WebhookPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WasmPluginFromFileConfig {
+    pub meta: WasmPluginMeta,
+    pub config: WasmPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ScriptPluginFromFileConfig {
+    pub meta: ScriptPluginMeta,
+    pub config: ScriptPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExecPluginFromFileConfig {
+    pub meta: ExecPluginMeta,
+    pub config: ExecPluginConfig,
+    #[serde(flatten)]
+    pub options: PluginOptions,
 }
 
 #[cfg(test)]
@@ -176,8 +384,16 @@ mod test {
             server: ServerConfig {
                 host: LocalhostOrIpv4Addr::Localhost(Localhost),
                 port: 8080,
+                push_signature: None,
+                drain_timeout_seconds: 30,
+                plugin_shutdown_timeout_seconds: 10,
+                push_retry: Default::default(),
+                max_body_bytes: default_max_body_bytes(),
+                max_batch_body_bytes: default_max_batch_body_bytes(),
             },
             plugins: None,
+            pipeline: None,
+            pipelines: Default::default(),
         };
         let config = serde_json::to_string_pretty(&config).expect("failed to serialize config");
         println!("{}", config);
@@ -190,8 +406,16 @@ mod test {
             server: ServerConfig {
                 host: LocalhostOrIpv4Addr::Ipv4Addr(Ipv4Addr::new(10, 12, 3, 1)),
                 port: 8080,
+                push_signature: None,
+                drain_timeout_seconds: 30,
+                plugin_shutdown_timeout_seconds: 10,
+                push_retry: Default::default(),
+                max_body_bytes: default_max_body_bytes(),
+                max_batch_body_bytes: default_max_batch_body_bytes(),
             },
             plugins: None,
+            pipeline: None,
+            pipelines: Default::default(),
         };
         let config = serde_json::to_string_pretty(&config).expect("failed to serialize config");
         println!("{}", config);
@@ -204,6 +428,12 @@ mod test {
             server: ServerConfig {
                 host: LocalhostOrIpv4Addr::Ipv4Addr(Ipv4Addr::new(10, 12, 3, 1)),
                 port: 8080,
+                push_signature: None,
+                drain_timeout_seconds: 30,
+                plugin_shutdown_timeout_seconds: 10,
+                push_retry: Default::default(),
+                max_body_bytes: default_max_body_bytes(),
+                max_batch_body_bytes: default_max_batch_body_bytes(),
             },
             plugins: Some(PluginsConfig {
                 filter_plugin: Some(vec![FilterPluginFromFileConfig {
@@ -213,11 +443,11 @@ mod test {
                     },
                     config: FilterPluginConfig {
                         webhook_url: url::Url::parse("http://localhost:8080").unwrap(),
-                        group_labels: vec![],
-                        common_labels: vec![],
-                        common_annotations: vec![],
-                        alerts_labels: vec![],
-                        alerts_annotations: vec![],
+                        http_client: Default::default(),
+                        actions: Default::default(),
+                    },
+                    options: PluginOptions {
+                        delivery: Delivery::AsIs,
                     },
                 }]),
                 file_plugin: Some(vec![
@@ -229,9 +459,12 @@ mod test {
                         config: FilePluginConfig {
                             dir_path: "/tmp".into(),
                             extension: "json".into(),
-                            formatter_config: FormatterConfig {
-                                format_type: FormatType::Json,
-                            },
+                            path_template: "{date}/{hash}.{extension}".into(),
+                            formatter_config: FormatterConfig::new(FormatType::Json),
+                            write_mode: Default::default(),
+                        },
+                        options: PluginOptions {
+                            delivery: Delivery::PerAlert,
                         },
                     },
                     FilePluginFromFileConfig {
@@ -242,9 +475,12 @@ mod test {
                         config: FilePluginConfig {
                             dir_path: "/tmp".into(),
                             extension: "yaml".into(),
-                            formatter_config: FormatterConfig {
-                                format_type: FormatType::Yaml,
-                            },
+                            path_template: "{group_key}.{extension}".into(),
+                            formatter_config: FormatterConfig::new(FormatType::Yaml),
+                            write_mode: Default::default(),
+                        },
+                        options: PluginOptions {
+                            delivery: Delivery::RegroupBy(vec!["team".to_string()]),
                         },
                     },
                 ]),
@@ -254,9 +490,10 @@ mod test {
                         group: "print".to_string(),
                     },
                     config: PrintPluginConfig {
-                        formatter_config: FormatterConfig {
-                            format_type: FormatType::Json,
-                        },
+                        formatter_config: FormatterConfig::new(FormatType::Json),
+                    },
+                    options: PluginOptions {
+                        delivery: Delivery::AsIs,
                     },
                 }]),
                 mongo_plugin: None,
@@ -264,7 +501,13 @@ mod test {
                 postgres_sea_plugin: None,
                 postgres_x_plugin: None,
                 sqlite_plugin: None,
+                webhook_plugin: None,
+                wasm_plugin: None,
+                script_plugin: None,
+                exec_plugin: None,
             }),
+            pipeline: None,
+            pipelines: Default::default(),
         };
         let config = serde_yaml::to_string(&config).expect("failed to serialize config");
         println!("{}", config);
@@ -306,4 +549,89 @@ mod test {
         );
         assert_eq!(config.server.port, 8080);
     }
+
+    #[test]
+    fn deserialize_delivery() {
+        let config = r#"
+        server:
+          host: localhost
+          port: 8080
+        plugins:
+          print_plugin:
+            - meta: { name: as_is, group: print }
+              config: { formatter_config: { format_type: { type: Json } } }
+            - meta: { name: per_alert, group: print }
+              config: { formatter_config: { format_type: { type: Json } } }
+              delivery: per_alert
+            - meta: { name: regrouped, group: print }
+              config: { formatter_config: { format_type: { type: Json } } }
+              delivery:
+                regroup_by: [team, service]
+        "#;
+
+        let config: Config = serde_yaml::from_str(config).expect("failed to deserialize config");
+        let deliveries = config
+            .plugins
+            .unwrap()
+            .print_plugin
+            .unwrap()
+            .into_iter()
+            .map(|plugin| plugin.options.delivery)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            deliveries,
+            vec![
+                Delivery::AsIs,
+                Delivery::PerAlert,
+                Delivery::RegroupBy(vec!["team".to_string(), "service".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_pipelines() {
+        let config = r#"
+        server:
+          host: localhost
+          port: 8080
+        pipelines:
+          team_a:
+            push_signature: { secret: team_a }
+            plugins:
+              print_plugin:
+                - meta: { name: print, group: print }
+                  config: { formatter_config: { format_type: { type: Json } } }
+            pipeline:
+              stages:
+                - type: enrich
+                  common_labels: { team: a }
+          team_b: {}
+        "#;
+
+        let config: Config = serde_yaml::from_str(config).expect("failed to deserialize config");
+        assert_eq!(
+            config.pipelines.keys().collect::<Vec<_>>(),
+            vec!["team_a", "team_b"]
+        );
+
+        let team_a = &config.pipelines["team_a"];
+        assert!(team_a.push_signature.is_some());
+        assert_eq!(
+            team_a
+                .plugins
+                .as_ref()
+                .unwrap()
+                .print_plugin
+                .as_ref()
+                .unwrap()[0]
+                .meta
+                .name,
+            "print"
+        );
+        assert_eq!(team_a.pipeline.as_ref().unwrap().stages.len(), 1);
+
+        let team_b = &config.pipelines["team_b"];
+        assert!(team_b.push_signature.is_none() && team_b.plugins.is_none());
+    }
 }
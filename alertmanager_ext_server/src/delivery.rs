@@ -0,0 +1,121 @@
+use crate::traits::PushAndPlugin;
+use async_trait::async_trait;
+use models::AlertmanagerPush;
+use plugins_definitions::{HealthError, Plugin, PluginMeta};
+use push_definitions::{
+    AlertOutcome, AlertResult, Delivery, InitializeError, Push, PushError, PushOutcome,
+    ShutdownError,
+};
+use std::sync::Arc;
+
+/// Splits pushes according to the delivery before handing them to the plugin
+pub(crate) struct DeliveryPlugin<P> {
+    plugin: P,
+    delivery: Delivery,
+}
+
+/// Wraps the plugin if its delivery is not `as_is`
+pub(crate) fn with_delivery<P: PushAndPlugin>(
+    plugin: P,
+    delivery: Delivery,
+) -> Arc<dyn PushAndPlugin> {
+    match delivery {
+        Delivery::AsIs => Arc::new(plugin),
+        delivery => Arc::new(DeliveryPlugin { plugin, delivery }),
+    }
+}
+
+impl<P: PushAndPlugin> DeliveryPlugin<P> {
+    /// Adds the results of a sub-push
+    fn collect(
+        &self,
+        push: &AlertmanagerPush,
+        result: Result<PushOutcome, PushError>,
+        results: &mut Vec<AlertResult>,
+    ) {
+        match result {
+            Ok(outcome) => results.extend(outcome.for_alerts(push)),
+            Err(error) => {
+                tracing::error!(name = self.name(), %error, group_key = push.group_key, "Failed to deliver push.");
+                results.extend(AlertResult::all(push, AlertOutcome::from(&error)));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<P: PushAndPlugin> Plugin for DeliveryPlugin<P> {
+    fn meta(&self) -> PluginMeta<'_> {
+        self.plugin.meta()
+    }
+
+    async fn health(&self) -> Result<(), HealthError> {
+        self.plugin.health().await
+    }
+}
+
+#[async_trait]
+impl<P: PushAndPlugin> Push for DeliveryPlugin<P> {
+    async fn initialize(&mut self) -> Result<(), InitializeError> {
+        self.plugin.initialize().await
+    }
+
+    /// Pushes the sub-pushes in order and reports the outcome per alert
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
+        let mut results = vec![];
+
+        for push in self.delivery.split(alertmanager_push).iter() {
+            let result = self.plugin.push_alert(push).await;
+            self.collect(push, result, &mut results);
+        }
+
+        Ok(PushOutcome::from_alerts(results))
+    }
+
+    /// Splits every push and hands all sub-pushes to the plugin as one batch
+    async fn push_alerts(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Vec<Result<PushOutcome, PushError>> {
+        let split = alertmanager_pushes
+            .iter()
+            .map(|alertmanager_push| self.delivery.split(alertmanager_push))
+            .collect::<Vec<_>>();
+
+        let sub_pushes = split.iter().flatten().cloned().collect::<Vec<_>>();
+        let mut sub_results = self.plugin.push_alerts(&sub_pushes).await.into_iter();
+
+        split
+            .iter()
+            .map(|pushes| {
+                let mut results = vec![];
+
+                for push in pushes.iter() {
+                    let result = sub_results.next().unwrap_or_else(|| {
+                        Err(PushError::from(
+                            Box::<dyn std::error::Error + Send + Sync>::from(
+                                "Plugin returned no result",
+                            ),
+                        ))
+                    });
+                    self.collect(push, result, &mut results);
+                }
+
+                Ok(PushOutcome::from_alerts(results))
+            })
+            .collect()
+    }
+
+    fn retries_itself(&self) -> bool {
+        self.plugin.retries_itself()
+    }
+
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        self.plugin.shutdown().await
+    }
+}
+
+impl<P: PushAndPlugin> PushAndPlugin for DeliveryPlugin<P> {}
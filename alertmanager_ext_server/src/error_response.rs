@@ -1,5 +1,6 @@
 use crate::traits::HasStatusCode;
 use axum::{http::StatusCode, response::IntoResponse, Json};
+use plugins_filter::error::FilterParseError;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -17,6 +18,12 @@ impl ErrorResponse {
             error_type: ErrorResponseType::NotFound,
         }
     }
+
+    pub fn pipeline_not_found(name: String) -> Self {
+        Self {
+            error_type: ErrorResponseType::PipelineNotFound(PipelineNotFound { name }),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
@@ -29,8 +36,16 @@ pub enum ErrorResponseType {
     QueryInvalid(QueryInvalid),
     /// Filter is invalid
     PluginFilterInvalid(PluginFilterInvalid),
+    /// Alert filter is invalid
+    AlertFilterInvalid(AlertFilterInvalid),
     /// Path is invalid
     PathInvalid(PathInvalid),
+    /// Request signature is missing or invalid
+    SignatureInvalid(SignatureInvalid),
+    /// Request body is larger than the configured limit
+    PayloadTooLarge(PayloadTooLarge),
+    /// No pipeline with the name is configured
+    PipelineNotFound(PipelineNotFound),
     /// Internal server error
     InternalServerError(InternalServerError),
     /// Not found
@@ -50,6 +65,39 @@ pub struct QueryInvalid {
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct PluginFilterInvalid {
     pub(crate) reason: String,
+    /// Column (1-based) of the offending token
+    pub(crate) column: usize,
+    /// The filter with a caret underlining the offending token
+    pub(crate) snippet: String,
+}
+
+impl From<FilterParseError> for PluginFilterInvalid {
+    fn from(error: FilterParseError) -> Self {
+        Self {
+            reason: error.message,
+            column: error.column,
+            snippet: error.snippet,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct AlertFilterInvalid {
+    pub(crate) reason: String,
+    /// Column (1-based) of the offending token
+    pub(crate) column: usize,
+    /// The filter with a caret underlining the offending token
+    pub(crate) snippet: String,
+}
+
+impl From<FilterParseError> for AlertFilterInvalid {
+    fn from(error: FilterParseError) -> Self {
+        Self {
+            reason: error.message,
+            column: error.column,
+            snippet: error.snippet,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
@@ -67,6 +115,21 @@ pub struct PathInvalid {
     pub(crate) reason: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SignatureInvalid {
+    pub(crate) reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct PayloadTooLarge {
+    pub(crate) max_bytes: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct PipelineNotFound {
+    pub(crate) name: String,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, ToSchema)]
 #[non_exhaustive]
 pub struct InternalServerError {
@@ -91,7 +154,11 @@ impl HasStatusCode for ErrorResponseType {
             ErrorResponseType::PayloadInvalid(payload_invalid) => payload_invalid.status_code,
             ErrorResponseType::QueryInvalid(query_invalid) => query_invalid.status_code,
             ErrorResponseType::PluginFilterInvalid(..) => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorResponseType::AlertFilterInvalid(..) => StatusCode::UNPROCESSABLE_ENTITY,
             ErrorResponseType::PathInvalid(path_invalid) => path_invalid.status_code,
+            ErrorResponseType::SignatureInvalid(..) => StatusCode::UNAUTHORIZED,
+            ErrorResponseType::PayloadTooLarge(..) => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorResponseType::PipelineNotFound(..) => StatusCode::NOT_FOUND,
             ErrorResponseType::InternalServerError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             ErrorResponseType::NotFound => StatusCode::NOT_FOUND,
             ErrorResponseType::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
@@ -1,13 +1,15 @@
 use crate::{
-    error_response::{ErrorResponse, ErrorResponseType, PluginFilterInvalid, QueryInvalid},
-    routes::models::PluginFilterQuery,
+    error_response::{
+        AlertFilterInvalid, ErrorResponse, ErrorResponseType, PluginFilterInvalid, QueryInvalid,
+    },
+    routes::models::{AlertFilterQuery, PluginFilterQuery},
 };
 use async_trait::async_trait;
 use axum::{
     extract::{FromRequestParts, Query as AxumQuery},
     http::request::Parts,
 };
-use plugins_filter::ast::Expr;
+use plugins_filter::ast::{AlertExpr, Expr};
 use schemars::{schema_for, JsonSchema};
 use serde::de::DeserializeOwned;
 use std::fmt::Debug;
@@ -68,16 +70,38 @@ where
         match query.0.filter {
             None => Ok(ApiPluginFilterQuery(None)),
             Some(filter) => {
-                let exp = plugins_filter::filter::ExprParser::new()
-                    .parse(&filter)
-                    .map_err(|error| {
-                        ErrorResponseType::PluginFilterInvalid(PluginFilterInvalid {
-                            reason: error.to_string(),
-                        })
-                    })?;
+                let exp = plugins_filter::parse_expr(&filter).map_err(|error| {
+                    ErrorResponseType::PluginFilterInvalid(PluginFilterInvalid::from(error))
+                })?;
 
                 Ok(ApiPluginFilterQuery(Some(exp)))
             }
         }
     }
 }
+
+pub struct ApiAlertFilterQuery(pub Option<Box<AlertExpr>>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ApiAlertFilterQuery
+where
+    S: Send + Sync,
+{
+    type Rejection = ErrorResponse;
+
+    #[tracing::instrument(name = "alert_filter_query_extractor", skip_all)]
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = ApiQuery::<AlertFilterQuery>::from_request_parts(parts, _state).await?;
+
+        match query.0.alert_filter {
+            None => Ok(ApiAlertFilterQuery(None)),
+            Some(alert_filter) => {
+                let exp = plugins_filter::parse_alert_expr(&alert_filter).map_err(|error| {
+                    ErrorResponseType::AlertFilterInvalid(AlertFilterInvalid::from(error))
+                })?;
+
+                Ok(ApiAlertFilterQuery(Some(exp)))
+            }
+        }
+    }
+}
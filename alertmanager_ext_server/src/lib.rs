@@ -1,11 +1,14 @@
 pub mod cli;
 pub mod config;
+pub(crate) mod delivery;
 pub(crate) mod error_response;
 pub(crate) mod extractors;
 pub(crate) mod middlewares;
 pub(crate) mod openapi;
+pub(crate) mod pipeline;
 pub(crate) mod prometheus_client;
 pub(crate) mod routes;
 pub mod server;
+pub(crate) mod shutdown;
 pub(crate) mod state;
 pub(crate) mod traits;
@@ -1,2 +1,3 @@
 pub mod method_not_allowed;
 pub mod trace_response_body;
+pub mod verify_signature;
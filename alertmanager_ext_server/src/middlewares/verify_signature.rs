@@ -0,0 +1,103 @@
+use crate::{
+    error_response::{ErrorResponse, ErrorResponseType, PayloadTooLarge, SignatureInvalid},
+    extractors::path::ApiPath,
+    state::ApiState,
+};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use http_body_util::LengthLimitError;
+use http_client::signature::SignatureConfig;
+use std::sync::Arc;
+
+/// State for [`verify_signature`]
+#[derive(Clone)]
+pub struct SignatureState {
+    pub config: Arc<SignatureConfig>,
+    /// Largest body read to verify the signature
+    pub max_body_bytes: usize,
+}
+
+/// State for [`verify_pipeline_signature`]
+#[derive(Clone)]
+pub struct PipelineSignatureState {
+    pub state: ApiState,
+    /// Largest body read to verify the signature
+    pub max_body_bytes: usize,
+}
+
+/// Middleware to verify the HMAC signature of the request body
+///
+/// Bodies larger than `max_body_bytes` are rejected with 413 without being read further.
+pub async fn verify_signature(
+    State(SignatureState {
+        config,
+        max_body_bytes,
+    }): State<SignatureState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ErrorResponse> {
+    let (parts, body) = req.into_parts();
+    let bytes = match axum::body::to_bytes(body, max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            let source = std::error::Error::source(&error);
+            if source.is_some_and(|source| source.is::<LengthLimitError>()) {
+                tracing::warn!(max_body_bytes, "Rejecting request with a body too large.");
+                return Err(ErrorResponseType::PayloadTooLarge(PayloadTooLarge {
+                    max_bytes: max_body_bytes,
+                })
+                .into());
+            }
+            return Err(error.into());
+        }
+    };
+
+    let header_value = parts
+        .headers
+        .get(config.header.as_str())
+        .and_then(|value| value.to_str().ok());
+
+    if let Err(error) = config.verify(header_value, &bytes) {
+        tracing::warn!(%error, "Rejecting request with invalid signature.");
+        return Err(ErrorResponseType::SignatureInvalid(SignatureInvalid {
+            reason: error.to_string(),
+        })
+        .into());
+    }
+
+    let req = Request::from_parts(parts, Body::from(bytes));
+
+    Ok(next.run(req).await)
+}
+
+/// Middleware to verify the HMAC signature of pushes to a named pipeline
+///
+/// Pipelines without a signature and unknown pipelines are passed on. The route rejects the latter.
+pub async fn verify_pipeline_signature(
+    State(PipelineSignatureState {
+        state,
+        max_body_bytes,
+    }): State<PipelineSignatureState>,
+    ApiPath(pipeline): ApiPath<String>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ErrorResponse> {
+    let push_signature = state
+        .pipeline(&pipeline)
+        .and_then(|pipeline| pipeline.push_signature.clone());
+
+    match push_signature {
+        Some(config) => {
+            let signature_state = SignatureState {
+                config,
+                max_body_bytes,
+            };
+            verify_signature(State(signature_state), req, next).await
+        }
+        None => Ok(next.run(req).await),
+    }
+}
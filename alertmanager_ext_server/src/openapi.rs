@@ -6,7 +6,11 @@ use utoipa::OpenApi;
         crate::routes::metrics::metrics,
         crate::routes::health::health,
         crate::routes::health::plugin_health,
+        crate::routes::health::pipeline_plugin_health,
         crate::routes::push::push,
+        crate::routes::push::push_batch,
+        crate::routes::push::push_to_pipeline,
+        crate::routes::push::push_batch_to_pipeline,
     ),
     components(schemas(
         models::AlertmanagerPush,
@@ -15,7 +19,12 @@ use utoipa::OpenApi;
         crate::routes::models::PluginResponseMeta,
         crate::routes::push::PushStatus,
         crate::routes::push::PluginPushStatus,
+        crate::routes::push::PushErrorClass,
+        crate::routes::push::AlertPushStatus,
+        crate::routes::push::AlertPushResponse,
+        crate::routes::push::BatchPushResponse,
         crate::routes::push::PluginPushResponse,
+        crate::pipeline::StageResponse,
         crate::routes::push::PushResponse,
         crate::routes::health::ServerHealthResponse,
         crate::routes::health::HealthStatus,
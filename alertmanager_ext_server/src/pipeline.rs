@@ -0,0 +1,556 @@
+use crate::config::{
+    DedupStageConfig, EnrichStageConfig, OnStageError, PipelineConfig, StageConfig,
+};
+use filter_plugin::FilterActions;
+use models::AlertmanagerPush;
+use plugins_definitions::PluginMeta;
+use plugins_filter::ast::Expr;
+use push_definitions::ErrorClass;
+use schemars::JsonSchema;
+use script_plugin::{NewScriptError, ScriptError, ScriptOutcome, ScriptTransform};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use thiserror::Error as ThisError;
+use utoipa::ToSchema;
+use wasm_plugin::{NewWasmPluginError, TransformError, WasmTransform};
+
+#[derive(ThisError, Debug)]
+pub enum NewPipelineError {
+    #[error("Failed to create Wasm stage: {0}")]
+    Wasm(
+        #[source]
+        #[from]
+        NewWasmPluginError,
+    ),
+    #[error("Failed to create Script stage: {0}")]
+    Script(
+        #[source]
+        #[from]
+        NewScriptError,
+    ),
+}
+
+#[derive(ThisError, Debug)]
+enum StageError {
+    #[error("Wasm stage failed: {0}")]
+    Wasm(
+        #[source]
+        #[from]
+        TransformError,
+    ),
+    #[error("Script stage failed: {0}")]
+    Script(
+        #[source]
+        #[from]
+        ScriptError,
+    ),
+}
+
+impl StageError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            StageError::Wasm(error) => error.class(),
+            StageError::Script(error) => error.class(),
+        }
+    }
+}
+
+/// Transforms applied to a push before it is sent to the plugins
+///
+/// Runs in-process, so no loopback through `/push` and no loop detection labels are needed.
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+    targets: Option<Box<Expr>>,
+}
+
+enum Stage {
+    Filter(FilterActions),
+    Enrich(EnrichStageConfig),
+    Dedup(DedupStage),
+    Wasm(WasmTransform, OnStageError),
+    Script(ScriptTransform, OnStageError),
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema, ToSchema)]
+/// Response for a pipeline stage
+pub struct StageResponse {
+    /// Type of the stage
+    pub stage_type: String,
+    /// Number of alerts before the stage
+    pub alerts_in: usize,
+    /// Number of alerts after the stage
+    pub alerts_out: usize,
+    /// Error of the stage, if it failed
+    pub error: Option<String>,
+}
+
+/// Result of running the stages on a push
+pub struct PipelineRun {
+    /// Responses for each stage that ran, in order
+    pub stage_responses: Vec<StageResponse>,
+    /// Names of the plugins a script routed the push to. All targets if not set
+    pub routes: Option<Vec<String>>,
+    /// Class of the error of a stage that stopped the pipeline.
+    /// The push must not be delivered if set
+    pub failure: Option<ErrorClass>,
+    /// Alerts recorded by the dedup stages, with the index of the stage
+    seen: Vec<(usize, SeenAlerts)>,
+}
+
+impl PipelineRun {
+    /// Whether a script routed the push to the plugin
+    pub fn is_routed(&self, meta: &PluginMeta) -> bool {
+        self.routes
+            .as_ref()
+            .map(|routes| routes.iter().any(|route| route == meta.name))
+            .unwrap_or(true)
+    }
+}
+
+/// What a stage did besides changing the push
+enum StageEffect {
+    None,
+    /// A script routed the push to these plugins
+    Routes(Vec<String>),
+    /// A dedup stage recorded these alerts
+    Seen(SeenAlerts),
+}
+
+struct DedupStage {
+    window: Duration,
+    /// Last time an alert was seen, keyed by plugin filter and by fingerprint and status
+    seen: Mutex<HashMap<(String, String), Instant>>,
+}
+
+/// Keys a dedup stage recorded for a push
+struct SeenAlerts {
+    keys: Vec<(String, String)>,
+    at: Instant,
+}
+
+impl Pipeline {
+    pub async fn new(config: PipelineConfig) -> Result<Self, NewPipelineError> {
+        let mut stages = Vec::with_capacity(config.stages.len());
+
+        for stage in config.stages {
+            stages.push(match stage {
+                StageConfig::Filter(actions) => Stage::Filter(actions),
+                StageConfig::Enrich(config) => Stage::Enrich(config),
+                StageConfig::Dedup(config) => Stage::Dedup(DedupStage::new(config)),
+                StageConfig::Wasm(config) => {
+                    Stage::Wasm(WasmTransform::new(config.config)?, config.on_error)
+                }
+                StageConfig::Script(config) => {
+                    Stage::Script(ScriptTransform::new(config.config).await?, config.on_error)
+                }
+            });
+        }
+
+        Ok(Self {
+            stages,
+            targets: config.targets,
+        })
+    }
+
+    /// Whether the plugin is a target of the pipeline
+    pub fn is_target(&self, meta: &PluginMeta) -> bool {
+        self.targets
+            .as_ref()
+            .map(|targets| targets.is_match(meta))
+            .unwrap_or(true)
+    }
+
+    /// Runs all stages on the push.
+    ///
+    /// Stops early if a stage removes all alerts or fails with `on_error: fail`.
+    /// If several scripts route the push, the last one wins.
+    /// Dedup stages only drop alerts seen with the same plugin filter `exp`.
+    pub async fn run(&self, push: &mut AlertmanagerPush, exp: Option<&Expr>) -> PipelineRun {
+        let mut stage_responses = Vec::with_capacity(self.stages.len());
+        let mut routes = None;
+        let mut failure = None;
+        let mut seen = vec![];
+        let scope = exp.map(ToString::to_string).unwrap_or_default();
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            let alerts_in = push.alerts.len();
+            let error = match stage.run(push, &scope).await {
+                Ok(StageEffect::None) => None,
+                Ok(StageEffect::Routes(stage_routes)) => {
+                    routes = Some(stage_routes);
+                    None
+                }
+                Ok(StageEffect::Seen(seen_alerts)) => {
+                    seen.push((index, seen_alerts));
+                    None
+                }
+                Err(error) => Some(error),
+            };
+            let alerts_out = push.alerts.len();
+
+            tracing::trace!(stage = stage.type_(), alerts_in, alerts_out, "Stage done.");
+
+            stage_responses.push(StageResponse {
+                stage_type: stage.type_().to_string(),
+                alerts_in,
+                alerts_out,
+                error: error.as_ref().map(ToString::to_string),
+            });
+
+            if let Some(error) = error {
+                if stage.on_error() == OnStageError::Fail {
+                    tracing::error!(stage = stage.type_(), %error, "Stage failed. Failing the push.");
+                    failure = Some(error.class());
+                    break;
+                }
+
+                tracing::error!(stage = stage.type_(), %error, "Stage failed. Keeping the push unchanged.");
+            }
+
+            if push.alerts.is_empty() {
+                break;
+            }
+        }
+
+        PipelineRun {
+            stage_responses,
+            routes,
+            failure,
+            seen,
+        }
+    }
+
+    /// Forgets the alerts the dedup stages recorded during the run.
+    ///
+    /// Called if the push was not delivered, so that a retry is not dropped as a duplicate.
+    pub fn forget(&self, run: &PipelineRun) {
+        for (index, seen_alerts) in run.seen.iter() {
+            if let Some(Stage::Dedup(dedup)) = self.stages.get(*index) {
+                dedup.forget(seen_alerts);
+            }
+        }
+    }
+}
+
+impl Stage {
+    fn type_(&self) -> &'static str {
+        match self {
+            Stage::Filter(_) => "filter",
+            Stage::Enrich(_) => "enrich",
+            Stage::Dedup(_) => "dedup",
+            Stage::Wasm(..) => "wasm",
+            Stage::Script(..) => "script",
+        }
+    }
+
+    fn on_error(&self) -> OnStageError {
+        match self {
+            Stage::Wasm(_, on_error) | Stage::Script(_, on_error) => *on_error,
+            // Can't fail
+            Stage::Filter(_) | Stage::Enrich(_) | Stage::Dedup(_) => OnStageError::Continue,
+        }
+    }
+
+    /// The push is unchanged if the stage fails
+    async fn run(
+        &self,
+        push: &mut AlertmanagerPush,
+        scope: &str,
+    ) -> Result<StageEffect, StageError> {
+        match self {
+            Stage::Filter(actions) => *push = actions.apply(push),
+            Stage::Enrich(config) => Self::enrich(config, push),
+            Stage::Dedup(dedup) => return Ok(StageEffect::Seen(dedup.run(push, scope))),
+            Stage::Wasm(transform, _) => *push = transform.transform(push).await?,
+            Stage::Script(transform, _) => match transform.transform(push).await? {
+                ScriptOutcome::Keep(transformed) => *push = transformed,
+                ScriptOutcome::Drop => push.retain_alerts(|_| false),
+                ScriptOutcome::Route {
+                    push: transformed,
+                    targets,
+                } => {
+                    *push = transformed;
+                    return Ok(StageEffect::Routes(targets));
+                }
+            },
+        }
+
+        Ok(StageEffect::None)
+    }
+
+    fn enrich(config: &EnrichStageConfig, push: &mut AlertmanagerPush) {
+        let extend = |map: &mut BTreeMap<String, String>, entries: &BTreeMap<String, String>| {
+            for (name, value) in entries {
+                if config.overwrite || !map.contains_key(name) {
+                    map.insert(name.clone(), value.clone());
+                }
+            }
+        };
+
+        extend(&mut push.common_labels, &config.common_labels);
+        extend(&mut push.common_annotations, &config.common_annotations);
+
+        for alert in push.alerts.iter_mut() {
+            extend(&mut alert.labels, &config.alerts_labels);
+            extend(&mut alert.annotations, &config.alerts_annotations);
+        }
+    }
+}
+
+impl DedupStage {
+    fn new(config: DedupStageConfig) -> Self {
+        Self {
+            window: Duration::from_secs(config.window_seconds),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Drops alerts seen with the same status and `scope` within the window
+    /// and records the others.
+    fn run(&self, push: &mut AlertmanagerPush, scope: &str) -> SeenAlerts {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(|error| error.into_inner());
+
+        seen.retain(|_, last_seen| now.duration_since(*last_seen) < self.window);
+
+        let mut keys = vec![];
+        push.retain_alerts(|alert| {
+            let key = (
+                scope.to_string(),
+                format!("{}:{}", alert.fingerprint, alert.status.to_string()),
+            );
+            if seen.contains_key(&key) {
+                return false;
+            }
+
+            seen.insert(key.clone(), now);
+            keys.push(key);
+            true
+        });
+
+        SeenAlerts { keys, at: now }
+    }
+
+    /// Removes the keys, unless they were recorded again since
+    fn forget(&self, seen_alerts: &SeenAlerts) {
+        let mut seen = self.seen.lock().unwrap_or_else(|error| error.into_inner());
+
+        for key in seen_alerts.keys.iter() {
+            if seen.get(key) == Some(&seen_alerts.at) {
+                seen.remove(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use models::{Alert, Status};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn run_stages() {
+        let config: PipelineConfig = serde_yaml::from_str(
+            r#"
+            stages:
+              - type: filter
+                alerts:
+                  - action: DropResolved
+              - type: enrich
+                common_labels:
+                  env: prod
+                alerts_labels:
+                  team: ops
+              - type: dedup
+                window_seconds: 60
+            targets: group is storage
+            "#,
+        )
+        .unwrap();
+
+        let pipeline = Pipeline::new(config).await.unwrap();
+
+        let alert = |fingerprint: &str, status: Status| Alert {
+            fingerprint: fingerprint.to_string(),
+            status,
+            labels: [("team".to_string(), "db".to_string())].into(),
+            ..Default::default()
+        };
+
+        let push = AlertmanagerPush {
+            status: Status::Firing,
+            alerts: vec![
+                alert("a", Status::Firing),
+                alert("b", Status::Firing),
+                alert("c", Status::Resolved),
+            ],
+            ..Default::default()
+        };
+
+        let mut first = push.clone();
+        let stage_responses = pipeline.run(&mut first, None).await.stage_responses;
+
+        let counts: Vec<_> = stage_responses
+            .iter()
+            .map(|response| {
+                (
+                    response.stage_type.as_str(),
+                    response.alerts_in,
+                    response.alerts_out,
+                )
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            [("filter", 3, 2), ("enrich", 2, 2), ("dedup", 2, 2)]
+        );
+        assert_eq!(first.common_labels.get("env"), Some(&"prod".to_string()));
+        assert_eq!(first.alerts[0].labels.get("team"), Some(&"db".to_string()));
+
+        let mut second = push.clone();
+        let stage_responses = pipeline.run(&mut second, None).await.stage_responses;
+
+        assert!(second.alerts.is_empty());
+        assert_eq!(second.truncated_alerts, 3);
+        assert_eq!(stage_responses.last().unwrap().alerts_out, 0);
+
+        assert!(pipeline.is_target(&PluginMeta {
+            name: "postgres",
+            type_: "postgres",
+            group: "storage",
+        }));
+        assert!(!pipeline.is_target(&PluginMeta {
+            name: "print",
+            type_: "print",
+            group: "debug",
+        }));
+    }
+
+    #[tokio::test]
+    async fn dedup_per_filter_and_forget() {
+        let config: PipelineConfig =
+            serde_yaml::from_str("stages: [{ type: dedup, window_seconds: 60 }]").unwrap();
+
+        let pipeline = Pipeline::new(config).await.unwrap();
+
+        let push = AlertmanagerPush {
+            status: Status::Firing,
+            alerts: vec![Alert {
+                fingerprint: "a".to_string(),
+                status: Status::Firing,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let storage: Expr = "group is storage".parse().unwrap();
+
+        let mut first = push.clone();
+        let run = pipeline.run(&mut first, None).await;
+        assert_eq!(first.alerts.len(), 1);
+
+        // Delivery failed, so the retry must not be dropped
+        pipeline.forget(&run);
+
+        let mut retry = push.clone();
+        pipeline.run(&mut retry, None).await;
+        assert_eq!(retry.alerts.len(), 1);
+
+        let mut duplicate = push.clone();
+        pipeline.run(&mut duplicate, None).await;
+        assert!(duplicate.alerts.is_empty());
+
+        let mut other_filter = push.clone();
+        pipeline.run(&mut other_filter, Some(&storage)).await;
+        assert_eq!(other_filter.alerts.len(), 1);
+    }
+
+    fn script_file(source: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(source.as_bytes()).unwrap();
+        file
+    }
+
+    #[tokio::test]
+    async fn route_with_script() {
+        let file = script_file(
+            r#"
+            if push.commonLabels.team == "db" {
+                return ["postgres"];
+            }
+            "#,
+        );
+
+        let config: PipelineConfig = serde_yaml::from_str(&format!(
+            "stages: [{{ type: script, script_path: {} }}]",
+            file.path().display()
+        ))
+        .unwrap();
+
+        let pipeline = Pipeline::new(config).await.unwrap();
+
+        let mut push = AlertmanagerPush {
+            common_labels: [("team".to_string(), "db".to_string())].into(),
+            alerts: vec![Alert::default()],
+            ..Default::default()
+        };
+        let run = pipeline.run(&mut push, None).await;
+
+        assert_eq!(run.routes, Some(vec!["postgres".to_string()]));
+        assert!(run.is_routed(&PluginMeta {
+            name: "postgres",
+            type_: "postgres",
+            group: "storage",
+        }));
+        assert!(!run.is_routed(&PluginMeta {
+            name: "print",
+            type_: "print",
+            group: "debug",
+        }));
+    }
+
+    #[tokio::test]
+    async fn failed_script_fails_the_push() {
+        let file = script_file(r#"throw "broken";"#);
+
+        let push = AlertmanagerPush {
+            alerts: vec![Alert::default()],
+            ..Default::default()
+        };
+
+        let config: PipelineConfig = serde_yaml::from_str(&format!(
+            "stages: [{{ type: script, script_path: {} }}, {{ type: enrich, common_labels: {{ env: prod }} }}]",
+            file.path().display()
+        ))
+        .unwrap();
+        let pipeline = Pipeline::new(config).await.unwrap();
+
+        let mut failed = push.clone();
+        let run = pipeline.run(&mut failed, None).await;
+
+        assert_eq!(run.failure, Some(ErrorClass::Permanent));
+        assert_eq!(run.stage_responses.len(), 1);
+        assert!(run.stage_responses[0].error.is_some());
+
+        let config: PipelineConfig = serde_yaml::from_str(&format!(
+            "stages: [{{ type: script, script_path: {}, on_error: continue }}, {{ type: enrich, common_labels: {{ env: prod }} }}]",
+            file.path().display()
+        ))
+        .unwrap();
+        let pipeline = Pipeline::new(config).await.unwrap();
+
+        let mut kept = push.clone();
+        let run = pipeline.run(&mut kept, None).await;
+
+        assert_eq!(run.failure, None);
+        assert_eq!(run.stage_responses.len(), 2);
+        assert_eq!(kept.alerts, push.alerts);
+        assert_eq!(kept.common_labels.get("env"), Some(&"prod".to_string()));
+    }
+}
@@ -1,24 +1,71 @@
+use crate::shutdown::ShutdownOutcome;
 use plugins_definitions::PluginMeta;
 use prometheus_client::{
     encoding::{text, EncodeLabelSet},
     metrics::{counter::Counter, family::Family},
     registry::Registry,
 };
+use push_definitions::ErrorClass;
 use std::fmt::Error as FmtError;
 
+/// `pipeline` is the last label of all sets, so the labels from before pipelines keep their order
 #[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
 pub struct PushLabel {
     pub plugin_name: String,
     pub plugin_type: String,
     pub plugin_group: String,
+    pub pipeline: String,
 }
 
-impl<'a> From<PluginMeta<'a>> for PushLabel {
-    fn from(plugin_meta: PluginMeta) -> Self {
+impl PushLabel {
+    pub(crate) fn new(pipeline: &str, plugin_meta: PluginMeta) -> Self {
         Self {
             plugin_name: plugin_meta.name.to_owned(),
             plugin_type: plugin_meta.type_.to_owned(),
             plugin_group: plugin_meta.group.to_owned(),
+            pipeline: pipeline.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
+pub struct FailedPushLabel {
+    pub plugin_name: String,
+    pub plugin_type: String,
+    pub plugin_group: String,
+    pub class: String,
+    pub pipeline: String,
+}
+
+impl FailedPushLabel {
+    pub(crate) fn new(push_label: &PushLabel, class: ErrorClass) -> Self {
+        Self {
+            plugin_name: push_label.plugin_name.clone(),
+            plugin_type: push_label.plugin_type.clone(),
+            plugin_group: push_label.plugin_group.clone(),
+            class: class.as_str().to_owned(),
+            pipeline: push_label.pipeline.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
+pub struct ShutdownLabel {
+    pub plugin_name: String,
+    pub plugin_type: String,
+    pub plugin_group: String,
+    pub outcome: String,
+    pub pipeline: String,
+}
+
+impl ShutdownLabel {
+    pub(crate) fn new(push_label: &PushLabel, outcome: ShutdownOutcome) -> Self {
+        Self {
+            plugin_name: push_label.plugin_name.clone(),
+            plugin_type: push_label.plugin_type.clone(),
+            plugin_group: push_label.plugin_group.clone(),
+            outcome: outcome.as_str().to_owned(),
+            pipeline: push_label.pipeline.clone(),
         }
     }
 }
@@ -26,7 +73,11 @@ impl<'a> From<PluginMeta<'a>> for PushLabel {
 pub struct PromtheusClient {
     registry: Registry,
     success_push_counter: Family<PushLabel, Counter<u64>>,
-    failed_push_counter: Family<PushLabel, Counter<u64>>,
+    skipped_push_counter: Family<PushLabel, Counter<u64>>,
+    partial_push_counter: Family<PushLabel, Counter<u64>>,
+    failed_push_counter: Family<FailedPushLabel, Counter<u64>>,
+    retry_counter: Family<PushLabel, Counter<u64>>,
+    shutdown_counter: Family<ShutdownLabel, Counter<u64>>,
 }
 
 impl PromtheusClient {
@@ -40,17 +91,49 @@ impl PromtheusClient {
             success_push_counter.clone(),
         );
 
-        let failed_push_counter = Family::<PushLabel, Counter<u64>>::default();
+        let skipped_push_counter = Family::<PushLabel, Counter<u64>>::default();
+        registry.register(
+            "push_skipped_total",
+            "Total number of pushes the plugins intentionally skipped",
+            skipped_push_counter.clone(),
+        );
+
+        let partial_push_counter = Family::<PushLabel, Counter<u64>>::default();
+        registry.register(
+            "push_partial_total",
+            "Total number of pushes with different outcomes per alert",
+            partial_push_counter.clone(),
+        );
+
+        let failed_push_counter = Family::<FailedPushLabel, Counter<u64>>::default();
         registry.register(
             "push_failed_total",
             "Total number of failed pushes",
             failed_push_counter.clone(),
         );
 
+        let retry_counter = Family::<PushLabel, Counter<u64>>::default();
+        registry.register(
+            "push_retries_total",
+            "Total number of retried pushes",
+            retry_counter.clone(),
+        );
+
+        let shutdown_counter = Family::<ShutdownLabel, Counter<u64>>::default();
+        registry.register(
+            "plugin_shutdown_total",
+            "Total number of plugin shutdowns by outcome",
+            shutdown_counter.clone(),
+        );
+
         Self {
             registry,
             success_push_counter,
+            skipped_push_counter,
+            partial_push_counter,
             failed_push_counter,
+            retry_counter,
+            shutdown_counter,
         }
     }
 
@@ -64,9 +147,25 @@ impl PromtheusClient {
         self.success_push_counter.get_or_create(label).inc();
     }
 
-    pub fn add_failed_push(&self, label: &PushLabel) {
+    pub fn add_skipped_push(&self, label: &PushLabel) {
+        self.skipped_push_counter.get_or_create(label).inc();
+    }
+
+    pub fn add_partial_push(&self, label: &PushLabel) {
+        self.partial_push_counter.get_or_create(label).inc();
+    }
+
+    pub fn add_failed_push(&self, label: &FailedPushLabel) {
         self.failed_push_counter.get_or_create(label).inc();
     }
+
+    pub fn add_retry(&self, label: &PushLabel) {
+        self.retry_counter.get_or_create(label).inc();
+    }
+
+    pub fn add_shutdown(&self, label: &ShutdownLabel) {
+        self.shutdown_counter.get_or_create(label).inc();
+    }
 }
 
 impl Default for PromtheusClient {
@@ -1,12 +1,14 @@
 use crate::{
-    extractors::query::ApiPluginFilterQuery,
+    error_response::ErrorResponse,
+    extractors::{path::ApiPath, query::ApiPluginFilterQuery},
     routes::models::{PluginFilterQuery, PluginResponseMeta},
 };
 use crate::{
-    state::ApiState,
+    state::{ApiState, PipelineState},
     traits::{HasStatusCode, PushAndPlugin},
 };
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use plugins_filter::ast::Expr;
 use schemars::JsonSchema;
 use serde::Serialize;
 use std::sync::Arc;
@@ -70,6 +72,8 @@ pub enum PluginHealthStatus {
 pub struct PlugingHealthResponse {
     /// Health status for the plugin
     pub status: PluginHealthStatus,
+    /// Name of the pipeline the plugin belongs to
+    pub pipeline: String,
     /// Meta information about the plugin
     pub plugin_meta: PluginResponseMeta,
 }
@@ -109,25 +113,78 @@ impl IntoResponse for PluginsHealthResponse {
 }
 
 /// Helper function
-async fn match_plugin_health(plugin: &Arc<dyn PushAndPlugin>) -> PlugingHealthResponse {
+async fn match_plugin_health(
+    pipeline: &str,
+    plugin: &Arc<dyn PushAndPlugin>,
+) -> PlugingHealthResponse {
     match plugin.health().await {
         Ok(_) => PlugingHealthResponse {
             status: PluginHealthStatus::Healthy,
+            pipeline: pipeline.to_string(),
             plugin_meta: plugin.meta().into(),
         },
         Err(error) => {
-            tracing::error!(name=plugin.name(), %error, "Plugin is unhealthy.");
+            tracing::error!(pipeline, name=plugin.name(), %error, "Plugin is unhealthy.");
             PlugingHealthResponse {
                 status: PluginHealthStatus::Unhealthy {
                     message: error.to_string(),
                 },
+                pipeline: pipeline.to_string(),
                 plugin_meta: plugin.meta().into(),
             }
         }
     }
 }
 
-/// Health check for plugins
+/// Helper function
+///
+/// Checks the plugins of the pipelines that match the filter.
+async fn pipelines_health(
+    pipelines: Vec<&PipelineState>,
+    exp: Option<&Expr>,
+) -> PluginsHealthResponse {
+    let mut plugin_health_responses = vec![];
+    let mut healthy_plugins_count: usize = 0;
+
+    let affected_plugins = pipelines
+        .into_iter()
+        .flat_map(|pipeline| {
+            pipeline
+                .plugins
+                .iter()
+                .map(move |plugin| (pipeline.name.as_str(), plugin))
+        })
+        .filter(|(_, plugin)| exp.is_none_or(|exp| exp.is_match(&plugin.meta())))
+        .collect::<Vec<_>>();
+
+    if affected_plugins.is_empty() {
+        return PluginsHealthResponse {
+            status: HealthStatus::NoPlugins,
+            plugin_health_responses: vec![],
+        };
+    }
+
+    for (pipeline, plugin) in affected_plugins.iter() {
+        let res = match_plugin_health(pipeline, plugin).await;
+        if let PluginHealthStatus::Healthy = res.status {
+            healthy_plugins_count += 1;
+        }
+        plugin_health_responses.push(res);
+    }
+
+    let status = match healthy_plugins_count {
+        0 => HealthStatus::Unhealthy,
+        n if n == affected_plugins.len() => HealthStatus::Healthy,
+        _ => HealthStatus::Partial,
+    };
+
+    PluginsHealthResponse {
+        status,
+        plugin_health_responses,
+    }
+}
+
+/// Health check for the plugins of all pipelines
 #[utoipa::path(
     get,
     path = "/plugin_health", 
@@ -141,6 +198,7 @@ async fn match_plugin_health(plugin: &Arc<dyn PushAndPlugin>) -> PlugingHealthRe
             "plugin_health_responses": [
                 {
                     "status": "Healthy",
+                    "pipeline": "default",
                     "plugin_meta": {
                         "plugin_name": "example",
                         "plugin_type": "push",
@@ -161,42 +219,36 @@ pub async fn plugin_health(
 ) -> PluginsHealthResponse {
     tracing::trace!("Health check for plugins");
 
-    let mut plugin_health_responses = vec![];
-    let mut healthy_plugins_count: usize = 0;
-
-    let affected_plugins: Vec<&Arc<dyn PushAndPlugin>> = if let Some(ref exp) = exp {
-        state
-            .plugins
-            .iter()
-            .filter(|plugin| exp.is_match(&plugin.meta()))
-            .collect()
-    } else {
-        state.plugins.iter().collect()
-    };
-
-    if affected_plugins.is_empty() {
-        return PluginsHealthResponse {
-            status: HealthStatus::NoPlugins,
-            plugin_health_responses: vec![],
-        };
-    }
+    pipelines_health(state.all_pipelines().collect(), exp.as_deref()).await
+}
 
-    for plugin in affected_plugins.iter() {
-        let res = match_plugin_health(plugin).await;
-        if let PluginHealthStatus::Healthy = res.status {
-            healthy_plugins_count += 1;
-        }
-        plugin_health_responses.push(res);
-    }
+/// Health check for the plugins of a pipeline
+#[utoipa::path(
+    get,
+    path = "/plugin_health/{pipeline}", 
+    tag = "health", 
+    params(
+        ("pipeline" = String, Path, description = "Name of the pipeline. `default` is the pipeline served at `/push`"),
+        PluginFilterQuery
+    ),
+    responses(
+        (status = 200, description = "All affected plugins are healthy.", body = PluginsHealthResponse),
+        (status = 207, description = "Some affected plugins are unhealthy.", body = PluginsHealthResponse),
+        (status = 503, description = "All affected plugins are unhealthy.", body = PluginsHealthResponse),
+        (status = 404, description = "No plugins or no pipeline with the name were found.", body = PluginsHealthResponse)
+    )
+)]
+#[tracing::instrument(name = "plugin_health", skip_all, fields(pipeline = %name))]
+pub async fn pipeline_plugin_health(
+    State(state): State<ApiState>,
+    ApiPath(name): ApiPath<String>,
+    ApiPluginFilterQuery(exp): ApiPluginFilterQuery,
+) -> Result<PluginsHealthResponse, ErrorResponse> {
+    tracing::trace!("Health check for plugins");
 
-    let status = match healthy_plugins_count {
-        0 => HealthStatus::Unhealthy,
-        n if n == affected_plugins.len() => HealthStatus::Healthy,
-        _ => HealthStatus::Partial,
-    };
+    let pipeline = state
+        .pipeline(&name)
+        .ok_or_else(|| ErrorResponse::pipeline_not_found(name))?;
 
-    PluginsHealthResponse {
-        status,
-        plugin_health_responses,
-    }
+    Ok(pipelines_health(vec![pipeline], exp.as_deref()).await)
 }
@@ -7,8 +7,14 @@ use axum::extract::State;
         json!("
 # HELP push_success_total Total number of successful pushes.
 # TYPE push_success_total counter
+# HELP push_skipped_total Total number of pushes the plugins intentionally skipped.
+# TYPE push_skipped_total counter
+# HELP push_partial_total Total number of pushes with different outcomes per alert.
+# TYPE push_partial_total counter
 # HELP push_failed_total Total number of failed pushes.
 # TYPE push_failed_total counter
+# HELP push_retries_total Total number of retried pushes.
+# TYPE push_retries_total counter
 # EOF
         ")),
     (status = 500, description = "Iternal server error.")
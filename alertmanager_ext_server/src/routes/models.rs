@@ -34,3 +34,14 @@ pub struct PluginFilterQuery {
     #[param(nullable, example = json!("name matches /^postgres.*$/"))]
     pub filter: Option<String>,
 }
+
+/// Query filter for alerts
+///
+/// This is used to restrict the alerts that are forwarded to plugins.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct AlertFilterQuery {
+    /// Filter alerts by receiver, status, labels or annotations.
+    #[param(nullable, example = json!("status is firing and label severity in [critical, page]"))]
+    pub alert_filter: Option<String>,
+}
@@ -1,17 +1,26 @@
 use super::models::PluginFilterQuery;
-use super::models::PluginResponseMeta;
+use super::models::{AlertFilterQuery, PluginResponseMeta};
 use crate::{
-    extractors::{json::ApiJson, query::ApiPluginFilterQuery},
-    prometheus_client::PushLabel,
-    state::ApiState,
+    error_response::ErrorResponse,
+    extractors::{
+        json::ApiJson,
+        path::ApiPath,
+        query::{ApiAlertFilterQuery, ApiPluginFilterQuery},
+    },
+    pipeline::{PipelineRun, StageResponse},
+    prometheus_client::{FailedPushLabel, PushLabel},
+    state::{ApiState, PipelineState},
     traits::{HasStatusCode, PushAndPlugin},
 };
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use http_client::RetryConfig;
 use models::AlertmanagerPush;
+use plugins_filter::ast::{AlertExpr, Expr};
+use push_definitions::{AlertOutcome, AlertResult, ErrorClass, PushError, PushOutcome};
 use schemars::JsonSchema;
 use serde::Serialize;
-use std::sync::Arc;
-use tokio::task::JoinHandle;
+use std::{sync::Arc, time::Duration};
+use tokio::task::{JoinError, JoinHandle};
 use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Serialize, JsonSchema, PartialEq, ToSchema)]
@@ -23,8 +32,12 @@ pub enum PushStatus {
     Partial,
     /// Push failed
     Failed,
+    /// Push failed permanently. Retrying will not help
+    Rejected,
     /// No plugins were found
     NoPlugins,
+    /// No alerts matched the alert filter
+    NoAlerts,
 }
 
 impl HasStatusCode for PushStatus {
@@ -33,29 +46,184 @@ impl HasStatusCode for PushStatus {
             PushStatus::Ok => StatusCode::ACCEPTED,
             PushStatus::Partial => StatusCode::MULTI_STATUS,
             PushStatus::Failed => StatusCode::INTERNAL_SERVER_ERROR,
+            PushStatus::Rejected => StatusCode::UNPROCESSABLE_ENTITY,
             PushStatus::NoPlugins => StatusCode::NOT_FOUND,
+            PushStatus::NoAlerts => StatusCode::OK,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema, PartialEq, ToSchema)]
+/// Whether a failure may go away when retried
+pub enum PushErrorClass {
+    /// Worth retrying
+    Transient,
+    /// Fails again when retried
+    Permanent,
+}
+
+impl From<ErrorClass> for PushErrorClass {
+    fn from(class: ErrorClass) -> Self {
+        match class {
+            ErrorClass::Transient => PushErrorClass::Transient,
+            ErrorClass::Permanent => PushErrorClass::Permanent,
+        }
+    }
+}
+
+impl From<PushErrorClass> for ErrorClass {
+    fn from(class: PushErrorClass) -> Self {
+        match class {
+            PushErrorClass::Transient => ErrorClass::Transient,
+            PushErrorClass::Permanent => ErrorClass::Permanent,
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq, ToSchema)]
+#[serde(tag = "type", content = "content")]
+/// Push status for an alert
+pub enum AlertPushStatus {
+    /// Alert was pushed
+    Pushed,
+    /// Alert was intentionally not pushed
+    Skipped {
+        /// Why the alert was skipped
+        reason: String,
+    },
+    /// Alert failed
+    Failed {
+        /// Error message
+        message: String,
+        /// Error class
+        class: PushErrorClass,
+    },
+}
+
+impl From<AlertOutcome> for AlertPushStatus {
+    fn from(outcome: AlertOutcome) -> Self {
+        match outcome {
+            AlertOutcome::Pushed => AlertPushStatus::Pushed,
+            AlertOutcome::Skipped { reason } => AlertPushStatus::Skipped { reason },
+            AlertOutcome::Failed { class, message } => AlertPushStatus::Failed {
+                message,
+                class: class.into(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq, ToSchema)]
+/// Response for an alert
+pub struct AlertPushResponse {
+    /// Fingerprint of the alert
+    pub fingerprint: String,
+    /// Status of the alert
+    pub status: AlertPushStatus,
+}
+
 #[derive(Debug, Clone, Serialize, JsonSchema, PartialEq, ToSchema)]
 #[serde(tag = "type", content = "content")]
 /// Push status for a plugin
 pub enum PluginPushStatus {
     /// Push was successful
     Ok,
+    /// Plugin intentionally pushed nothing
+    Skipped {
+        /// Why the push was skipped
+        reason: String,
+    },
+    /// Alerts had different outcomes
+    Partial {
+        /// Status of every alert
+        alerts: Vec<AlertPushResponse>,
+    },
     /// Push failed
     Failed {
         /// Error message
         message: String,
+        /// Error class
+        class: PushErrorClass,
     },
 }
 
+impl PluginPushStatus {
+    fn new(result: Result<PushOutcome, PushError>) -> Self {
+        match result {
+            Ok(PushOutcome::Pushed) => PluginPushStatus::Ok,
+            Ok(PushOutcome::Skipped { reason }) => PluginPushStatus::Skipped { reason },
+            Ok(PushOutcome::Alerts(results)) => {
+                let failures = results
+                    .iter()
+                    .filter_map(|result| match &result.outcome {
+                        AlertOutcome::Failed { class, message } => Some((class, message)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                if !results.is_empty() && failures.len() == results.len() {
+                    let class = match failures
+                        .iter()
+                        .any(|(class, _)| **class == ErrorClass::Transient)
+                    {
+                        true => ErrorClass::Transient,
+                        false => ErrorClass::Permanent,
+                    };
+
+                    return PluginPushStatus::Failed {
+                        message: format!(
+                            "All {} alerts failed. First error: {}",
+                            failures.len(),
+                            failures[0].1
+                        ),
+                        class: class.into(),
+                    };
+                }
+
+                PluginPushStatus::Partial {
+                    alerts: results
+                        .into_iter()
+                        .map(|result| AlertPushResponse {
+                            fingerprint: result.fingerprint,
+                            status: result.outcome.into(),
+                        })
+                        .collect(),
+                }
+            }
+            Err(error) => PluginPushStatus::Failed {
+                message: error.to_string(),
+                class: error.class.into(),
+            },
+        }
+    }
+
+    /// Class of the failure, if the push or any of its alerts failed.
+    /// Transient if any failed alert is transient
+    fn failure_class(&self) -> Option<PushErrorClass> {
+        match self {
+            PluginPushStatus::Ok | PluginPushStatus::Skipped { .. } => None,
+            PluginPushStatus::Partial { alerts } => alerts
+                .iter()
+                .filter_map(|alert| match alert.status {
+                    AlertPushStatus::Failed { class, .. } => Some(class),
+                    _ => None,
+                })
+                .reduce(|class, other| match class {
+                    PushErrorClass::Transient => class,
+                    PushErrorClass::Permanent => other,
+                }),
+            PluginPushStatus::Failed { class, .. } => Some(*class),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, JsonSchema, ToSchema)]
 /// Response for a plugin push
 pub struct PluginPushResponse {
     /// Status of the push for the plugin
     pub status: PluginPushStatus,
+    /// Number of attempts. More than one if transient failures were retried
+    pub attempts: u32,
     /// Meta information about the plugin
     pub plugin_meta: PluginResponseMeta,
 }
@@ -65,6 +233,8 @@ pub struct PluginPushResponse {
 pub struct PushResponse {
     /// Status of the push
     pub status: PushStatus,
+    /// Responses for each pipeline stage, in order
+    pub stage_responses: Vec<StageResponse>,
     /// Responses for each plugin
     pub plugin_push_responses: Vec<PluginPushResponse>,
 }
@@ -75,34 +245,293 @@ impl IntoResponse for PushResponse {
     }
 }
 
+/// Helper function
+///
+/// The push to send again, if it failed transiently.
+/// Only the alerts that failed transiently are sent again.
+fn retry_push(
+    alertmanager_push: &AlertmanagerPush,
+    result: &Result<PushOutcome, PushError>,
+) -> Option<AlertmanagerPush> {
+    match result {
+        Err(error) if error.class == ErrorClass::Transient => Some(alertmanager_push.clone()),
+        Ok(PushOutcome::Alerts(results)) => {
+            let transient = |fingerprint: &str| {
+                results.iter().any(|result| {
+                    result.fingerprint == fingerprint
+                        && matches!(
+                            result.outcome,
+                            AlertOutcome::Failed {
+                                class: ErrorClass::Transient,
+                                ..
+                            }
+                        )
+                })
+            };
+
+            let alerts = alertmanager_push
+                .alerts
+                .iter()
+                .filter(|alert| transient(&alert.fingerprint))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            (!alerts.is_empty()).then(|| AlertmanagerPush {
+                alerts,
+                ..alertmanager_push.clone()
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Helper function
+///
+/// Replaces the results of the retried alerts.
+fn merge_retry(
+    result: Result<PushOutcome, PushError>,
+    retried_push: &AlertmanagerPush,
+    retry_result: Result<PushOutcome, PushError>,
+) -> Result<PushOutcome, PushError> {
+    let Ok(PushOutcome::Alerts(mut results)) = result else {
+        // The whole push was retried
+        return retry_result;
+    };
+
+    let retried = match retry_result {
+        Ok(outcome) => outcome.for_alerts(retried_push),
+        Err(error) => AlertResult::all(retried_push, AlertOutcome::from(&error)),
+    };
+
+    for result in results.iter_mut() {
+        if let Some(retried) = retried
+            .iter()
+            .find(|retried| retried.fingerprint == result.fingerprint)
+        {
+            result.outcome = retried.outcome.clone();
+        }
+    }
+
+    Ok(PushOutcome::from_alerts(results))
+}
+
 /// Helper function
 async fn match_plugin_push(
+    state: &ApiState,
+    pipeline: &str,
     plugin: &Arc<dyn PushAndPlugin>,
     alertmanager_push: &AlertmanagerPush,
 ) -> PluginPushResponse {
-    match plugin.push_alert(alertmanager_push).await {
-        Ok(_) => PluginPushResponse {
-            status: PluginPushStatus::Ok,
-            plugin_meta: plugin.meta().into(),
+    let result = plugin.push_alert(alertmanager_push).await;
+    finish_plugin_push(state, pipeline, plugin, alertmanager_push, result).await
+}
+
+/// Helper function
+///
+/// Retries transient failures and creates the response.
+async fn finish_plugin_push(
+    state: &ApiState,
+    pipeline: &str,
+    plugin: &Arc<dyn PushAndPlugin>,
+    alertmanager_push: &AlertmanagerPush,
+    mut result: Result<PushOutcome, PushError>,
+) -> PluginPushResponse {
+    let RetryConfig {
+        max_retries,
+        backoff_millis,
+        ..
+    } = state.push_retry;
+    // Only one retry layer applies
+    let max_retries = match plugin.retries_itself() {
+        true => 0,
+        false => max_retries,
+    };
+
+    let mut attempts = 1;
+
+    while attempts <= max_retries {
+        let Some(retried_push) = retry_push(alertmanager_push, &result) else {
+            break;
+        };
+
+        let backoff = backoff_millis.saturating_mul(1 << (attempts - 1).min(16));
+        tracing::warn!(
+            name = plugin.name(),
+            attempt = attempts,
+            "Push failed transiently. Retrying."
+        );
+        tokio::time::sleep(Duration::from_millis(backoff)).await;
+
+        state
+            .prometheus_client
+            .add_retry(&PushLabel::new(pipeline, plugin.meta()));
+        attempts += 1;
+
+        let retry_result = plugin.push_alert(&retried_push).await;
+        result = merge_retry(result, &retried_push, retry_result);
+    }
+
+    if let Err(error) = &result {
+        tracing::error!(name=plugin.name(), %error, "Failed to push alerts to plugin.");
+    }
+
+    PluginPushResponse {
+        status: PluginPushStatus::new(result),
+        attempts,
+        plugin_meta: plugin.meta().into(),
+    }
+}
+
+/// Helper function
+///
+/// Response for a plugin push handler that panicked or was cancelled.
+fn join_error_response(plugin: &dyn PushAndPlugin, error: JoinError) -> PluginPushResponse {
+    if error.is_cancelled() {
+        tracing::error!(name=plugin.name(), %error, "Plugin push handler was cancelled.");
+    } else {
+        tracing::error!(name=plugin.name(), %error, "Plugin push handler panicked.");
+    }
+
+    PluginPushResponse {
+        status: PluginPushStatus::Failed {
+            message: error.to_string(),
+            class: PushErrorClass::Transient,
         },
-        Err(error) => {
-            tracing::error!(name=plugin.name(), %error, "Failed to push alerts to plugin.");
-            PluginPushResponse {
-                status: PluginPushStatus::Failed {
-                    message: error.to_string(),
-                },
-                plugin_meta: plugin.meta().into(),
-            }
+        attempts: 1,
+        plugin_meta: plugin.meta().clone().into(),
+    }
+}
+
+/// Helper function
+///
+/// Counts the plugin push in the metrics.
+fn record_plugin_push(
+    state: &ApiState,
+    pipeline: &str,
+    plugin: &dyn PushAndPlugin,
+    status: &PluginPushStatus,
+) {
+    let push_label = PushLabel::new(pipeline, plugin.meta());
+
+    match status {
+        PluginPushStatus::Ok => state.prometheus_client.add_success_push(&push_label),
+        PluginPushStatus::Skipped { .. } => state.prometheus_client.add_skipped_push(&push_label),
+        PluginPushStatus::Partial { .. } => state.prometheus_client.add_partial_push(&push_label),
+        PluginPushStatus::Failed { class, .. } => state
+            .prometheus_client
+            .add_failed_push(&FailedPushLabel::new(&push_label, (*class).into())),
+    }
+}
+
+/// Helper function
+///
+/// Status of a push from the responses of all affected plugins.
+/// Plugins with failed alerts count as failed.
+fn push_status(plugin_push_responses: &[PluginPushResponse]) -> PushStatus {
+    let total = plugin_push_responses.len();
+    if total == 0 {
+        return PushStatus::NoPlugins;
+    }
+
+    let failed_classes = plugin_push_responses
+        .iter()
+        .filter_map(|response| response.status.failure_class())
+        .collect::<Vec<_>>();
+
+    // Alertmanager retries server errors, but not client errors
+    match failed_classes.len() {
+        0 => PushStatus::Ok,
+        n if n == total && failed_classes.contains(&PushErrorClass::Transient) => {
+            PushStatus::Failed
         }
+        n if n == total => PushStatus::Rejected,
+        _ => PushStatus::Partial,
     }
 }
 
+/// Helper function
+///
+/// Applies the alert filter and the pipeline stages.
+/// Returns the final response if no alerts are left or a stage failed.
+async fn prepare_push(
+    pipeline: &PipelineState,
+    exp: Option<&Expr>,
+    alert_exp: Option<&AlertExpr>,
+    alertmanager_push: &mut AlertmanagerPush,
+) -> Result<PipelineRun, PushResponse> {
+    if let Some(alert_exp) = alert_exp {
+        alert_exp.retain_matching(alertmanager_push);
+
+        if alertmanager_push.alerts.is_empty() {
+            tracing::trace!("No alerts matched the alert filter.");
+            return Err(PushResponse {
+                status: PushStatus::NoAlerts,
+                stage_responses: vec![],
+                plugin_push_responses: vec![],
+            });
+        }
+    }
+
+    let pipeline_run = pipeline.pipeline.run(alertmanager_push, exp).await;
+
+    if let Some(class) = pipeline_run.failure {
+        pipeline.pipeline.forget(&pipeline_run);
+        return Err(PushResponse {
+            status: match class {
+                ErrorClass::Transient => PushStatus::Failed,
+                ErrorClass::Permanent => PushStatus::Rejected,
+            },
+            stage_responses: pipeline_run.stage_responses,
+            plugin_push_responses: vec![],
+        });
+    }
+
+    warn_unknown_routes(pipeline, &pipeline_run);
+
+    if alertmanager_push.alerts.is_empty() {
+        tracing::trace!("No alerts left after the pipeline stages.");
+        return Err(PushResponse {
+            status: PushStatus::NoAlerts,
+            stage_responses: pipeline_run.stage_responses,
+            plugin_push_responses: vec![],
+        });
+    }
+
+    Ok(pipeline_run)
+}
+
+/// Helper function
+///
+/// Scripts pick the plugins to route to at run time, so unknown names can't be rejected at load.
+fn warn_unknown_routes(pipeline: &PipelineState, pipeline_run: &PipelineRun) {
+    for route in pipeline_run.routes.iter().flatten() {
+        if !pipeline.plugins.iter().any(|plugin| plugin.name() == route) {
+            tracing::warn!(pipeline = %pipeline.name, %route, "Script routed the push to an unknown plugin.");
+        }
+    }
+}
+
+/// Helper function
+fn affected_plugins<'a>(
+    pipeline: &'a PipelineState,
+    exp: Option<&Expr>,
+    pipeline_run: Option<&PipelineRun>,
+) -> Vec<&'a Arc<dyn PushAndPlugin>> {
+    pipeline
+        .plugins
+        .iter()
+        .filter(|plugin| pipeline.pipeline.is_target(&plugin.meta()))
+        .filter(|plugin| pipeline_run.is_none_or(|run| run.is_routed(&plugin.meta())))
+        .filter(|plugin| exp.is_none_or(|exp| exp.is_match(&plugin.meta())))
+        .collect()
+}
+
 /// Helper struct
 ///
 /// Join handle for a plugin push response.
-struct PluginPushResponseJoinHandle<'a> {
+struct PluginPushResponseJoinHandle<'a, T> {
     /// Join handle
-    join_handle: JoinHandle<PluginPushResponse>,
+    join_handle: JoinHandle<T>,
     /// In case the join handle panics or is cancelled, we still want to know which plugin it was
     plugin: &'a dyn PushAndPlugin,
 }
@@ -112,26 +541,22 @@ struct PluginPushResponseJoinHandle<'a> {
 /// Pushes alerts asynchronously.
 async fn push_async(
     state: &ApiState,
+    pipeline: &PipelineState,
     affected_plugins: Vec<&Arc<dyn PushAndPlugin>>,
     alertmanager_push: &AlertmanagerPush,
 ) -> PushResponse {
-    if affected_plugins.is_empty() {
-        return PushResponse {
-            status: PushStatus::NoPlugins,
-            plugin_push_responses: vec![],
-        };
-    }
-
     let mut plugin_push_responses = vec![];
     let mut plugin_response_handles = vec![];
-    let mut ok_push_count: usize = 0;
 
     for plugin in affected_plugins.iter() {
         let plugin_c = Arc::clone(plugin);
+        let state_c = state.clone();
+        let pipeline_c = pipeline.name.clone();
 
         let alertmanager_push_c = alertmanager_push.clone();
-        let handle =
-            tokio::spawn(async move { match_plugin_push(&plugin_c, &alertmanager_push_c).await });
+        let handle = tokio::spawn(async move {
+            match_plugin_push(&state_c, &pipeline_c, &plugin_c, &alertmanager_push_c).await
+        });
         plugin_response_handles.push(PluginPushResponseJoinHandle {
             join_handle: handle,
             plugin: &***plugin, // Lol
@@ -139,61 +564,71 @@ async fn push_async(
     }
 
     for plugin_response_handle in plugin_response_handles {
-        let plugin_push_response = match plugin_response_handle.join_handle.await {
+        let plugin_push_response = match state
+            .in_flight_pushes
+            .join(plugin_response_handle.join_handle)
+            .await
+        {
             Ok(plugin_push_response) => plugin_push_response,
-            Err(error) => {
-                if error.is_cancelled() {
-                    tracing::error!(name=plugin_response_handle.plugin.name(), %error, "Plugin push handler was cancelled.");
-                } else {
-                    tracing::error!(name=plugin_response_handle.plugin.name(), %error, "Plugin push handler panicked.");
-                }
-                PluginPushResponse {
-                    status: PluginPushStatus::Failed {
-                        message: error.to_string(),
-                    },
-                    plugin_meta: plugin_response_handle.plugin.meta().clone().into(),
-                }
-            }
+            Err(error) => join_error_response(plugin_response_handle.plugin, error),
         };
 
-        let push_label = PushLabel::from(plugin_response_handle.plugin.meta());
-
-        match plugin_push_response.status {
-            PluginPushStatus::Ok => {
-                ok_push_count += 1;
-                state.prometheus_client.add_success_push(&push_label);
-            }
-            _ => {
-                state.prometheus_client.add_failed_push(&push_label);
-            }
-        }
+        record_plugin_push(
+            state,
+            &pipeline.name,
+            plugin_response_handle.plugin,
+            &plugin_push_response.status,
+        );
         plugin_push_responses.push(plugin_push_response);
     }
 
-    let status = match ok_push_count {
-        0 => PushStatus::Failed,
-        n if n == affected_plugins.len() => PushStatus::Ok,
-        _ => PushStatus::Partial,
-    };
-
     PushResponse {
-        status,
+        status: push_status(&plugin_push_responses),
+        stage_responses: vec![],
         plugin_push_responses,
     }
 }
 
-/// Push alerts to all plugins asynchronously
+/// Helper function
+///
+/// Pushes alerts to the plugins of a pipeline.
+async fn push_to(
+    state: &ApiState,
+    pipeline: &PipelineState,
+    exp: Option<&Expr>,
+    alert_exp: Option<&AlertExpr>,
+    mut alertmanager_push: AlertmanagerPush,
+) -> PushResponse {
+    let pipeline_run = match prepare_push(pipeline, exp, alert_exp, &mut alertmanager_push).await {
+        Ok(pipeline_run) => pipeline_run,
+        Err(push_response) => return push_response,
+    };
+
+    let affected_plugins = affected_plugins(pipeline, exp, Some(&pipeline_run));
+
+    let mut push_response = push_async(state, pipeline, affected_plugins, &alertmanager_push).await;
+    if push_response.status != PushStatus::Ok {
+        pipeline.pipeline.forget(&pipeline_run);
+    }
+    push_response.stage_responses = pipeline_run.stage_responses;
+    push_response
+}
+
+/// Push alerts to all plugins of the default pipeline asynchronously
 #[utoipa::path(
     post,
     path = "/push", 
     tag = "push",
     params(
-        PluginFilterQuery
+        PluginFilterQuery,
+        AlertFilterQuery
     ),
     request_body = AlertmanagerPush,
     responses(
+        (status = 200, description = "No alerts matched the alert filter or all alerts were dropped by the pipeline.", body = PushResponse),
         (status = 202, description = "Push was successful.", body = PushResponse),
         (status = 207, description = "Some pushes were successful.", body = PushResponse),
+        (status = 422, description = "Push failed permanently. Retrying will not help.", body = PushResponse),
         (status = 500, description = "Push failed.", body = PushResponse),
         (status = 404, description = "No plugins were found.", body = PushResponse)
     )
@@ -202,19 +637,514 @@ async fn push_async(
 pub async fn push(
     State(state): State<ApiState>,
     ApiPluginFilterQuery(exp): ApiPluginFilterQuery,
+    ApiAlertFilterQuery(alert_exp): ApiAlertFilterQuery,
     ApiJson(alertmanager_push): ApiJson<AlertmanagerPush>,
 ) -> PushResponse {
     tracing::trace!("Pushing alerts to plugins.");
 
-    let affected_plugins = if let Some(ref exp) = exp {
-        state
-            .plugins
+    push_to(
+        &state,
+        &state.default_pipeline,
+        exp.as_deref(),
+        alert_exp.as_deref(),
+        alertmanager_push,
+    )
+    .await
+}
+
+/// Push alerts to all plugins of a named pipeline asynchronously
+#[utoipa::path(
+    post,
+    path = "/push/{pipeline}", 
+    tag = "push",
+    params(
+        ("pipeline" = String, Path, description = "Name of the pipeline. `default` is the pipeline served at `/push`"),
+        PluginFilterQuery,
+        AlertFilterQuery
+    ),
+    request_body = AlertmanagerPush,
+    responses(
+        (status = 200, description = "No alerts matched the alert filter or all alerts were dropped by the pipeline.", body = PushResponse),
+        (status = 202, description = "Push was successful.", body = PushResponse),
+        (status = 207, description = "Some pushes were successful.", body = PushResponse),
+        (status = 422, description = "Push failed permanently. Retrying will not help.", body = PushResponse),
+        (status = 500, description = "Push failed.", body = PushResponse),
+        (status = 404, description = "No plugins or no pipeline with the name were found.", body = PushResponse)
+    )
+)]
+#[tracing::instrument(name = "push", skip_all, fields(pipeline = %name, group_key = alertmanager_push.group_key))]
+pub async fn push_to_pipeline(
+    State(state): State<ApiState>,
+    ApiPath(name): ApiPath<String>,
+    ApiPluginFilterQuery(exp): ApiPluginFilterQuery,
+    ApiAlertFilterQuery(alert_exp): ApiAlertFilterQuery,
+    ApiJson(alertmanager_push): ApiJson<AlertmanagerPush>,
+) -> Result<PushResponse, ErrorResponse> {
+    tracing::trace!("Pushing alerts to plugins.");
+
+    let pipeline = state
+        .pipeline(&name)
+        .ok_or_else(|| ErrorResponse::pipeline_not_found(name))?;
+
+    Ok(push_to(
+        &state,
+        pipeline,
+        exp.as_deref(),
+        alert_exp.as_deref(),
+        alertmanager_push,
+    )
+    .await)
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema, ToSchema)]
+/// Response for a batch of pushes
+pub struct BatchPushResponse {
+    /// Status of the batch, combined from the pushes with alerts
+    pub status: PushStatus,
+    /// Responses for each push, in order
+    pub push_responses: Vec<PushResponse>,
+}
+
+impl IntoResponse for BatchPushResponse {
+    fn into_response(self) -> axum::response::Response {
+        (self.status.status_code(), ApiJson(self)).into_response()
+    }
+}
+
+/// Helper function
+///
+/// Status of a batch from the statuses of its pushes.
+fn batch_status(push_responses: &[PushResponse]) -> PushStatus {
+    let statuses = push_responses
+        .iter()
+        .map(|push_response| &push_response.status)
+        .filter(|status| **status != PushStatus::NoAlerts)
+        .collect::<Vec<_>>();
+
+    let all = |expected: &[PushStatus]| statuses.iter().all(|status| expected.contains(status));
+
+    match statuses.first() {
+        None => PushStatus::NoAlerts,
+        Some(PushStatus::NoPlugins) => PushStatus::NoPlugins,
+        Some(_) if all(&[PushStatus::Ok]) => PushStatus::Ok,
+        Some(_) if all(&[PushStatus::Rejected]) => PushStatus::Rejected,
+        Some(_) if all(&[PushStatus::Failed, PushStatus::Rejected]) => PushStatus::Failed,
+        Some(_) => PushStatus::Partial,
+    }
+}
+
+/// Helper function
+///
+/// Pushes a batch to the plugins of a pipeline.
+async fn push_batch_to(
+    state: &ApiState,
+    pipeline: &PipelineState,
+    exp: Option<&Expr>,
+    alert_exp: Option<&AlertExpr>,
+    alertmanager_pushes: Vec<AlertmanagerPush>,
+) -> BatchPushResponse {
+    let mut push_responses = vec![];
+    // Pushes with alerts left, the index of their response and their pipeline run
+    let mut pushes = vec![];
+    let mut indices = vec![];
+    let mut pipeline_runs = vec![];
+
+    for mut alertmanager_push in alertmanager_pushes {
+        match prepare_push(pipeline, exp, alert_exp, &mut alertmanager_push).await {
+            Ok(mut pipeline_run) => {
+                indices.push(push_responses.len());
+                pushes.push(alertmanager_push);
+                push_responses.push(PushResponse {
+                    status: PushStatus::NoPlugins,
+                    stage_responses: std::mem::take(&mut pipeline_run.stage_responses),
+                    plugin_push_responses: vec![],
+                });
+                pipeline_runs.push(pipeline_run);
+            }
+            Err(push_response) => push_responses.push(push_response),
+        }
+    }
+
+    let mut plugin_response_handles = vec![];
+
+    for plugin in affected_plugins(pipeline, exp, None) {
+        // Pushes routed to the plugin and the index of their response
+        let (routed_indices, routed_pushes): (Vec<usize>, Vec<AlertmanagerPush>) = indices
             .iter()
-            .filter(|plugin| exp.is_match(&plugin.meta()))
-            .collect()
-    } else {
-        state.plugins.iter().collect()
-    };
+            .zip(pushes.iter())
+            .zip(pipeline_runs.iter())
+            .filter(|(_, pipeline_run)| pipeline_run.is_routed(&plugin.meta()))
+            .map(|((index, alertmanager_push), _)| (*index, alertmanager_push.clone()))
+            .unzip();
+
+        if routed_pushes.is_empty() {
+            continue;
+        }
+
+        let plugin_c = Arc::clone(plugin);
+        let state_c = state.clone();
+        let pipeline_c = pipeline.name.clone();
+
+        let handle = tokio::spawn(async move {
+            let results = plugin_c.push_alerts(&routed_pushes).await;
+
+            let mut plugin_push_responses = vec![];
+            for (alertmanager_push, result) in routed_pushes.iter().zip(results) {
+                plugin_push_responses.push(
+                    finish_plugin_push(&state_c, &pipeline_c, &plugin_c, alertmanager_push, result)
+                        .await,
+                );
+            }
+            plugin_push_responses
+        });
+        plugin_response_handles.push((
+            PluginPushResponseJoinHandle {
+                join_handle: handle,
+                plugin: &**plugin,
+            },
+            routed_indices,
+        ));
+    }
+
+    for (plugin_response_handle, routed_indices) in plugin_response_handles {
+        let plugin_push_responses = match state
+            .in_flight_pushes
+            .join(plugin_response_handle.join_handle)
+            .await
+        {
+            Ok(plugin_push_responses) => plugin_push_responses,
+            Err(error) => {
+                let response = join_error_response(plugin_response_handle.plugin, error);
+                vec![response; routed_indices.len()]
+            }
+        };
+
+        for (index, plugin_push_response) in routed_indices.iter().zip(plugin_push_responses) {
+            record_plugin_push(
+                state,
+                &pipeline.name,
+                plugin_response_handle.plugin,
+                &plugin_push_response.status,
+            );
+            push_responses[*index]
+                .plugin_push_responses
+                .push(plugin_push_response);
+        }
+    }
+
+    for (index, pipeline_run) in indices.into_iter().zip(pipeline_runs.iter()) {
+        let push_response = &mut push_responses[index];
+        push_response.status = push_status(&push_response.plugin_push_responses);
+        if push_response.status != PushStatus::Ok {
+            pipeline.pipeline.forget(pipeline_run);
+        }
+    }
+
+    BatchPushResponse {
+        status: batch_status(&push_responses),
+        push_responses,
+    }
+}
+
+/// Push a batch of payloads to all plugins of the default pipeline asynchronously
+///
+/// Every plugin gets all pushes routed to it at once, so databases can store them in a single transaction.
+/// The alert filter and the pipeline are applied to every push.
+#[utoipa::path(
+    post,
+    path = "/push/batch", 
+    tag = "push",
+    params(
+        PluginFilterQuery,
+        AlertFilterQuery
+    ),
+    request_body = Vec<AlertmanagerPush>,
+    responses(
+        (status = 200, description = "No alerts matched the alert filter or all alerts were dropped by the pipeline.", body = BatchPushResponse),
+        (status = 202, description = "All pushes were successful.", body = BatchPushResponse),
+        (status = 207, description = "Some pushes were successful.", body = BatchPushResponse),
+        (status = 422, description = "All pushes failed permanently. Retrying will not help.", body = BatchPushResponse),
+        (status = 500, description = "All pushes failed.", body = BatchPushResponse),
+        (status = 404, description = "No plugins were found.", body = BatchPushResponse)
+    )
+)]
+#[tracing::instrument(name = "push_batch", skip_all, fields(count = alertmanager_pushes.len()))]
+pub async fn push_batch(
+    State(state): State<ApiState>,
+    ApiPluginFilterQuery(exp): ApiPluginFilterQuery,
+    ApiAlertFilterQuery(alert_exp): ApiAlertFilterQuery,
+    ApiJson(alertmanager_pushes): ApiJson<Vec<AlertmanagerPush>>,
+) -> BatchPushResponse {
+    tracing::trace!("Pushing batch to plugins.");
+
+    push_batch_to(
+        &state,
+        &state.default_pipeline,
+        exp.as_deref(),
+        alert_exp.as_deref(),
+        alertmanager_pushes,
+    )
+    .await
+}
+
+/// Push a batch of payloads to all plugins of a named pipeline asynchronously
+///
+/// Like `/push/batch`, with the plugins and the pipeline stages of the named pipeline.
+#[utoipa::path(
+    post,
+    path = "/push/{pipeline}/batch", 
+    tag = "push",
+    params(
+        ("pipeline" = String, Path, description = "Name of the pipeline. `default` is the pipeline served at `/push`"),
+        PluginFilterQuery,
+        AlertFilterQuery
+    ),
+    request_body = Vec<AlertmanagerPush>,
+    responses(
+        (status = 200, description = "No alerts matched the alert filter or all alerts were dropped by the pipeline.", body = BatchPushResponse),
+        (status = 202, description = "All pushes were successful.", body = BatchPushResponse),
+        (status = 207, description = "Some pushes were successful.", body = BatchPushResponse),
+        (status = 422, description = "All pushes failed permanently. Retrying will not help.", body = BatchPushResponse),
+        (status = 500, description = "All pushes failed.", body = BatchPushResponse),
+        (status = 404, description = "No plugins or no pipeline with the name were found.", body = BatchPushResponse)
+    )
+)]
+#[tracing::instrument(name = "push_batch", skip_all, fields(pipeline = %name, count = alertmanager_pushes.len()))]
+pub async fn push_batch_to_pipeline(
+    State(state): State<ApiState>,
+    ApiPath(name): ApiPath<String>,
+    ApiPluginFilterQuery(exp): ApiPluginFilterQuery,
+    ApiAlertFilterQuery(alert_exp): ApiAlertFilterQuery,
+    ApiJson(alertmanager_pushes): ApiJson<Vec<AlertmanagerPush>>,
+) -> Result<BatchPushResponse, ErrorResponse> {
+    tracing::trace!("Pushing batch to plugins.");
+
+    let pipeline = state
+        .pipeline(&name)
+        .ok_or_else(|| ErrorResponse::pipeline_not_found(name))?;
+
+    Ok(push_batch_to(
+        &state,
+        pipeline,
+        exp.as_deref(),
+        alert_exp.as_deref(),
+        alertmanager_pushes,
+    )
+    .await)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{pipeline::Pipeline, state::DEFAULT_PIPELINE};
+    use async_trait::async_trait;
+    use models::Alert;
+    use plugins_definitions::{HealthError, Plugin, PluginMeta};
+    use push_definitions::{InitializeError, Push};
+    use std::sync::Mutex;
+
+    /// Fails `transient` on the first attempt and `permanent` always
+    #[derive(Default)]
+    struct FlakyPlugin {
+        received: Mutex<Vec<Vec<String>>>,
+        retries_itself: bool,
+    }
+
+    #[async_trait]
+    impl Plugin for FlakyPlugin {
+        fn meta(&self) -> PluginMeta<'_> {
+            PluginMeta {
+                name: "flaky",
+                type_: "test",
+                group: "test",
+            }
+        }
+
+        async fn health(&self) -> Result<(), HealthError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Push for FlakyPlugin {
+        async fn initialize(&mut self) -> Result<(), InitializeError> {
+            Ok(())
+        }
+
+        async fn push_alert(
+            &self,
+            alertmanager_push: &AlertmanagerPush,
+        ) -> Result<PushOutcome, PushError> {
+            let mut received = self.received.lock().unwrap();
+            let first_attempt = received.is_empty();
+            received.push(
+                alertmanager_push
+                    .alerts
+                    .iter()
+                    .map(|alert| alert.fingerprint.clone())
+                    .collect(),
+            );
+
+            let failed = |class| AlertOutcome::Failed {
+                class,
+                message: String::from("failed"),
+            };
 
-    push_async(&state, affected_plugins, &alertmanager_push).await
+            let results = alertmanager_push
+                .alerts
+                .iter()
+                .map(|alert| AlertResult {
+                    fingerprint: alert.fingerprint.clone(),
+                    outcome: match alert.fingerprint.as_str() {
+                        "transient" if first_attempt => failed(ErrorClass::Transient),
+                        "permanent" => failed(ErrorClass::Permanent),
+                        _ => AlertOutcome::Pushed,
+                    },
+                })
+                .collect();
+
+            Ok(PushOutcome::from_alerts(results))
+        }
+
+        fn retries_itself(&self) -> bool {
+            self.retries_itself
+        }
+    }
+
+    impl PushAndPlugin for FlakyPlugin {}
+
+    #[tokio::test]
+    async fn retry_transient_alerts() {
+        let flaky = Arc::new(FlakyPlugin::default());
+        let plugin: Arc<dyn PushAndPlugin> = flaky.clone();
+
+        let state = ApiState::new(
+            vec![],
+            Pipeline::new(Default::default()).await.unwrap(),
+            RetryConfig {
+                max_retries: 3,
+                backoff_millis: 1,
+                ..Default::default()
+            },
+        );
+
+        let alert = |fingerprint: &str| Alert {
+            fingerprint: fingerprint.to_string(),
+            ..Default::default()
+        };
+        let push = AlertmanagerPush {
+            alerts: vec![alert("ok"), alert("transient"), alert("permanent")],
+            ..Default::default()
+        };
+
+        let response = match_plugin_push(&state, DEFAULT_PIPELINE, &plugin, &push).await;
+
+        // Only the transient failure is sent again, and only once it succeeded
+        assert_eq!(
+            *flaky.received.lock().unwrap(),
+            vec![vec!["ok", "transient", "permanent"], vec!["transient"]]
+        );
+        assert_eq!(response.attempts, 2);
+
+        let PluginPushStatus::Partial { alerts } = response.status else {
+            panic!("Expected a partial push, got {:?}", response.status);
+        };
+        assert_eq!(alerts[1].status, AlertPushStatus::Pushed);
+        assert_eq!(
+            alerts[2].status,
+            AlertPushStatus::Failed {
+                message: String::from("failed"),
+                class: PushErrorClass::Permanent
+            }
+        );
+        assert_eq!(
+            PluginPushStatus::Partial { alerts }.failure_class(),
+            Some(PushErrorClass::Permanent)
+        );
+
+        let metrics = state.prometheus_client.metrics().unwrap();
+        assert!(
+            metrics.contains(r#"push_retries_total_total{plugin_name="flaky",plugin_type="test",plugin_group="test",pipeline="default"} 1"#),
+            "{}",
+            metrics
+        );
+
+        // Plugins that retry themselves are not retried again
+        let flaky = Arc::new(FlakyPlugin {
+            retries_itself: true,
+            ..Default::default()
+        });
+        let plugin: Arc<dyn PushAndPlugin> = flaky.clone();
+
+        let response = match_plugin_push(&state, DEFAULT_PIPELINE, &plugin, &push).await;
+        assert_eq!(flaky.received.lock().unwrap().len(), 1);
+        assert_eq!(response.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn partial_push_with_transient_failure_fails() {
+        let plugin: Arc<dyn PushAndPlugin> = Arc::new(FlakyPlugin::default());
+
+        let state = ApiState::new(
+            vec![],
+            Pipeline::new(Default::default()).await.unwrap(),
+            RetryConfig::default(),
+        );
+
+        let alert = |fingerprint: &str| Alert {
+            fingerprint: fingerprint.to_string(),
+            ..Default::default()
+        };
+        let push = AlertmanagerPush {
+            alerts: vec![alert("ok"), alert("transient")],
+            ..Default::default()
+        };
+
+        let response = match_plugin_push(&state, DEFAULT_PIPELINE, &plugin, &push).await;
+        assert!(
+            matches!(response.status, PluginPushStatus::Partial { .. }),
+            "{:?}",
+            response.status
+        );
+
+        // Alertmanager must retry instead of seeing a 404
+        assert_eq!(
+            push_status(std::slice::from_ref(&response)),
+            PushStatus::Failed
+        );
+
+        let ok = PluginPushResponse {
+            status: PluginPushStatus::Ok,
+            ..response.clone()
+        };
+        assert_eq!(push_status(&[ok, response]), PushStatus::Partial);
+        assert_eq!(push_status(&[]), PushStatus::NoPlugins);
+    }
+
+    #[test]
+    fn batch_status_ignores_pushes_without_alerts() {
+        let response = |status| PushResponse {
+            status,
+            stage_responses: vec![],
+            plugin_push_responses: vec![],
+        };
+
+        let batch = |statuses: Vec<PushStatus>| {
+            batch_status(&statuses.into_iter().map(response).collect::<Vec<_>>())
+        };
+
+        assert_eq!(batch(vec![]), PushStatus::NoAlerts);
+        assert_eq!(
+            batch(vec![PushStatus::NoAlerts, PushStatus::Ok]),
+            PushStatus::Ok
+        );
+        assert_eq!(
+            batch(vec![PushStatus::Rejected, PushStatus::Failed]),
+            PushStatus::Failed
+        );
+        assert_eq!(
+            batch(vec![PushStatus::Ok, PushStatus::Rejected]),
+            PushStatus::Partial
+        );
+    }
 }
@@ -1,22 +1,34 @@
 use crate::{
-    config::Config, error_response::ErrorResponse, openapi::ApiDoc, state::ApiState,
+    config::{Config, PipelineConfig, PipelineSignatureConfig, PluginsConfig},
+    delivery::with_delivery,
+    error_response::ErrorResponse,
+    middlewares::verify_signature::{PipelineSignatureState, SignatureState},
+    openapi::ApiDoc,
+    pipeline::Pipeline,
+    shutdown::shutdown_plugins,
+    state::{ApiState, PipelineState, DEFAULT_PIPELINE},
     traits::PushAndPlugin,
 };
-use anyhow::{Context, Result as AnyResult};
+use anyhow::{ensure, Context, Result as AnyResult};
 use axum::{
+    extract::DefaultBodyLimit,
     middleware,
     routing::{get, post},
     Router,
 };
+use exec_plugin::ExecPlugin;
 use file_plugin::FilePlugin;
 use filter_plugin::FilterPlugin;
+use http_client::signature::SignatureConfig;
 use postgres_plugin::PostgresPlugin;
 use postgres_sea_plugin::PostgresSeaPlugin;
 use postgres_x_plugin::PostgresXPlugin;
 use print_plugin::PrintPlugin;
 use push_definitions::Push;
+use script_plugin::ScriptPlugin;
 use sqlite_plugin::SqlitePlugin;
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::HashSet, future::IntoFuture, sync::Arc, time::Duration};
+use tokio::{sync::oneshot, time::Instant};
 use tower::ServiceBuilder;
 use tower_http::{
     cors::CorsLayer,
@@ -28,17 +40,22 @@ use utoipa::OpenApi;
 use utoipa_rapidoc::RapiDoc;
 use utoipa_redoc::{Redoc, Servable};
 use utoipa_swagger_ui::SwaggerUi;
+use wasm_plugin::WasmPlugin;
+use webhook_plugin::WebhookPlugin;
 
 async fn not_found() -> ErrorResponse {
     ErrorResponse::not_found()
 }
 
-async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>> {
+async fn create_plugins(
+    pipeline: &str,
+    plugins_config: Option<PluginsConfig>,
+) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>> {
     let mut plugins: Vec<Arc<dyn PushAndPlugin>> = vec![];
 
-    tracing::debug!("Creating plugins.");
+    tracing::debug!(pipeline, "Creating plugins.");
 
-    if let Some(plugins_from_file) = config.plugins {
+    if let Some(plugins_from_file) = plugins_config {
         if let Some(file_plugins) = plugins_from_file.file_plugin {
             for conf_file_plugin in file_plugins {
                 let mut file_plugin =
@@ -49,7 +66,10 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
                     .await
                     .context("Failed to initialize File plugin")?;
 
-                plugins.push(Arc::new(file_plugin));
+                plugins.push(with_delivery(
+                    file_plugin,
+                    conf_file_plugin.options.delivery,
+                ));
             }
         }
 
@@ -57,6 +77,7 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
             for conf_filter_plugin in filter_plugins {
                 let mut filter_plugin =
                     FilterPlugin::new(conf_filter_plugin.meta, conf_filter_plugin.config)
+                        .await
                         .context("Failed to create Filter plugin")?;
 
                 filter_plugin
@@ -64,7 +85,10 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
                     .await
                     .context("Failed to initialize Filter plugin")?;
 
-                plugins.push(Arc::new(filter_plugin));
+                plugins.push(with_delivery(
+                    filter_plugin,
+                    conf_filter_plugin.options.delivery,
+                ));
             }
         }
 
@@ -80,7 +104,10 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
                     .await
                     .context("Failed to initialize Postgres plugin")?;
 
-                plugins.push(Arc::new(postgres_plugin));
+                plugins.push(with_delivery(
+                    postgres_plugin,
+                    conf_postgres_plugin.options.delivery,
+                ));
             }
         }
 
@@ -98,7 +125,10 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
                     .await
                     .context("Failed to initialize PostgresSea plugin")?;
 
-                plugins.push(Arc::new(postgres_sea_plugin));
+                plugins.push(with_delivery(
+                    postgres_sea_plugin,
+                    conf_postgres_sea_plugin.options.delivery,
+                ));
             }
         }
 
@@ -116,7 +146,10 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
                     .await
                     .context("Failed to initialize PostgresX plugin")?;
 
-                plugins.push(Arc::new(postgres_x_plugin));
+                plugins.push(with_delivery(
+                    postgres_x_plugin,
+                    conf_postgres_x_plugin.options.delivery,
+                ));
             }
         }
 
@@ -130,7 +163,10 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
                     .await
                     .context("Failed to initialize Print plugin")?;
 
-                plugins.push(Arc::new(print_plugin));
+                plugins.push(with_delivery(
+                    print_plugin,
+                    conf_print_plugin.options.delivery,
+                ));
             }
         }
 
@@ -145,11 +181,89 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
                     .await
                     .context("Failed to initialize SQLite plugin")?;
 
-                plugins.push(Arc::new(sqlite_plugin));
+                plugins.push(with_delivery(
+                    sqlite_plugin,
+                    conf_sqlite_plugin.options.delivery,
+                ));
+            }
+        }
+
+        if let Some(webhook_plugins) = plugins_from_file.webhook_plugin {
+            for conf_webhook_plugin in webhook_plugins {
+                let mut webhook_plugin =
+                    WebhookPlugin::new(conf_webhook_plugin.meta, conf_webhook_plugin.config)
+                        .await
+                        .context("Failed to create Webhook plugin")?;
+
+                webhook_plugin
+                    .initialize()
+                    .await
+                    .context("Failed to initialize Webhook plugin")?;
+
+                plugins.push(with_delivery(
+                    webhook_plugin,
+                    conf_webhook_plugin.options.delivery,
+                ));
+            }
+        }
+
+        if let Some(wasm_plugins) = plugins_from_file.wasm_plugin {
+            for conf_wasm_plugin in wasm_plugins {
+                let mut wasm_plugin =
+                    WasmPlugin::new(conf_wasm_plugin.meta, conf_wasm_plugin.config)
+                        .context("Failed to create Wasm plugin")?;
+
+                wasm_plugin
+                    .initialize()
+                    .await
+                    .context("Failed to initialize Wasm plugin")?;
+
+                plugins.push(with_delivery(
+                    wasm_plugin,
+                    conf_wasm_plugin.options.delivery,
+                ));
+            }
+        }
+
+        if let Some(script_plugins) = plugins_from_file.script_plugin {
+            for conf_script_plugin in script_plugins {
+                let mut script_plugin =
+                    ScriptPlugin::new(conf_script_plugin.meta, conf_script_plugin.config)
+                        .await
+                        .context("Failed to create Script plugin")?;
+
+                script_plugin
+                    .initialize()
+                    .await
+                    .context("Failed to initialize Script plugin")?;
+
+                plugins.push(with_delivery(
+                    script_plugin,
+                    conf_script_plugin.options.delivery,
+                ));
+            }
+        }
+
+        if let Some(exec_plugins) = plugins_from_file.exec_plugin {
+            for conf_exec_plugin in exec_plugins {
+                let mut exec_plugin =
+                    ExecPlugin::new(conf_exec_plugin.meta, conf_exec_plugin.config)
+                        .await
+                        .context("Failed to create Exec plugin")?;
+
+                exec_plugin
+                    .initialize()
+                    .await
+                    .context("Failed to initialize Exec plugin")?;
+
+                plugins.push(with_delivery(
+                    exec_plugin,
+                    conf_exec_plugin.options.delivery,
+                ));
             }
         }
     } else {
-        tracing::warn!("No plugins configured.");
+        tracing::warn!(pipeline, "No plugins configured.");
     }
 
     let mut plugin_names: HashSet<&str> = HashSet::new();
@@ -157,12 +271,13 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
     for plugin in &plugins {
         let name = plugin.name();
         if plugin_names.contains(name) {
-            tracing::warn!(name = name, "Duplicate plugin name.");
+            tracing::warn!(pipeline, name = name, "Duplicate plugin name.");
         }
 
         plugin_names.insert(name);
 
         tracing::debug!(
+            pipeline,
             name = %name,
             type_ = %plugin.type_(),
             group = %plugin.group(),
@@ -173,12 +288,116 @@ async fn create_plugins(config: Config) -> AnyResult<Vec<Arc<dyn PushAndPlugin>>
     Ok(plugins)
 }
 
-async fn create_router(config: Config) -> AnyResult<Router> {
-    let plugins = create_plugins(config).await?;
+async fn create_pipeline_state(
+    name: &str,
+    plugins_config: Option<PluginsConfig>,
+    pipeline_config: Option<PipelineConfig>,
+    push_signature: Option<SignatureConfig>,
+) -> AnyResult<PipelineState> {
+    let pipeline = Pipeline::new(pipeline_config.unwrap_or_default())
+        .await
+        .with_context(|| format!("Failed to create pipeline {name}"))?;
+
+    let plugins = create_plugins(name, plugins_config).await?;
+
+    Ok(PipelineState {
+        name: name.to_string(),
+        plugins,
+        pipeline,
+        push_signature: push_signature.map(Arc::new),
+    })
+}
 
-    let state = ApiState::new(plugins);
+async fn create_state(config: Config) -> AnyResult<ApiState> {
+    let push_retry = config.server.push_retry.clone();
+    let push_signature = config.server.push_signature.clone();
+
+    let default_pipeline = create_pipeline_state(
+        DEFAULT_PIPELINE,
+        config.plugins,
+        config.pipeline,
+        push_signature.clone(),
+    )
+    .await?;
+
+    let mut pipelines = Vec::with_capacity(config.pipelines.len());
+
+    for (name, pipeline_config) in config.pipelines {
+        // `/push/batch` and `/push/default` are taken
+        ensure!(
+            !["batch", DEFAULT_PIPELINE].contains(&name.as_str()),
+            "Pipeline name {name} is reserved"
+        );
+        ensure!(
+            !name.is_empty() && !name.contains('/'),
+            "Pipeline name {name:?} must be a single path segment"
+        );
 
-    let app = Router::new()
+        pipelines.push(
+            create_pipeline_state(
+                &name,
+                pipeline_config.plugins,
+                pipeline_config.pipeline,
+                match pipeline_config.push_signature {
+                    Some(PipelineSignatureConfig::Required(push_signature)) => Some(push_signature),
+                    Some(PipelineSignatureConfig::None(_)) => None,
+                    None => push_signature.clone(),
+                },
+            )
+            .await?,
+        );
+    }
+
+    Ok(ApiState::with_pipelines(
+        default_pipeline,
+        pipelines,
+        push_retry,
+    ))
+}
+
+fn create_router_with_state(config: &Config, state: ApiState) -> Router {
+    let max_body_bytes = config.server.max_body_bytes;
+    let max_batch_body_bytes = config.server.max_batch_body_bytes;
+
+    let verify_pipeline_signature = |max_body_bytes| {
+        middleware::from_fn_with_state(
+            PipelineSignatureState {
+                state: state.clone(),
+                max_body_bytes,
+            },
+            crate::middlewares::verify_signature::verify_pipeline_signature,
+        )
+    };
+    let push_to_pipeline_route = post(crate::routes::push::push_to_pipeline)
+        .layer(verify_pipeline_signature(max_body_bytes));
+    let push_batch_to_pipeline_route = post(crate::routes::push::push_batch_to_pipeline).layer(
+        ServiceBuilder::new()
+            .layer(DefaultBodyLimit::max(max_batch_body_bytes))
+            .layer(verify_pipeline_signature(max_batch_body_bytes)),
+    );
+
+    let mut push_route = post(crate::routes::push::push);
+    let mut push_batch_route =
+        post(crate::routes::push::push_batch).layer(DefaultBodyLimit::max(max_batch_body_bytes));
+    if let Some(push_signature) = config.server.push_signature.clone() {
+        let push_signature = Arc::new(push_signature);
+        push_route = push_route.layer(middleware::from_fn_with_state(
+            SignatureState {
+                config: push_signature.clone(),
+                max_body_bytes,
+            },
+            crate::middlewares::verify_signature::verify_signature,
+        ));
+        push_batch_route = push_batch_route.layer(middleware::from_fn_with_state(
+            SignatureState {
+                config: push_signature,
+                max_body_bytes: max_batch_body_bytes,
+            },
+            crate::middlewares::verify_signature::verify_signature,
+        ));
+    }
+
+    Router::new()
         .fallback(not_found)
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .merge(Redoc::with_url("/redoc", ApiDoc::openapi()))
@@ -186,8 +405,16 @@ async fn create_router(config: Config) -> AnyResult<Router> {
         .route("/metrics", get(crate::routes::metrics::metrics))
         .route("/health", get(crate::routes::health::health))
         .route("/plugin_health", get(crate::routes::health::plugin_health))
-        .route("/push", post(crate::routes::push::push))
+        .route(
+            "/plugin_health/:pipeline",
+            get(crate::routes::health::pipeline_plugin_health),
+        )
+        .route("/push", push_route)
+        .route("/push/batch", push_batch_route)
+        .route("/push/:pipeline", push_to_pipeline_route)
+        .route("/push/:pipeline/batch", push_batch_to_pipeline_route)
         .with_state(state)
+        .layer(DefaultBodyLimit::max(max_body_bytes))
         .layer(
             ServiceBuilder::new()
                 .layer(middleware::from_fn(
@@ -211,24 +438,53 @@ async fn create_router(config: Config) -> AnyResult<Router> {
                     crate::middlewares::trace_response_body::trace_response_body,
                 ))
                 .layer(CorsLayer::permissive()),
-        );
-
-    Ok(app)
+        )
 }
 
+/// Time for the requests of cancelled pushes to be answered
+const CANCELLED_PUSHES_GRACE: Duration = Duration::from_secs(1);
+
 pub async fn run(config: Config) -> AnyResult<()> {
     let addr = config.addr();
+    let drain_timeout = Duration::from_secs(config.server.drain_timeout_seconds);
+    let plugin_shutdown_timeout =
+        Duration::from_secs(config.server.plugin_shutdown_timeout_seconds);
 
-    let app = create_router(config).await?;
+    let state = create_state(config.clone()).await?;
+    let app = create_router_with_state(&config, state.clone());
 
     tracing::info!(%addr, "Starting server.");
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
         .context("Bind failed")?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .context("Server failed")?;
+
+    let (signal_sender, mut signal_receiver) = oneshot::channel();
+    let serve = axum::serve(listener, app).with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = signal_sender.send(());
+    });
+    let mut serve = std::pin::pin!(serve.into_future());
+
+    // The drain starts when the listener stops accepting pushes
+    tokio::select! {
+        result = &mut serve => result.context("Server failed")?,
+        Ok(()) = &mut signal_receiver => {
+            match tokio::time::timeout(drain_timeout, &mut serve).await {
+                Ok(result) => result.context("Server failed")?,
+                Err(_) => {
+                    tracing::warn!("In-flight pushes did not finish before the deadline, cancelling them.");
+                    state.in_flight_pushes.cancel();
+
+                    // The cancelled pushes are answered, so the connections can close
+                    if tokio::time::timeout(CANCELLED_PUSHES_GRACE, &mut serve).await.is_err() {
+                        tracing::warn!("Connections did not close after cancelling the pushes.");
+                    }
+                }
+            }
+        }
+    };
+
+    shutdown_plugins(&state, Instant::now() + plugin_shutdown_timeout).await;
 
     Ok(())
 }
@@ -266,6 +522,12 @@ mod tests {
     use random_models_generator::generate_random_alertmanager_pushes;
     use tracing_test::traced_test;
 
+    async fn create_router(config: Config) -> AnyResult<Router> {
+        let state = create_state(config.clone()).await?;
+
+        Ok(create_router_with_state(&config, state))
+    }
+
     #[ignore]
     #[tokio::test]
     #[traced_test]
@@ -287,4 +549,280 @@ mod tests {
             server.post("/push").json(&push).await;
         }
     }
+
+    #[tokio::test]
+    async fn invalid_plugin_filter_reports_position() {
+        let config = Config::new_from_yaml_str("server: { host: localhost, port: 5050 }")
+            .await
+            .expect("Failed to load config.");
+
+        let app = create_router(config)
+            .await
+            .expect("Failed to create router.");
+
+        let server = TestServer::new(app).expect("Failed to create test server.");
+
+        let response = server
+            .get("/plugin_health")
+            .add_query_param("filter", "name is a or group iz b")
+            .await;
+
+        response.assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.json::<serde_json::Value>();
+        assert_eq!(body["error_type"]["type"], "PluginFilterInvalid");
+        assert_eq!(body["error_type"]["error"]["column"], 20);
+        assert_eq!(
+            body["error_type"]["error"]["snippet"],
+            "name is a or group iz b\n                   ^^"
+        );
+
+        let push = generate_random_alertmanager_pushes(1).pop().unwrap();
+        let response = server
+            .post("/push")
+            .add_query_param("alert_filter", r#"label "a" matches /(/"#)
+            .json(&push)
+            .await;
+
+        response.assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.json::<serde_json::Value>();
+        assert_eq!(body["error_type"]["type"], "AlertFilterInvalid");
+        assert_eq!(body["error_type"]["error"]["column"], 19);
+    }
+
+    #[tokio::test]
+    async fn push_signature_is_verified() {
+        let config = Config::new_from_yaml_str(
+            "server: { host: localhost, port: 5050, push_signature: { secret: hook } }",
+        )
+        .await
+        .expect("Failed to load config.");
+
+        let signature = config.server.push_signature.clone().unwrap();
+
+        let app = create_router(config)
+            .await
+            .expect("Failed to create router.");
+
+        let server = TestServer::new(app).expect("Failed to create test server.");
+
+        let push = generate_random_alertmanager_pushes(1).pop().unwrap();
+        let body = serde_json::to_vec(&push).unwrap();
+
+        let response = server.post("/push").json(&push).await;
+
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+        let response_body = response.json::<serde_json::Value>();
+        assert_eq!(response_body["error_type"]["type"], "SignatureInvalid");
+
+        let response = server
+            .post("/push")
+            .add_header(
+                axum::http::HeaderName::from_static("x-alertmanager-ext-signature"),
+                axum::http::HeaderValue::from_str(&signature.sign(b"tampered")).unwrap(),
+            )
+            .json(&push)
+            .await;
+
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .post("/push")
+            .add_header(
+                axum::http::HeaderName::from_static("x-alertmanager-ext-signature"),
+                axum::http::HeaderValue::from_str(&signature.sign(&body)).unwrap(),
+            )
+            .json(&push)
+            .await;
+
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+        let response_body = response.json::<serde_json::Value>();
+        assert_eq!(response_body["status"], "NoPlugins");
+    }
+
+    #[tokio::test]
+    async fn signed_push_over_body_limit_is_rejected() {
+        let config = Config::new_from_yaml_str(
+            "server: { host: localhost, port: 5050, max_body_bytes: 64, push_signature: { secret: hook } }",
+        )
+        .await
+        .expect("Failed to load config.");
+
+        let app = create_router(config)
+            .await
+            .expect("Failed to create router.");
+
+        let server = TestServer::new(app).expect("Failed to create test server.");
+
+        let push = generate_random_alertmanager_pushes(1).pop().unwrap();
+        let response = server.post("/push").json(&push).await;
+
+        response.assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+        let response_body = response.json::<serde_json::Value>();
+        assert_eq!(response_body["error_type"]["type"], "PayloadTooLarge");
+        assert_eq!(response_body["error_type"]["error"]["max_bytes"], 64);
+    }
+
+    #[tokio::test]
+    async fn batch_has_its_own_body_limit() {
+        let config = Config::new_from_yaml_str(
+            r#"
+            server:
+              host: localhost
+              port: 5050
+              max_body_bytes: 64
+              max_batch_body_bytes: 128
+              push_signature: { secret: hook }
+            pipelines: { team_a: {} }
+            "#,
+        )
+        .await
+        .expect("Failed to load config.");
+
+        let app = create_router(config)
+            .await
+            .expect("Failed to create router.");
+
+        let server = TestServer::new(app).expect("Failed to create test server.");
+
+        let pushes = generate_random_alertmanager_pushes(1);
+        for path in ["/push/batch", "/push/team_a/batch"] {
+            let response = server.post(path).json(&pushes).await;
+
+            response.assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+            let response_body = response.json::<serde_json::Value>();
+            assert_eq!(response_body["error_type"]["error"]["max_bytes"], 128);
+        }
+
+        // 81 bytes, between the limits
+        let body = vec![0u8; 40];
+        let response = server.post("/push").json(&body).await;
+        response.assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Read in full and rejected for the missing signature
+        let response = server.post("/push/batch").json(&body).await;
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn named_pipeline_opts_out_of_server_signature() {
+        let config = Config::new_from_yaml_str(
+            r#"
+            server: { host: localhost, port: 5050, push_signature: { secret: server } }
+            pipelines:
+              team_a:
+                push_signature: none
+                plugins:
+                  print_plugin:
+                    - meta: { name: print, group: print }
+                      config: { formatter_config: { format_type: { type: Json } } }
+            "#,
+        )
+        .await
+        .expect("Failed to load config.");
+
+        let state = create_state(config.clone())
+            .await
+            .expect("Failed to create state.");
+        let app = create_router_with_state(&config, state);
+
+        let server = TestServer::new(app).expect("Failed to create test server.");
+
+        let push = generate_random_alertmanager_pushes(1).pop().unwrap();
+
+        let response = server.post("/push").json(&push).await;
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = server.post("/push/team_a").json(&push).await;
+        response.assert_status(axum::http::StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn push_to_named_pipeline() {
+        let config = Config::new_from_yaml_str(
+            r#"
+            server: { host: localhost, port: 5050 }
+            pipelines:
+              team_a:
+                push_signature: { secret: team_a }
+                plugins:
+                  print_plugin:
+                    - meta: { name: print, group: print }
+                      config: { formatter_config: { format_type: { type: Json } } }
+            "#,
+        )
+        .await
+        .expect("Failed to load config.");
+
+        let Some(PipelineSignatureConfig::Required(signature)) =
+            config.pipelines["team_a"].push_signature.clone()
+        else {
+            panic!("Expected a required signature.");
+        };
+
+        let state = create_state(config.clone())
+            .await
+            .expect("Failed to create state.");
+        let app = create_router_with_state(&config, state.clone());
+
+        let server = TestServer::new(app).expect("Failed to create test server.");
+
+        let push = generate_random_alertmanager_pushes(1).pop().unwrap();
+        let body = serde_json::to_vec(&push).unwrap();
+
+        // The default pipeline has no plugins
+        let response = server.post("/push").json(&push).await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+        assert_eq!(response.json::<serde_json::Value>()["status"], "NoPlugins");
+
+        let response = server.post("/push/team_b").json(&push).await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+        let response_body = response.json::<serde_json::Value>();
+        assert_eq!(response_body["error_type"]["type"], "PipelineNotFound");
+
+        let response = server.post("/push/team_a").json(&push).await;
+        response.assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .post("/push/team_a")
+            .add_header(
+                axum::http::HeaderName::from_static("x-alertmanager-ext-signature"),
+                axum::http::HeaderValue::from_str(&signature.sign(&body)).unwrap(),
+            )
+            .json(&push)
+            .await;
+
+        response.assert_status(axum::http::StatusCode::ACCEPTED);
+        let response_body = response.json::<serde_json::Value>();
+        assert_eq!(
+            response_body["plugin_push_responses"][0]["plugin_meta"]["plugin_name"],
+            "print"
+        );
+
+        let response = server.get("/plugin_health/team_a").await;
+        response.assert_status_ok();
+        let response_body = response.json::<serde_json::Value>();
+        assert_eq!(
+            response_body["plugin_health_responses"][0]["pipeline"],
+            "team_a"
+        );
+
+        let metrics = state.prometheus_client.metrics().unwrap();
+        assert!(
+            metrics.contains(r#"push_success_total_total{plugin_name="print","#)
+                && metrics.contains(r#",pipeline="team_a"} 1"#),
+            "{}",
+            metrics
+        );
+    }
+
+    #[tokio::test]
+    async fn reserved_pipeline_names_are_rejected() {
+        let config = Config::new_from_yaml_str(
+            "{ server: { host: localhost, port: 5050 }, pipelines: { batch: {} } }",
+        )
+        .await
+        .expect("Failed to load config.");
+
+        assert!(create_state(config).await.is_err());
+    }
 }
@@ -0,0 +1,243 @@
+use crate::{
+    prometheus_client::{PushLabel, ShutdownLabel},
+    state::ApiState,
+};
+use tokio::{
+    sync::watch,
+    task::{JoinError, JoinHandle},
+    time::Instant,
+};
+
+/// Lets plugin pushes that outlast the drain be cancelled before the plugins shut down
+pub struct InFlightPushes {
+    cancelled: watch::Sender<bool>,
+}
+
+impl Default for InFlightPushes {
+    fn default() -> Self {
+        let (cancelled, _) = watch::channel(false);
+        Self { cancelled }
+    }
+}
+
+impl InFlightPushes {
+    /// Cancels the running and all later plugin pushes
+    pub(crate) fn cancel(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    /// Waits for a spawned plugin push, aborting it once pushes are cancelled
+    pub(crate) async fn join<T>(&self, mut handle: JoinHandle<T>) -> Result<T, JoinError> {
+        let mut cancelled = self.cancelled.subscribe();
+        let cancelled = async move { cancelled.wait_for(|cancelled| *cancelled).await.is_ok() };
+
+        tokio::select! {
+            result = &mut handle => result,
+            true = cancelled => {
+                handle.abort();
+                handle.await
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How a plugin shut down
+pub(crate) enum ShutdownOutcome {
+    Ok,
+    Failed,
+    /// Did not finish before the deadline
+    TimedOut,
+}
+
+impl ShutdownOutcome {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownOutcome::Ok => "ok",
+            ShutdownOutcome::Failed => "failed",
+            ShutdownOutcome::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// Shuts the plugins of all pipelines down concurrently.
+///
+/// Plugins that did not finish by the deadline are given up on.
+pub(crate) async fn shutdown_plugins(
+    state: &ApiState,
+    deadline: Instant,
+) -> Vec<(PushLabel, ShutdownOutcome)> {
+    let handles = state
+        .all_pipelines()
+        .flat_map(|pipeline| {
+            pipeline.plugins.iter().map(move |plugin| {
+                let plugin_c = plugin.clone();
+                let handle = tokio::spawn(async move {
+                    tokio::time::timeout_at(deadline, plugin_c.shutdown()).await
+                });
+
+                (PushLabel::new(&pipeline.name, plugin.meta()), handle)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    tracing::info!(plugins = handles.len(), "Shutting down plugins.");
+
+    let mut outcomes = vec![];
+    for (label, handle) in handles {
+        let outcome = match handle.await {
+            Ok(Ok(Ok(()))) => {
+                tracing::info!(name = label.plugin_name, "Plugin shut down.");
+                ShutdownOutcome::Ok
+            }
+            Ok(Ok(Err(error))) => {
+                tracing::error!(name = label.plugin_name, %error, "Plugin failed to shut down.");
+                ShutdownOutcome::Failed
+            }
+            Ok(Err(_)) => {
+                tracing::warn!(
+                    name = label.plugin_name,
+                    "Plugin did not shut down before the deadline."
+                );
+                ShutdownOutcome::TimedOut
+            }
+            Err(error) => {
+                tracing::error!(name = label.plugin_name, %error, "Plugin shutdown handler panicked.");
+                ShutdownOutcome::Failed
+            }
+        };
+
+        state
+            .prometheus_client
+            .add_shutdown(&ShutdownLabel::new(&label, outcome));
+        outcomes.push((label, outcome));
+    }
+
+    outcomes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{pipeline::Pipeline, traits::PushAndPlugin};
+    use async_trait::async_trait;
+    use models::AlertmanagerPush;
+    use plugins_definitions::{HealthError, Plugin, PluginMeta};
+    use push_definitions::{InitializeError, Push, PushError, PushOutcome, ShutdownError};
+    use std::{sync::Arc, time::Duration};
+
+    struct TestPlugin {
+        name: &'static str,
+        delay: Duration,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Plugin for TestPlugin {
+        fn meta(&self) -> PluginMeta<'_> {
+            PluginMeta {
+                name: self.name,
+                type_: "test",
+                group: "test",
+            }
+        }
+
+        async fn health(&self) -> Result<(), HealthError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Push for TestPlugin {
+        async fn initialize(&mut self) -> Result<(), InitializeError> {
+            Ok(())
+        }
+
+        async fn push_alert(&self, _: &AlertmanagerPush) -> Result<PushOutcome, PushError> {
+            Ok(PushOutcome::Pushed)
+        }
+
+        async fn shutdown(&self) -> Result<(), ShutdownError> {
+            tokio::time::sleep(self.delay).await;
+
+            match self.fail {
+                true => Err(ShutdownError::from(Box::<
+                    dyn std::error::Error + Send + Sync,
+                >::from("disk full"))),
+                false => Ok(()),
+            }
+        }
+    }
+
+    impl PushAndPlugin for TestPlugin {}
+
+    #[tokio::test]
+    async fn shutdown_within_deadline() {
+        let plugin = |name, delay, fail| -> Arc<dyn PushAndPlugin> {
+            Arc::new(TestPlugin {
+                name,
+                delay: Duration::from_millis(delay),
+                fail,
+            })
+        };
+
+        let state = ApiState::new(
+            vec![
+                plugin("fast", 0, false),
+                plugin("failing", 0, true),
+                plugin("slow", 60_000, false),
+            ],
+            Pipeline::new(Default::default()).await.unwrap(),
+            Default::default(),
+        );
+
+        let started = Instant::now();
+        let outcomes = shutdown_plugins(&state, Instant::now() + Duration::from_millis(100)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let outcomes = outcomes
+            .into_iter()
+            .map(|(label, outcome)| (label.plugin_name, outcome))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("fast".to_string(), ShutdownOutcome::Ok),
+                ("failing".to_string(), ShutdownOutcome::Failed),
+                ("slow".to_string(), ShutdownOutcome::TimedOut),
+            ]
+        );
+
+        let metrics = state.prometheus_client.metrics().unwrap();
+        assert!(metrics.contains(r#"plugin_name="slow",plugin_type="test",plugin_group="test",outcome="timed_out",pipeline="default"} 1"#), "{}", metrics);
+    }
+
+    #[tokio::test]
+    async fn cancel_in_flight_pushes() {
+        let in_flight_pushes = Arc::new(InFlightPushes::default());
+
+        let fast = tokio::spawn(async { 1 });
+        assert_eq!(in_flight_pushes.join(fast).await.unwrap(), 1);
+
+        let slow = tokio::spawn(tokio::time::sleep(Duration::from_secs(60)));
+        let in_flight_pushes_c = Arc::clone(&in_flight_pushes);
+        let joined = tokio::spawn(async move { in_flight_pushes_c.join(slow).await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        in_flight_pushes.cancel();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), joined)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(result.unwrap_err().is_cancelled());
+
+        // Pushes started after the cancellation are cancelled right away
+        let late = tokio::spawn(tokio::time::sleep(Duration::from_secs(60)));
+        assert!(in_flight_pushes
+            .join(late)
+            .await
+            .unwrap_err()
+            .is_cancelled());
+    }
+}
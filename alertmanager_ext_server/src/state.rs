@@ -1,5 +1,12 @@
-use crate::{prometheus_client::PromtheusClient, traits::PushAndPlugin};
-use std::{ops::Deref, sync::Arc};
+use crate::{
+    pipeline::Pipeline, prometheus_client::PromtheusClient, shutdown::InFlightPushes,
+    traits::PushAndPlugin,
+};
+use http_client::{signature::SignatureConfig, RetryConfig};
+use std::{collections::BTreeMap, ops::Deref, sync::Arc};
+
+/// Name of the pipeline served at `/push`
+pub const DEFAULT_PIPELINE: &str = "default";
 
 #[derive(Clone)]
 pub struct ApiState {
@@ -7,19 +14,77 @@ pub struct ApiState {
 }
 
 impl ApiState {
-    pub fn new(plugins: Vec<Arc<dyn PushAndPlugin>>) -> Self {
+    /// State with only the default pipeline
+    #[cfg(test)]
+    pub fn new(
+        plugins: Vec<Arc<dyn PushAndPlugin>>,
+        pipeline: Pipeline,
+        push_retry: RetryConfig,
+    ) -> Self {
+        let default_pipeline = PipelineState {
+            name: DEFAULT_PIPELINE.to_string(),
+            plugins,
+            pipeline,
+            push_signature: None,
+        };
+
+        Self::with_pipelines(default_pipeline, vec![], push_retry)
+    }
+
+    pub fn with_pipelines(
+        default_pipeline: PipelineState,
+        pipelines: Vec<PipelineState>,
+        push_retry: RetryConfig,
+    ) -> Self {
+        let pipelines = pipelines
+            .into_iter()
+            .map(|pipeline| (pipeline.name.clone(), pipeline))
+            .collect();
+
         Self {
             inner: Arc::new(ApiStateInner {
-                plugins,
+                default_pipeline,
+                pipelines,
+                push_retry,
                 prometheus_client: PromtheusClient::default(),
+                in_flight_pushes: InFlightPushes::default(),
             }),
         }
     }
 }
 
-pub struct ApiStateInner {
+/// Plugins and transforms of a pipeline
+pub struct PipelineState {
+    pub name: String,
     pub plugins: Vec<Arc<dyn PushAndPlugin>>,
+    pub pipeline: Pipeline,
+    /// Signature required for pushes to `/push/{name}`
+    pub push_signature: Option<Arc<SignatureConfig>>,
+}
+
+pub struct ApiStateInner {
+    /// Pipeline served at `/push`
+    pub default_pipeline: PipelineState,
+    /// Pipelines served at `/push/{name}`, by name
+    pub pipelines: BTreeMap<String, PipelineState>,
+    pub push_retry: RetryConfig,
     pub prometheus_client: PromtheusClient,
+    pub in_flight_pushes: InFlightPushes,
+}
+
+impl ApiStateInner {
+    /// The pipeline with the name, including the default pipeline
+    pub fn pipeline(&self, name: &str) -> Option<&PipelineState> {
+        match name {
+            DEFAULT_PIPELINE => Some(&self.default_pipeline),
+            _ => self.pipelines.get(name),
+        }
+    }
+
+    /// The default pipeline first, then the named ones by name
+    pub fn all_pipelines(&self) -> impl Iterator<Item = &PipelineState> {
+        std::iter::once(&self.default_pipeline).chain(self.pipelines.values())
+    }
 }
 
 impl Deref for ApiState {
@@ -1,4 +1,5 @@
 use axum::http::StatusCode;
+use exec_plugin::ExecPlugin;
 use file_plugin::FilePlugin;
 use filter_plugin::FilterPlugin;
 use plugins_definitions::Plugin;
@@ -7,7 +8,10 @@ use postgres_sea_plugin::PostgresSeaPlugin;
 use postgres_x_plugin::PostgresXPlugin;
 use print_plugin::PrintPlugin;
 use push_definitions::Push;
+use script_plugin::ScriptPlugin;
 use sqlite_plugin::SqlitePlugin;
+use wasm_plugin::WasmPlugin;
+use webhook_plugin::WebhookPlugin;
 
 pub trait PushAndPlugin: Push + Plugin {}
 
@@ -25,6 +29,14 @@ impl PushAndPlugin for PrintPlugin {}
 
 impl PushAndPlugin for FilterPlugin {}
 
+impl PushAndPlugin for WebhookPlugin {}
+
+impl PushAndPlugin for WasmPlugin {}
+
+impl PushAndPlugin for ScriptPlugin {}
+
+impl PushAndPlugin for ExecPlugin {}
+
 pub trait HasStatusCode {
     fn status_code(&self) -> StatusCode;
 }
@@ -27,6 +27,40 @@ pub struct AlertmanagerPush {
     pub alerts: Vec<Alert>,
 }
 
+impl AlertmanagerPush {
+    /// Keeps only the alerts for which `keep` returns `true`.
+    ///
+    /// Removed alerts are counted in `truncated_alerts`,
+    /// so that `alerts.len() + truncated_alerts` is still the size of the group,
+    /// and `status` is recomputed from the remaining alerts.
+    pub fn retain_alerts(&mut self, keep: impl FnMut(&Alert) -> bool) {
+        let alerts_count = self.alerts.len();
+        self.alerts.retain(keep);
+        self.count_removed_alerts(alerts_count);
+    }
+
+    /// Like [`AlertmanagerPush::retain_alerts`], for alerts that were already removed
+    /// from a push that had `alerts_count` alerts
+    pub fn count_removed_alerts(&mut self, alerts_count: usize) {
+        let Some(removed_count) = alerts_count.checked_sub(self.alerts.len()) else {
+            return;
+        };
+
+        if removed_count > 0 {
+            self.truncated_alerts += removed_count as i32;
+            self.status = if self
+                .alerts
+                .iter()
+                .any(|alert| alert.status == Status::Firing)
+            {
+                Status::Firing
+            } else {
+                Status::Resolved
+            };
+        }
+    }
+}
+
 #[derive(
     Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema, PartialEq, Eq, Hash, Default,
 )]
@@ -0,0 +1,69 @@
+use super::rfc3339;
+use models::AlertmanagerPush;
+
+/// Quotes a field if it contains a separator, a quote or a line break (RFC 4180).
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn row(fields: impl IntoIterator<Item = String>) -> String {
+    fields
+        .into_iter()
+        .map(|value| field(&value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Header row followed by one row per alert, separated by CRLF (RFC 4180)
+///
+/// Missing labels and annotations are written as empty fields.
+pub(crate) fn format(push: &AlertmanagerPush, labels: &[String], annotations: &[String]) -> String {
+    let header = [
+        "receiver",
+        "groupKey",
+        "status",
+        "startsAt",
+        "endsAt",
+        "fingerprint",
+    ]
+    .into_iter()
+    .map(String::from)
+    .chain(labels.iter().map(|name| format!("labels.{}", name)))
+    .chain(
+        annotations
+            .iter()
+            .map(|name| format!("annotations.{}", name)),
+    );
+
+    let mut lines = vec![row(header)];
+
+    for alert in push.alerts.iter() {
+        let fields = [
+            push.receiver.clone(),
+            push.group_key.clone(),
+            alert.status.to_string(),
+            rfc3339(&alert.starts_at),
+            alert.ends_at.as_ref().map(rfc3339).unwrap_or_default(),
+            alert.fingerprint.clone(),
+        ]
+        .into_iter()
+        .chain(
+            labels
+                .iter()
+                .map(|name| alert.labels.get(name).cloned().unwrap_or_default()),
+        )
+        .chain(
+            annotations
+                .iter()
+                .map(|name| alert.annotations.get(name).cloned().unwrap_or_default()),
+        );
+
+        lines.push(row(fields));
+    }
+
+    lines.join("\r\n")
+}
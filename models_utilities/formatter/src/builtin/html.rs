@@ -0,0 +1,72 @@
+use super::{rfc3339, title};
+use models::AlertmanagerPush;
+use std::collections::BTreeMap;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn pairs(map: &BTreeMap<String, String>) -> String {
+    map.iter()
+        .map(|(name, value)| format!("{}={}", escape(name), escape(value)))
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+pub(crate) fn format(push: &AlertmanagerPush) -> String {
+    let mut lines = vec![
+        format!("<h2>{}</h2>", escape(&title(push))),
+        format!(
+            "<p><strong>Receiver:</strong> {}</p>",
+            escape(&push.receiver)
+        ),
+    ];
+
+    if !push.common_labels.is_empty() {
+        lines.push(format!(
+            "<p><strong>Common labels:</strong><br>{}</p>",
+            pairs(&push.common_labels)
+        ));
+    }
+
+    if !push.common_annotations.is_empty() {
+        lines.push(format!(
+            "<p><strong>Common annotations:</strong><br>{}</p>",
+            pairs(&push.common_annotations)
+        ));
+    }
+
+    lines.push(String::from("<table>"));
+    lines.push(String::from(
+        "<thead><tr><th>Status</th><th>Starts at</th><th>Labels</th><th>Annotations</th></tr></thead>",
+    ));
+    lines.push(String::from("<tbody>"));
+
+    for alert in push.alerts.iter() {
+        lines.push(format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            alert.status.to_string(),
+            rfc3339(&alert.starts_at),
+            pairs(&alert.labels),
+            pairs(&alert.annotations)
+        ));
+    }
+
+    lines.push(String::from("</tbody>"));
+    lines.push(String::from("</table>"));
+
+    lines.join("\n")
+}
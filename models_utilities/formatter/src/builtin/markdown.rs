@@ -0,0 +1,68 @@
+use super::{rfc3339, title};
+use models::AlertmanagerPush;
+use std::collections::BTreeMap;
+
+/// Escapes all characters with a meaning in CommonMark and flattens line breaks.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '{' | '}' | '[' | ']' | '(' | ')' | '#' | '+' | '-' | '.'
+            | '!' | '|' | '<' | '>' | '~' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn pairs(map: &BTreeMap<String, String>) -> String {
+    map.iter()
+        .map(|(name, value)| format!("{}={}", escape(name), escape(value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn format(push: &AlertmanagerPush) -> String {
+    let mut lines = vec![
+        format!("## {}", escape(&title(push))),
+        String::new(),
+        format!("**Receiver:** {}", escape(&push.receiver)),
+    ];
+
+    if !push.common_labels.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("**Common labels:** {}", pairs(&push.common_labels)));
+    }
+
+    if !push.common_annotations.is_empty() {
+        lines.push(String::new());
+        lines.push(format!(
+            "**Common annotations:** {}",
+            pairs(&push.common_annotations)
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(String::from("### Alerts"));
+    lines.push(String::new());
+
+    for alert in push.alerts.iter() {
+        lines.push(format!(
+            "- **{}** since {}",
+            alert.status.to_string(),
+            escape(&rfc3339(&alert.starts_at))
+        ));
+        lines.push(format!("  - Labels: {}", pairs(&alert.labels)));
+        if !alert.annotations.is_empty() {
+            lines.push(format!("  - Annotations: {}", pairs(&alert.annotations)));
+        }
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,38 @@
+//! Built-in output formats that would otherwise be written as Jinja templates
+
+use chrono::{NaiveDateTime, SecondsFormat};
+use std::collections::BTreeMap;
+
+pub(crate) mod csv;
+pub(crate) mod html;
+pub(crate) mod markdown;
+pub(crate) mod ndjson;
+
+/// `[FIRING:2] alertname=DiskFull, severity=critical`
+fn title(push: &models::AlertmanagerPush) -> String {
+    let mut title = format!(
+        "[{}:{}]",
+        push.status.to_string().to_uppercase(),
+        push.alerts.len()
+    );
+
+    if !push.group_labels.is_empty() {
+        title.push(' ');
+        title.push_str(&join_pairs(&push.group_labels, ", "));
+    }
+
+    title
+}
+
+fn join_pairs(map: &BTreeMap<String, String>, separator: &str) -> String {
+    map.iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn rfc3339(date_time: &NaiveDateTime) -> String {
+    date_time
+        .and_utc()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
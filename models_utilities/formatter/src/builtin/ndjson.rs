@@ -0,0 +1,39 @@
+use models::{Alert, AlertmanagerPush};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// An alert together with the context of its group
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FlatAlert<'a> {
+    group_key: &'a str,
+    receiver: &'a str,
+    group_labels: &'a BTreeMap<String, String>,
+    common_labels: &'a BTreeMap<String, String>,
+    common_annotations: &'a BTreeMap<String, String>,
+    #[serde(rename = "externalURL")]
+    external_url: &'a str,
+    #[serde(flatten)]
+    alert: &'a Alert,
+}
+
+/// One JSON object per alert and line
+pub(crate) fn format(push: &AlertmanagerPush) -> Result<String, serde_json::Error> {
+    let lines = push
+        .alerts
+        .iter()
+        .map(|alert| {
+            serde_json::to_string(&FlatAlert {
+                group_key: &push.group_key,
+                receiver: &push.receiver,
+                group_labels: &push.group_labels,
+                common_labels: &push.common_labels,
+                common_annotations: &push.common_annotations,
+                external_url: &push.external_url,
+                alert,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(lines.join("\n"))
+}
@@ -1,11 +1,16 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
-use jinja_renderer::JinjaRenderer;
-use models::AlertmanagerPush;
+use jinja_renderer::{JinjaExtensions, JinjaRenderer, UndefinedBehavior};
+use models::{Alert, AlertmanagerPush};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error as ThisError;
 
+mod builtin;
+mod truncate;
+
+pub use truncate::Truncation;
+
 #[derive(ThisError, Debug)]
 pub enum NewFormatterError {
     #[error("Failed to create jinja renderer: {0}")]
@@ -14,6 +19,27 @@ pub enum NewFormatterError {
         #[from]
         jinja_renderer::NewJinjaRendererError,
     ),
+    #[error("Failed to format the {sample} sample push{}: {error}", location(.error))]
+    Validation {
+        sample: String,
+        #[source]
+        error: FormatError,
+    },
+    #[error("Main part {0} is not defined")]
+    MissingMainPart(String),
+    #[error("Cut truncation is only supported for text formats")]
+    CutStructuredFormat,
+}
+
+/// ` at <template>:<line>` if known
+fn location(error: &FormatError) -> String {
+    match error {
+        FormatError::JinjaRender(error) => error
+            .location()
+            .map(|location| format!(" at {}", location))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
 }
 
 #[derive(ThisError, Debug)]
@@ -38,6 +64,10 @@ pub enum FormatError {
     ),
     #[error("Jinja renderer not initialized")]
     JinjaUninitialized,
+    #[error("Part {0} not initialized")]
+    PartUninitialized(String),
+    #[error("Output does not fit into {0} bytes, even without alerts")]
+    TooLarge(usize),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -53,41 +83,462 @@ pub enum FormatType {
     Yaml,
     /// Jinja template
     Jinja { template: PathBuf },
+    /// Inline Jinja template, for short parts like a title
+    JinjaInline { template: String },
+    /// Jinja templates loaded from a directory
+    ///
+    /// Every `.j2` file is loaded under its path relative to the directory,
+    /// so templates can include, import and extend each other.
+    JinjaDirectory {
+        directory: PathBuf,
+        /// Template to render, like `slack/message.j2`
+        entry: String,
+        /// Reloads the templates when they change.
+        /// A broken edit keeps the last good templates.
+        #[serde(default)]
+        watch: bool,
+    },
+    /// Markdown summary
+    Markdown,
+    /// Html summary
+    Html,
+    /// One json object per alert and line, with the group context
+    Ndjson,
+    /// One row per alert
+    Csv {
+        /// Labels written as columns
+        #[serde(default)]
+        labels: Vec<String>,
+        /// Annotations written as columns
+        #[serde(default)]
+        annotations: Vec<String>,
+    },
+    /// Named parts like a title and a body, each with its own format
+    ///
+    /// Callers that need a single string get the main part.
+    Parts {
+        parts: BTreeMap<String, FormatType>,
+        /// Defaults to `body`
+        #[serde(default = "default_main_part")]
+        main: String,
+    },
+}
+
+fn default_main_part() -> String {
+    String::from("body")
+}
+
+impl FormatType {
+    /// Format of the main part
+    fn main_format_type(&self) -> &FormatType {
+        match self {
+            FormatType::Parts { parts, main } => parts
+                .get(main)
+                .map(FormatType::main_format_type)
+                .unwrap_or(self),
+            _ => self,
+        }
+    }
+
+    /// Whether a footer can be appended without breaking the format
+    fn is_text(&self) -> bool {
+        matches!(
+            self.main_format_type(),
+            FormatType::Debug
+                | FormatType::Pretty
+                | FormatType::Jinja { .. }
+                | FormatType::JinjaInline { .. }
+                | FormatType::JinjaDirectory { .. }
+                | FormatType::Markdown
+                | FormatType::Html
+        )
+    }
+
+    /// Whether the output can be cut without breaking the format
+    fn can_cut(&self) -> bool {
+        match self {
+            FormatType::Parts { parts, .. } => parts.values().all(FormatType::can_cut),
+            _ => self.is_text(),
+        }
+    }
+
+    fn main_part(&self) -> String {
+        match self {
+            FormatType::Parts { main, .. } => main.clone(),
+            _ => default_main_part(),
+        }
+    }
+
+    /// Media type of the formatted output
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FormatType::Parts { parts, main } => parts
+                .get(main)
+                .map(FormatType::content_type)
+                .unwrap_or("text/plain; charset=utf-8"),
+            FormatType::Debug | FormatType::Pretty => "text/plain; charset=utf-8",
+            FormatType::Json => "application/json",
+            FormatType::Yaml => "application/yaml",
+            FormatType::Jinja { .. }
+            | FormatType::JinjaInline { .. }
+            | FormatType::JinjaDirectory { .. } => "text/plain; charset=utf-8",
+            FormatType::Markdown => "text/markdown; charset=utf-8",
+            FormatType::Html => "text/html; charset=utf-8",
+            FormatType::Ndjson => "application/x-ndjson",
+            FormatType::Csv { .. } => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+/// How templates treat undefined values like missing labels
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Undefined {
+    /// Undefined values render as empty strings
+    #[default]
+    Lenient,
+    /// Like lenient, but attributes of undefined values are undefined too
+    Chainable,
+    /// Any use of an undefined value is an error
+    Strict,
+}
+
+impl From<Undefined> for UndefinedBehavior {
+    fn from(undefined: Undefined) -> Self {
+        match undefined {
+            Undefined::Lenient => UndefinedBehavior::Lenient,
+            Undefined::Chainable => UndefinedBehavior::Chainable,
+            Undefined::Strict => UndefinedBehavior::Strict,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FormatterConfig {
     pub format_type: FormatType,
+    /// Checks on creation that templates only use known variables like `push` and `now()`
+    /// and formats generated sample pushes with them,
+    /// so template errors fail at startup instead of on the first alert
+    #[serde(default)]
+    pub validate: bool,
+    #[serde(default)]
+    pub undefined: Undefined,
+    /// Maximum size of the output, or of each part
+    pub max_bytes: Option<usize>,
+    /// How the output is fitted into `max_bytes`
+    #[serde(default)]
+    pub truncation: Truncation,
+}
+
+impl FormatterConfig {
+    pub fn new(format_type: FormatType) -> Self {
+        Self {
+            format_type,
+            validate: false,
+            undefined: Undefined::default(),
+            max_bytes: None,
+            truncation: Truncation::default(),
+        }
+    }
+}
+
+/// Formatted output, fitted into `max_bytes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatted {
+    /// Formatted parts. Formats without parts have a single `body` part
+    pub parts: BTreeMap<String, String>,
+    /// Trailing alerts that were dropped
+    pub omitted_alerts: Vec<Alert>,
+    /// Whether the output was cut
+    pub cut: bool,
 }
 
 pub struct Formatter {
     config: FormatterConfig,
     jinja_renderer: Option<JinjaRenderer>,
+    jinja_extensions: JinjaExtensions,
+    /// Formatters of the parts
+    parts: BTreeMap<String, Formatter>,
 }
 
 impl Formatter {
     pub async fn new(config: FormatterConfig) -> Result<Self, NewFormatterError> {
+        Self::new_with_extensions(config, JinjaExtensions::default()).await
+    }
+
+    /// Creates a formatter whose templates can use the given filters and functions
+    pub async fn new_with_extensions(
+        config: FormatterConfig,
+        jinja_extensions: JinjaExtensions,
+    ) -> Result<Self, NewFormatterError> {
+        if config.max_bytes.is_some()
+            && matches!(config.truncation, Truncation::Cut)
+            && !config.format_type.can_cut()
+        {
+            return Err(NewFormatterError::CutStructuredFormat);
+        }
+
+        let jinja_extensions = jinja_extensions.undefined_behavior(config.undefined.into());
+
         let jinja_renderer = match &config.format_type {
             FormatType::Jinja { template } => {
-                let jinja_renderer = JinjaRenderer::new_from_file(template).await?;
+                let jinja_renderer =
+                    JinjaRenderer::new_from_file_with_extensions(template, &jinja_extensions)
+                        .await?;
+                Some(jinja_renderer)
+            }
+            FormatType::JinjaInline { template } => {
+                let jinja_renderer = JinjaRenderer::new_from_str_with_extensions(
+                    template.clone(),
+                    &jinja_extensions,
+                )
+                .await?;
+                Some(jinja_renderer)
+            }
+            FormatType::JinjaDirectory {
+                directory,
+                entry,
+                watch,
+            } => {
+                let mut jinja_renderer = JinjaRenderer::new_from_dir_with_extensions(
+                    directory,
+                    entry,
+                    &jinja_extensions,
+                )
+                .await?;
+
+                if *watch {
+                    jinja_renderer.watch()?;
+                }
+
                 Some(jinja_renderer)
             }
             _ => None,
         };
 
-        Ok(Self {
+        let mut parts = BTreeMap::new();
+        if let FormatType::Parts {
+            parts: part_types,
+            main,
+        } = &config.format_type
+        {
+            if !part_types.contains_key(main) {
+                return Err(NewFormatterError::MissingMainPart(main.clone()));
+            }
+
+            for (name, format_type) in part_types.iter() {
+                let part_config = FormatterConfig {
+                    undefined: config.undefined,
+                    ..FormatterConfig::new(format_type.clone())
+                };
+                let part = Box::pin(Self::new_with_extensions(
+                    part_config,
+                    jinja_extensions.clone(),
+                ))
+                .await?;
+                parts.insert(name.clone(), part);
+            }
+        }
+
+        let formatter = Self {
             config,
             jinja_renderer,
-        })
+            jinja_extensions,
+            parts,
+        };
+
+        if formatter.config.validate {
+            formatter.validate()?;
+        }
+
+        Ok(formatter)
+    }
+
+    /// Checks the variables used by the templates, then formats random pushes
+    /// and edge cases like empty labels and a missing `ends_at`
+    pub fn validate(&self) -> Result<(), NewFormatterError> {
+        self.check_variables()?;
+
+        let random = random_models_generator::generate_random_alertmanager_pushes(10)
+            .into_iter()
+            .enumerate()
+            .map(|(i, push)| (format!("random #{}", i + 1), push));
+
+        let edge_cases = random_models_generator::generate_edge_case_alertmanager_pushes()
+            .into_iter()
+            .map(|(description, push)| (format!("\"{}\"", description), push));
+
+        for (sample, push) in edge_cases.chain(random) {
+            self.format_parts(&push)
+                .map_err(|error| NewFormatterError::Validation { sample, error })?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the variables used by the templates of the formatter and its parts
+    fn check_variables(&self) -> Result<(), NewFormatterError> {
+        if let Some(jinja_renderer) = &self.jinja_renderer {
+            jinja_renderer.check_variables()?;
+        }
+
+        for part in self.parts.values() {
+            part.check_variables()?;
+        }
+
+        Ok(())
+    }
+
+    /// Extensions to use for other templates rendered alongside this formatter
+    pub fn jinja_extensions(&self) -> &JinjaExtensions {
+        &self.jinja_extensions
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        self.config.format_type.content_type()
     }
 
+    /// Formats the push, or its main part, fitted into `max_bytes`
     pub fn format(&self, alertmanager_push: &AlertmanagerPush) -> Result<String, FormatError> {
-        let formatted = match self.config.format_type {
+        let mut formatted = self.format_limited(alertmanager_push)?;
+        let main = formatted
+            .parts
+            .remove(&self.config.format_type.main_part())
+            .unwrap_or_default();
+
+        Ok(main)
+    }
+
+    /// Formats every part, fitted into `max_bytes`. Formats without parts have a single `body` part
+    pub fn format_parts(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<BTreeMap<String, String>, FormatError> {
+        Ok(self.format_limited(alertmanager_push)?.parts)
+    }
+
+    /// Formats every part, fitted into `max_bytes`, and reports how it was fitted
+    pub fn format_limited(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<Formatted, FormatError> {
+        let parts = self.render_parts(alertmanager_push)?;
+
+        let Some(max_bytes) = self.config.max_bytes else {
+            return Ok(Formatted {
+                parts,
+                omitted_alerts: vec![],
+                cut: false,
+            });
+        };
+
+        if fits(&parts, max_bytes) {
+            return Ok(Formatted {
+                parts,
+                omitted_alerts: vec![],
+                cut: false,
+            });
+        }
+
+        if let Truncation::DropAlerts = self.config.truncation {
+            if let Some(formatted) = self.drop_alerts(alertmanager_push, max_bytes)? {
+                return Ok(formatted);
+            }
+        }
+
+        // Cutting would leave structured formats unparsable
+        if !self.config.format_type.can_cut() {
+            return Err(FormatError::TooLarge(max_bytes));
+        }
+
+        let parts = parts
+            .into_iter()
+            .map(|(name, part)| (name, truncate::cut(&part, max_bytes)))
+            .collect();
+
+        Ok(Formatted {
+            parts,
+            omitted_alerts: vec![],
+            cut: true,
+        })
+    }
+
+    /// Keeps as many leading alerts as fit, or `None` if not even the push without alerts fits
+    fn drop_alerts(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+        max_bytes: usize,
+    ) -> Result<Option<Formatted>, FormatError> {
+        let alerts = &alertmanager_push.alerts;
+        let main = self.config.format_type.main_part();
+        let add_footer = self.config.format_type.is_text();
+
+        let render = |kept: usize| -> Result<BTreeMap<String, String>, FormatError> {
+            let omitted = alerts.len() - kept;
+            let push = AlertmanagerPush {
+                alerts: alerts[..kept].to_vec(),
+                truncated_alerts: alertmanager_push.truncated_alerts + omitted as i32,
+                ..alertmanager_push.clone()
+            };
+
+            let mut parts = self.render_parts(&push)?;
+            if let (true, Some(main)) = (add_footer, parts.get_mut(&main)) {
+                main.truncate(main.trim_end().len());
+                main.push_str(&truncate::footer(omitted));
+            }
+
+            Ok(parts)
+        };
+
+        // The output grows with the alerts, so the largest number that fits can be searched.
+        // All alerts are known not to fit
+        let (mut low, mut high) = (0, alerts.len());
+        let mut best = None;
+        while low < high {
+            let kept = (low + high) / 2;
+            let parts = render(kept)?;
+
+            if fits(&parts, max_bytes) {
+                best = Some((kept, parts));
+                low = kept + 1;
+            } else {
+                high = kept;
+            }
+        }
+
+        Ok(best.map(|(kept, parts)| Formatted {
+            parts,
+            omitted_alerts: alerts[kept..].to_vec(),
+            cut: false,
+        }))
+    }
+
+    /// Formats every part without limits
+    fn render_parts(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<BTreeMap<String, String>, FormatError> {
+        if !matches!(self.config.format_type, FormatType::Parts { .. }) {
+            let body = self.render(alertmanager_push)?;
+            return Ok(BTreeMap::from([(default_main_part(), body)]));
+        }
+
+        self.parts
+            .iter()
+            .map(|(name, part)| Ok((name.clone(), part.render(alertmanager_push)?)))
+            .collect()
+    }
+
+    /// Formats the push, or its main part, without limits
+    fn render(&self, alertmanager_push: &AlertmanagerPush) -> Result<String, FormatError> {
+        let formatted = match &self.config.format_type {
             FormatType::Debug => format!("{:?}", alertmanager_push),
             FormatType::Pretty => format!("{:#?}", alertmanager_push),
             FormatType::Json => serde_json::to_string(alertmanager_push)?,
             FormatType::Yaml => serde_yaml::to_string(alertmanager_push)?,
-            FormatType::Jinja { .. } => {
+            FormatType::Jinja { .. }
+            | FormatType::JinjaInline { .. }
+            | FormatType::JinjaDirectory { .. } => {
                 let jinja_renderer = self
                     .jinja_renderer
                     .as_ref()
@@ -95,8 +546,351 @@ impl Formatter {
 
                 jinja_renderer.render(alertmanager_push)?
             }
+            FormatType::Markdown => builtin::markdown::format(alertmanager_push),
+            FormatType::Html => builtin::html::format(alertmanager_push),
+            FormatType::Ndjson => builtin::ndjson::format(alertmanager_push)?,
+            FormatType::Csv {
+                labels,
+                annotations,
+            } => builtin::csv::format(alertmanager_push, labels, annotations),
+            FormatType::Parts { main, .. } => self
+                .parts
+                .get(main)
+                .ok_or_else(|| FormatError::PartUninitialized(main.clone()))?
+                .render(alertmanager_push)?,
         };
 
         Ok(formatted)
     }
 }
+
+fn fits(parts: &BTreeMap<String, String>, max_bytes: usize) -> bool {
+    parts.values().all(|part| part.len() <= max_bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use models::{Alert, Status};
+
+    fn push() -> AlertmanagerPush {
+        AlertmanagerPush {
+            receiver: "team-a".to_string(),
+            group_key: "{}:{alertname=\"Disk\"}".to_string(),
+            status: Status::Firing,
+            group_labels: [("alertname".to_string(), "Disk".to_string())].into(),
+            alerts: vec![Alert {
+                status: Status::Firing,
+                labels: [
+                    ("alertname".to_string(), "Disk".to_string()),
+                    ("instance".to_string(), "<db-1>".to_string()),
+                ]
+                .into(),
+                annotations: [("summary".to_string(), "90% full, \"now\"".to_string())].into(),
+                fingerprint: "abc".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    async fn format(format_type: FormatType) -> String {
+        Formatter::new(FormatterConfig::new(format_type))
+            .await
+            .unwrap()
+            .format(&push())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn validate_template() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+        std::fs::create_dir_all(directory.join("ok")).unwrap();
+        std::fs::create_dir_all(directory.join("typo")).unwrap();
+        std::fs::create_dir_all(directory.join("render")).unwrap();
+
+        let new = |format_type: FormatType, undefined: Undefined| {
+            Formatter::new(FormatterConfig {
+                validate: true,
+                undefined,
+                ..FormatterConfig::new(format_type)
+            })
+        };
+        let from_directory = |name: &str, entry: &str| FormatType::JinjaDirectory {
+            directory: directory.join(name),
+            entry: entry.to_string(),
+            watch: false,
+        };
+
+        // Valid for pushes without alerts, labels or `ends_at`, even with strict undefined
+        std::fs::write(
+            directory.join("ok/message.j2"),
+            "{% for alert in push.alerts %}{{ loop.index }} {{ alert.endsAt or 'ongoing' }}{% endfor %}\n\
+             {% if 'severity' in push.commonLabels %}{{ push.commonLabels.severity }}{% endif %} {{ now() | humanize }}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("ok/severity.j2"),
+            "{{ push.commonLabels.severity }}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("typo/message.j2"),
+            "{% include 'footer.j2' %}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("typo/footer.j2"),
+            "{{ push.receiver }}\n{{ alert.labels.severity }}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("render/filter.j2"),
+            "{{ push.receiver }}\n{{ push.commonLabels | sortt }}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("render/duration.j2"),
+            "{% for alert in push.alerts %}\n{{ duration(alert.endsAt) }}{% endfor %}",
+        )
+        .unwrap();
+        std::fs::write(directory.join("unknown.j2"), "{{ alerts | length }}").unwrap();
+
+        assert!(new(from_directory("ok", "message.j2"), Undefined::Strict)
+            .await
+            .is_ok());
+        assert!(new(from_directory("ok", "severity.j2"), Undefined::Lenient)
+            .await
+            .is_ok());
+
+        let assert_error = |error: NewFormatterError, expected: String| {
+            assert!(error.to_string().contains(&expected), "{}", error);
+        };
+
+        // Static check
+        let error = new(from_directory("typo", "message.j2"), Undefined::Lenient)
+            .await
+            .err()
+            .unwrap();
+        assert_error(
+            error,
+            format!(
+                "Unknown variable alert in {}",
+                directory.join("typo/footer.j2").display()
+            ),
+        );
+
+        let template = directory.join("unknown.j2");
+        let error = new(
+            FormatType::Jinja {
+                template: template.clone(),
+            },
+            Undefined::Lenient,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_error(
+            error,
+            format!("Unknown variable alerts in {}", template.display()),
+        );
+
+        // Rendering the samples
+        let error = new(from_directory("ok", "severity.j2"), Undefined::Strict)
+            .await
+            .err()
+            .unwrap();
+        assert_error(
+            error,
+            format!(
+                "\"no alerts\" sample push at {}:1",
+                directory.join("ok/severity.j2").display()
+            ),
+        );
+
+        let error = new(from_directory("render", "filter.j2"), Undefined::Lenient)
+            .await
+            .err()
+            .unwrap();
+        assert_error(
+            error,
+            format!(" at {}:2: ", directory.join("render/filter.j2").display()),
+        );
+
+        let error = new(from_directory("render", "duration.j2"), Undefined::Lenient)
+            .await
+            .err()
+            .unwrap();
+        assert_error(
+            error,
+            format!(
+                "\"empty labels and missing ends_at\" sample push at {}:2",
+                directory.join("render/duration.j2").display()
+            ),
+        );
+    }
+
+    #[tokio::test]
+    async fn format_parts() {
+        let config: FormatterConfig = serde_yaml::from_str(
+            r#"
+            format_type:
+              type: Parts
+              parts:
+                title:
+                  type: JinjaInline
+                  template: "[{{ push.status | upper }}] {{ push.groupLabels.alertname }}"
+                body:
+                  type: Markdown
+                tags:
+                  type: JinjaInline
+                  template: "{{ push.alerts | map(attribute='labels.instance') | join(',') }}"
+            "#,
+        )
+        .unwrap();
+
+        let formatter = Formatter::new(config).await.unwrap();
+        assert_eq!(formatter.content_type(), "text/markdown; charset=utf-8");
+
+        let parts = formatter.format_parts(&push()).unwrap();
+        assert_eq!(parts["title"], "[FIRING] Disk");
+        assert_eq!(parts["tags"], "<db-1>");
+        assert_eq!(parts["body"], format(FormatType::Markdown).await);
+        assert_eq!(formatter.format(&push()).unwrap(), parts["body"]);
+
+        let parts = Formatter::new(FormatterConfig::new(FormatType::Json))
+            .await
+            .unwrap()
+            .format_parts(&push())
+            .unwrap();
+        assert_eq!(parts.keys().collect::<Vec<_>>(), vec!["body"]);
+
+        let missing_main = FormatterConfig::new(FormatType::Parts {
+            parts: BTreeMap::new(),
+            main: default_main_part(),
+        });
+        assert!(matches!(
+            Formatter::new(missing_main).await,
+            Err(NewFormatterError::MissingMainPart(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn max_bytes() {
+        let alert = |n: usize| Alert {
+            labels: [("alertname".to_string(), format!("Alert{}", n))].into(),
+            ..Default::default()
+        };
+        let push = AlertmanagerPush {
+            alerts: (0..10).map(alert).collect(),
+            ..Default::default()
+        };
+
+        let new = |truncation: Truncation| {
+            Formatter::new(FormatterConfig {
+                max_bytes: Some(60),
+                truncation,
+                ..FormatterConfig::new(FormatType::JinjaInline {
+                    template:
+                        "{% for alert in push.alerts %}{{ alert.labels.alertname }}\n{% endfor %}"
+                            .to_string(),
+                })
+            })
+        };
+
+        let formatted = new(Truncation::DropAlerts)
+            .await
+            .unwrap()
+            .format_limited(&push)
+            .unwrap();
+        assert_eq!(
+            formatted.parts["body"],
+            "Alert0\nAlert1\nAlert2\nAlert3\nAlert4\n\n… and 5 more alerts"
+        );
+        assert_eq!(formatted.omitted_alerts, push.alerts[5..]);
+        assert!(!formatted.cut);
+
+        let formatted = new(Truncation::Cut)
+            .await
+            .unwrap()
+            .format_limited(&push)
+            .unwrap();
+        assert_eq!(
+            formatted.parts["body"],
+            "Alert0\nAlert1\nAlert2\nAlert3\nAlert4\nAlert5\nAlert6\nAlert7…"
+        );
+        assert!(formatted.omitted_alerts.is_empty());
+        assert!(formatted.cut);
+
+        // Json gets no footer, but the dropped alerts are counted
+        let json = Formatter::new(FormatterConfig {
+            max_bytes: Some(600),
+            ..FormatterConfig::new(FormatType::Json)
+        })
+        .await
+        .unwrap()
+        .format(&push)
+        .unwrap();
+        let json: AlertmanagerPush = serde_json::from_str(&json).unwrap();
+        assert_eq!(json.alerts.len() as i32 + json.truncated_alerts, 10);
+        assert!(json.truncated_alerts > 0);
+
+        // Cut Json cannot be parsed, so it is rejected or fails instead
+        for format_type in [FormatType::Json, FormatType::Yaml, FormatType::Ndjson] {
+            assert!(matches!(
+                Formatter::new(FormatterConfig {
+                    max_bytes: Some(600),
+                    truncation: Truncation::Cut,
+                    ..FormatterConfig::new(format_type)
+                })
+                .await,
+                Err(NewFormatterError::CutStructuredFormat)
+            ));
+        }
+        let json = Formatter::new(FormatterConfig {
+            max_bytes: Some(10),
+            ..FormatterConfig::new(FormatType::Json)
+        })
+        .await
+        .unwrap();
+        assert!(matches!(
+            json.format_limited(&push),
+            Err(FormatError::TooLarge(10))
+        ));
+    }
+
+    #[tokio::test]
+    async fn builtin_formats() {
+        let markdown = format(FormatType::Markdown).await;
+        assert!(markdown.starts_with("## \\[FIRING:1\\] alertname=Disk\n"));
+        assert!(markdown.contains("  - Labels: alertname=Disk, instance=\\<db\\-1\\>"));
+
+        let html = format(FormatType::Html).await;
+        assert!(html.contains("instance=&lt;db-1&gt;"));
+        assert!(html.contains("summary=90% full, &quot;now&quot;"));
+
+        let ndjson = format(FormatType::Ndjson).await;
+        let line: serde_json::Value = serde_json::from_str(&ndjson).unwrap();
+        assert_eq!(line["receiver"], "team-a");
+        assert_eq!(line["groupLabels"]["alertname"], "Disk");
+        assert_eq!(line["labels"]["instance"], "<db-1>");
+        assert_eq!(line["fingerprint"], "abc");
+
+        let csv = format(FormatType::Csv {
+            labels: vec!["instance".to_string(), "missing".to_string()],
+            annotations: vec!["summary".to_string()],
+        })
+        .await;
+        assert_eq!(csv.matches("\r\n").count(), 1);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("receiver,groupKey,status,startsAt,endsAt,fingerprint,labels.instance,labels.missing,annotations.summary")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("team-a,\"{}:{alertname=\"\"Disk\"\"}\",firing,1970-01-01T00:00:00Z,,abc,<db-1>,,\"90% full, \"\"now\"\"\"")
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Fitting formatted output into `max_bytes`
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const ELLIPSIS: &str = "…";
+
+/// What to do when the formatted output exceeds `max_bytes`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Truncation {
+    /// Drops trailing alerts and adds an "N more alerts" footer to text formats.
+    /// Cuts text formats if even no alerts fit, and fails for structured formats
+    #[default]
+    DropAlerts,
+    /// Cuts the output, preferably at a line break or whitespace.
+    /// Only allowed for text formats, since cut Json, Yaml, NDJSON or CSV cannot be parsed
+    Cut,
+}
+
+/// Appended to the main part of text formats when alerts were dropped
+pub(crate) fn footer(omitted: usize) -> String {
+    match omitted {
+        1 => format!("\n\n{} and 1 more alert", ELLIPSIS),
+        _ => format!("\n\n{} and {} more alerts", ELLIPSIS, omitted),
+    }
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Cuts the text to at most `max_bytes`, ending with an ellipsis.
+///
+/// Cuts at the last line break, or else the last whitespace, if that keeps at least half of the text.
+pub(crate) fn cut(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+
+    if max_bytes < ELLIPSIS.len() {
+        return text[..floor_char_boundary(text, max_bytes)].to_string();
+    }
+
+    let end = floor_char_boundary(text, max_bytes - ELLIPSIS.len());
+    let head = &text[..end];
+
+    let end = head
+        .rfind('\n')
+        .filter(|&index| index >= end / 2)
+        .or_else(|| {
+            head.rfind(char::is_whitespace)
+                .filter(|&index| index >= end / 2)
+        })
+        .unwrap_or(end);
+
+    format!("{}{}", head[..end].trim_end(), ELLIPSIS)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cut_text() {
+        assert_eq!(cut("short", 10), "short");
+        assert_eq!(cut("first line\nsecond line", 20), "first line…");
+        assert_eq!(cut("some words here", 14), "some words…");
+        assert_eq!(cut("aaaaaaaaaaaaaaaaaaaa b", 10), "aaaaaaa…");
+        assert_eq!(cut("ääää", 7), "ää…");
+        assert_eq!(cut("ääää", 2), "ä");
+
+        for max_bytes in 0..20 {
+            let cut = cut("ä ö ü ß € 😀 text", max_bytes);
+            assert!(cut.len() <= max_bytes);
+        }
+    }
+}
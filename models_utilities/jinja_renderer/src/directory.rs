@@ -0,0 +1,159 @@
+//! Template directories
+//!
+//! Every `.j2` file below the directory is loaded under its path relative to the directory,
+//! using `/` as separator, so templates can include, import and extend each other.
+
+use crate::{environment, JinjaExtensions, NewJinjaRendererError};
+use minijinja::Environment;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, RwLock,
+    },
+    time::Duration,
+};
+
+const EXTENSION: &str = "j2";
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+pub(crate) struct TemplateDirectory {
+    pub(crate) path: PathBuf,
+    pub(crate) entry: String,
+    pub(crate) extensions: JinjaExtensions,
+    /// Shared by all clones, so the watcher's reloads can be observed
+    pub(crate) reloads: Arc<ReloadCounts>,
+}
+
+/// Reloads triggered by changes below the directory
+#[derive(Default)]
+pub(crate) struct ReloadCounts {
+    pub(crate) attempts: AtomicUsize,
+    pub(crate) failures: AtomicUsize,
+}
+
+impl TemplateDirectory {
+    /// Loads all templates and checks that the entry template exists
+    pub(crate) fn load(&self) -> Result<Environment<'static>, NewJinjaRendererError> {
+        let mut env = environment(&self.extensions);
+
+        for file in self.files()? {
+            let name = template_name(&self.path, &file);
+            let source = std::fs::read_to_string(&file)?;
+            env.add_template_owned(name, source)?;
+        }
+
+        env.get_template(&self.entry)?;
+
+        Ok(env)
+    }
+
+    /// Names of the templates currently below the directory
+    pub(crate) fn template_names(&self) -> Result<Vec<String>, std::io::Error> {
+        Ok(self
+            .files()?
+            .iter()
+            .map(|file| template_name(&self.path, file))
+            .collect())
+    }
+
+    fn files(&self) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut files = vec![];
+        collect_files(&self.path, &mut HashSet::new(), &mut files)?;
+        Ok(files)
+    }
+
+    /// Reloads the templates on every change below the directory.
+    ///
+    /// Changes are collected until the directory has been quiet for a moment,
+    /// so half-written files are not loaded.
+    /// If the templates fail to load, the last good ones are kept.
+    /// Watching stops when the returned watcher is dropped.
+    pub(crate) fn watch(
+        self,
+        env: Arc<RwLock<Arc<Environment<'static>>>>,
+    ) -> Result<RecommendedWatcher, NewJinjaRendererError> {
+        let (sender, receiver) = mpsc::channel();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event)
+                    if matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    ) =>
+                {
+                    let _ = sender.send(());
+                }
+                Ok(_) => {}
+                Err(error) => tracing::error!(%error, "Failed to watch templates."),
+            })?;
+
+        watcher.watch(&self.path, RecursiveMode::Recursive)?;
+
+        std::thread::spawn(move || {
+            // Ends when the watcher and with it the sender is dropped
+            while receiver.recv().is_ok() {
+                while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+
+                self.reloads.attempts.fetch_add(1, Ordering::Relaxed);
+                match self.load() {
+                    Ok(new_env) => {
+                        *env.write().unwrap_or_else(|error| error.into_inner()) = Arc::new(new_env);
+                        tracing::info!(path = %self.path.display(), "Reloaded templates.");
+                    }
+                    Err(error) => {
+                        self.reloads.failures.fetch_add(1, Ordering::Relaxed);
+                        tracing::error!(%error, path = %self.path.display(), "Failed to reload templates. Keeping the last good ones.");
+                    }
+                }
+            }
+        });
+
+        Ok(watcher)
+    }
+}
+
+/// Collects the template files below the directory.
+///
+/// Symlinked directories are followed, but every directory is visited once,
+/// so symlink loops end.
+fn collect_files(
+    directory: &Path,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), std::io::Error> {
+    if !visited.insert(directory.canonicalize()?) {
+        tracing::debug!(path = %directory.display(), "Skipping directory that was already visited.");
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_files(&path, visited, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// `<directory>/partials/header.j2` -> `partials/header.j2`
+fn template_name(directory: &Path, file: &Path) -> String {
+    file.strip_prefix(directory)
+        .unwrap_or(file)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
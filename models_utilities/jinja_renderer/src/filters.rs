@@ -0,0 +1,209 @@
+//! Filters and functions available in every template
+
+use chrono::{DateTime, Datelike, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use minijinja::{value::Kwargs, Environment, Error, ErrorKind, Value};
+
+pub(crate) fn add_to_environment(env: &mut Environment<'static>) {
+    env.add_filter("humanize", humanize);
+    env.add_filter("join_labels", join_labels);
+    env.add_filter("severity_emoji", severity_emoji);
+    env.add_filter("tz", tz);
+    env.add_function("duration", duration);
+    env.add_function("now", now);
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidOperation, message.into())
+}
+
+/// Parses an RFC 3339 string or a unix timestamp.
+///
+/// `none` and Alertmanager's zero time `0001-01-01T00:00:00Z` are treated as unset.
+fn date_time(value: &Value) -> Result<Option<DateTime<Utc>>, Error> {
+    if value.is_none() || value.is_undefined() {
+        return Ok(None);
+    }
+
+    let date_time = match value.as_str() {
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map_err(|error| invalid(format!("invalid timestamp {}: {}", value, error)))?
+            .with_timezone(&Utc),
+        None => {
+            let timestamp = i64::try_from(value.clone())
+                .map_err(|_| invalid(format!("invalid timestamp {}", value)))?;
+            DateTime::from_timestamp(timestamp, 0)
+                .ok_or_else(|| invalid(format!("invalid timestamp {}", value)))?
+        }
+    };
+
+    Ok((date_time.year() > 1).then_some(date_time))
+}
+
+/// `93784` -> `1d 2h`
+fn format_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+
+    let units = [
+        (seconds / 86400, "d"),
+        (seconds % 86400 / 3600, "h"),
+        (seconds % 3600 / 60, "m"),
+        (seconds % 60, "s"),
+    ];
+
+    let parts = units
+        .iter()
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect::<Vec<_>>();
+
+    match parts.is_empty() {
+        true => String::from("0s"),
+        false => format!("{}{}", sign, parts.join(" ")),
+    }
+}
+
+/// Seconds between two timestamps, up to now if `ends_at` is unset
+fn duration(starts_at: Value, ends_at: Option<Value>) -> Result<i64, Error> {
+    let starts_at = date_time(&starts_at)?.ok_or_else(|| invalid("starts_at is not set"))?;
+    let ends_at = match ends_at {
+        Some(ends_at) => date_time(&ends_at)?,
+        None => None,
+    }
+    .unwrap_or_else(Utc::now);
+
+    Ok((ends_at - starts_at).num_seconds())
+}
+
+/// Seconds as a duration, timestamps as an age relative to now
+fn humanize(value: Value) -> Result<String, Error> {
+    if value.as_str().is_none() {
+        if let Ok(seconds) = i64::try_from(value.clone()) {
+            return Ok(format_duration(seconds));
+        }
+    }
+
+    let date_time = date_time(&value)?.ok_or_else(|| invalid("timestamp is not set"))?;
+    let seconds = (Utc::now() - date_time).num_seconds();
+
+    match seconds >= 0 {
+        true => Ok(format!("{} ago", format_duration(seconds))),
+        false => Ok(format!("in {}", format_duration(-seconds))),
+    }
+}
+
+/// `name=value` pairs sorted by name
+fn join_labels(labels: Value, kwargs: Kwargs) -> Result<String, Error> {
+    let exclude: Option<Vec<String>> = kwargs.get("exclude")?;
+    let separator: Option<String> = kwargs.get("sep")?;
+    kwargs.assert_all_used()?;
+
+    let exclude = exclude.unwrap_or_default();
+
+    let mut pairs = vec![];
+    for name in labels.try_iter()? {
+        let Some(name_str) = name.as_str() else {
+            continue;
+        };
+
+        if exclude.iter().any(|excluded| excluded == name_str) {
+            continue;
+        }
+
+        pairs.push((name_str.to_string(), labels.get_item(&name)?.to_string()));
+    }
+    pairs.sort();
+
+    Ok(pairs
+        .into_iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(separator.as_deref().unwrap_or(", ")))
+}
+
+fn severity_emoji(severity: Option<String>) -> &'static str {
+    match severity.unwrap_or_default().to_lowercase().as_str() {
+        "critical" | "page" | "fatal" => "🔴",
+        "error" | "high" => "🟠",
+        "warning" | "warn" | "medium" => "🟡",
+        "info" | "low" => "🔵",
+        "resolved" | "ok" => "🟢",
+        _ => "⚪",
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Converts a timestamp to a time zone like `Europe/Berlin`.
+///
+/// Returns RFC 3339, or uses the strftime `format` keyword argument.
+/// `%Z` in the format is the zone's abbreviation.
+fn tz(value: Value, name: &str, kwargs: Kwargs) -> Result<String, Error> {
+    let format: Option<String> = kwargs.get("format")?;
+    kwargs.assert_all_used()?;
+
+    let date_time = date_time(&value)?.ok_or_else(|| invalid("timestamp is not set"))?;
+
+    let time_zone = name
+        .parse::<Tz>()
+        .map_err(|error| invalid(format!("unknown time zone {}: {}", name, error)))?;
+    let local = date_time.with_timezone(&time_zone);
+
+    match format {
+        Some(format) => Ok(local.format(&format).to_string()),
+        None => Ok(local.to_rfc3339_opts(SecondsFormat::Secs, true)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use minijinja::context;
+
+    fn render(template: &str) -> String {
+        let mut env = Environment::new();
+        add_to_environment(&mut env);
+        env.render_str(
+            template,
+            context! {
+                labels => [("alertname", "Disk"), ("severity", "critical"), ("instance", "db-1")]
+                    .into_iter()
+                    .collect::<std::collections::BTreeMap<_, _>>()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn builtin_filters() {
+        assert_eq!(
+            render("{{ duration('2024-01-01T00:00:00Z', '2024-01-02T02:03:04Z') }}"),
+            "93784"
+        );
+        assert_eq!(
+            render("{{ duration('2024-01-01T00:00:00Z', '2024-01-02T02:03:04Z') | humanize }}"),
+            "1d 2h"
+        );
+        assert_eq!(render("{{ 45 | humanize }}"), "45s");
+        assert_eq!(render("{{ 3600 | humanize }}"), "1h");
+        assert_eq!(
+            render("{{ labels | join_labels(exclude=['alertname']) }}"),
+            "instance=db-1, severity=critical"
+        );
+        assert_eq!(render("{{ labels.severity | severity_emoji }}"), "🔴");
+        assert!(render("{{ now() | humanize }}").ends_with("ago"));
+        assert_eq!(
+            render("{{ '2024-07-01T12:00:00Z' | tz('Europe/Berlin') }}"),
+            "2024-07-01T14:00:00+02:00"
+        );
+        assert_eq!(
+            render("{{ '2024-01-01T12:00:00Z' | tz('Europe/Berlin', format='%H:%M %Z') }}"),
+            "13:00 CET"
+        );
+    }
+}
@@ -1,8 +1,22 @@
-use minijinja::{context, Environment};
+use directory::TemplateDirectory;
+use minijinja::{
+    context, filters::Filter, functions::Function, value::FunctionArgs, value::FunctionResult,
+    Environment,
+};
 use models::AlertmanagerPush;
-use std::path::Path;
+use notify::RecommendedWatcher;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
 use thiserror::Error as ThisError;
 
+mod directory;
+mod filters;
+mod variables;
+
+pub use minijinja::UndefinedBehavior;
+
 #[derive(ThisError, Debug)]
 pub enum NewJinjaRendererError {
     #[error("Failed to read file: {0}")]
@@ -17,43 +31,252 @@ pub enum NewJinjaRendererError {
         #[from]
         minijinja::Error,
     ),
+    #[error("Failed to watch templates: {0}")]
+    NotifyError(
+        #[source]
+        #[from]
+        notify::Error,
+    ),
+    #[error("Unknown variable {variable} in {template}")]
+    UnknownVariable { template: String, variable: String },
 }
 
 #[derive(ThisError, Debug)]
 pub enum RenderError {
-    #[error("Failed to render template: {0}")]
-    MinijinjaError(
+    #[error("Failed to render template: {error}")]
+    MinijinjaError {
+        /// Template file and line the error occurred at, like `templates/slack.j2:3`
+        location: Option<String>,
         #[source]
-        #[from]
-        minijinja::Error,
-    ),
+        error: minijinja::Error,
+    },
+}
+
+impl RenderError {
+    /// Template file and line the error occurred at, if known
+    pub fn location(&self) -> Option<&str> {
+        match self {
+            RenderError::MinijinjaError { location, .. } => location.as_deref(),
+        }
+    }
+}
+
+type Registration = Arc<dyn Fn(&mut Environment<'static>) + Send + Sync>;
+
+/// Extra filters and functions added to every template environment
+///
+/// Registered on top of `minijinja_contrib` and our built-in filters,
+/// so they can also replace them.
+#[derive(Clone, Default)]
+pub struct JinjaExtensions {
+    registrations: Vec<Registration>,
+}
+
+impl JinjaExtensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_filter<F, Rv, Args>(mut self, name: &'static str, filter: F) -> Self
+    where
+        F: Filter<Rv, Args> + for<'a> Filter<Rv, <Args as FunctionArgs<'a>>::Output> + Clone,
+        Rv: FunctionResult + 'static,
+        Args: for<'a> FunctionArgs<'a> + 'static,
+    {
+        self.registrations
+            .push(Arc::new(move |env| env.add_filter(name, filter.clone())));
+        self
+    }
+
+    pub fn add_function<F, Rv, Args>(mut self, name: &'static str, function: F) -> Self
+    where
+        F: Function<Rv, Args> + for<'a> Function<Rv, <Args as FunctionArgs<'a>>::Output> + Clone,
+        Rv: FunctionResult + 'static,
+        Args: for<'a> FunctionArgs<'a> + 'static,
+    {
+        self.registrations.push(Arc::new(move |env| {
+            env.add_function(name, function.clone())
+        }));
+        self
+    }
+
+    /// How undefined values like missing labels are treated. Defaults to lenient
+    pub fn undefined_behavior(mut self, behavior: UndefinedBehavior) -> Self {
+        self.registrations
+            .push(Arc::new(move |env| env.set_undefined_behavior(behavior)));
+        self
+    }
+
+    fn apply(&self, env: &mut Environment<'static>) {
+        for registration in self.registrations.iter() {
+            registration(env);
+        }
+    }
+}
+
+fn environment(extensions: &JinjaExtensions) -> Environment<'static> {
+    let mut env = Environment::new();
+    minijinja_contrib::add_to_environment(&mut env);
+    filters::add_to_environment(&mut env);
+    extensions.apply(&mut env);
+    env
 }
 
 pub struct JinjaRenderer {
-    env: Environment<'static>,
+    /// Swapped when the templates are reloaded
+    env: Arc<RwLock<Arc<Environment<'static>>>>,
+    /// Name of the template to render
+    entry: String,
+    /// Directory the templates were loaded from
+    directory: Option<TemplateDirectory>,
+    /// Reloads the templates while alive
+    watcher: Option<RecommendedWatcher>,
 }
 
 impl JinjaRenderer {
     pub async fn new_from_str(template: impl Into<String>) -> Result<Self, NewJinjaRendererError> {
-        let template = template.into();
-        let mut env = Environment::new();
-        minijinja_contrib::add_to_environment(&mut env);
-        env.add_template_owned("push", template)?;
+        Self::new_from_str_with_extensions(template, &JinjaExtensions::default()).await
+    }
 
-        Ok(Self { env })
+    pub async fn new_from_str_with_extensions(
+        template: impl Into<String>,
+        extensions: &JinjaExtensions,
+    ) -> Result<Self, NewJinjaRendererError> {
+        Self::from_source(String::from("push"), template.into(), extensions)
     }
 
     pub async fn new_from_file(file: impl AsRef<Path>) -> Result<Self, NewJinjaRendererError> {
-        let template = tokio::fs::read_to_string(file).await?;
-        Self::new_from_str(template).await
+        Self::new_from_file_with_extensions(file, &JinjaExtensions::default()).await
+    }
+
+    pub async fn new_from_file_with_extensions(
+        file: impl AsRef<Path>,
+        extensions: &JinjaExtensions,
+    ) -> Result<Self, NewJinjaRendererError> {
+        let template = tokio::fs::read_to_string(&file).await?;
+        // Named by its path, so errors point to the file
+        Self::from_source(file.as_ref().display().to_string(), template, extensions)
+    }
+
+    /// Loads every `.j2` file in the directory under its relative name and renders `entry`
+    pub async fn new_from_dir_with_extensions(
+        directory: impl Into<PathBuf>,
+        entry: impl Into<String>,
+        extensions: &JinjaExtensions,
+    ) -> Result<Self, NewJinjaRendererError> {
+        let directory = TemplateDirectory {
+            path: directory.into(),
+            entry: entry.into(),
+            extensions: extensions.clone(),
+            reloads: Default::default(),
+        };
+
+        let loading = directory.clone();
+        let env = tokio::task::spawn_blocking(move || loading.load())
+            .await
+            .map_err(std::io::Error::other)??;
+
+        Ok(Self::from_env(
+            env,
+            directory.entry.clone(),
+            Some(directory),
+        ))
+    }
+
+    fn from_source(
+        name: String,
+        template: String,
+        extensions: &JinjaExtensions,
+    ) -> Result<Self, NewJinjaRendererError> {
+        let mut env = environment(extensions);
+        env.add_template_owned(name.clone(), template)?;
+
+        Ok(Self::from_env(env, name, None))
+    }
+
+    fn from_env(
+        env: Environment<'static>,
+        entry: String,
+        directory: Option<TemplateDirectory>,
+    ) -> Self {
+        Self {
+            env: Arc::new(RwLock::new(Arc::new(env))),
+            entry,
+            directory,
+            watcher: None,
+        }
+    }
+
+    /// Reloads the templates when the directory changes.
+    ///
+    /// Does nothing for templates that were not loaded from a directory.
+    pub fn watch(&mut self) -> Result<(), NewJinjaRendererError> {
+        let Some(directory) = self.directory.clone() else {
+            return Ok(());
+        };
+
+        if self.watcher.is_none() {
+            self.watcher = Some(directory.watch(Arc::clone(&self.env))?);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the templates only use `push` and the registered functions and globals.
+    ///
+    /// Syntax errors already fail when the templates are loaded.
+    /// Values are not looked at, errors that depend on the push only show when rendering.
+    pub fn check_variables(&self) -> Result<(), NewJinjaRendererError> {
+        let env = Arc::clone(&self.env.read().unwrap_or_else(|error| error.into_inner()));
+
+        let names = match &self.directory {
+            Some(directory) => directory.template_names()?,
+            None => vec![self.entry.clone()],
+        };
+
+        for name in names {
+            if let Some(variable) = variables::unknown_variable(&env, &name)? {
+                return Err(NewJinjaRendererError::UnknownVariable {
+                    template: self.template_path(&name),
+                    variable,
+                });
+            }
+        }
+
+        Ok(())
     }
 
     pub fn render(&self, push: &AlertmanagerPush) -> Result<String, RenderError> {
-        let template = self.env.get_template("push")?;
+        let env = Arc::clone(&self.env.read().unwrap_or_else(|error| error.into_inner()));
         let context = context! {
             push => push
         };
-        Ok(template.render(context)?)
+
+        env.get_template(&self.entry)
+            .and_then(|template| template.render(context))
+            .map_err(|error| RenderError::MinijinjaError {
+                location: self.location(&error),
+                error,
+            })
+    }
+
+    /// Path and line of the template the error occurred in
+    fn location(&self, error: &minijinja::Error) -> Option<String> {
+        let name = error.name()?;
+        let template = self.template_path(name);
+
+        match error.line() {
+            Some(line) => Some(format!("{}:{}", template, line)),
+            None => Some(template),
+        }
+    }
+
+    /// Templates of a directory are named relative to it, others by their path
+    fn template_path(&self, name: &str) -> String {
+        match &self.directory {
+            Some(directory) => directory.path.join(name).display().to_string(),
+            None => name.to_string(),
+        }
     }
 }
 
@@ -61,6 +284,113 @@ impl JinjaRenderer {
 mod tests {
     use super::*;
     use random_models_generator::generate_random_alertmanager_pushes;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn render_with_extensions() {
+        let push = AlertmanagerPush {
+            receiver: "team-a".to_string(),
+            ..Default::default()
+        };
+
+        let extensions = JinjaExtensions::new()
+            .add_filter("shout", |value: String| value.to_uppercase())
+            .add_function("team", || "ops");
+
+        let renderer = JinjaRenderer::new_from_str_with_extensions(
+            "{{ push.receiver | shout }} {{ team() }} {{ 90 | humanize }}",
+            &extensions,
+        )
+        .await
+        .expect("failed to create renderer");
+
+        let rendered = renderer.render(&push).expect("failed to render");
+        assert_eq!(rendered, "TEAM-A ops 1m 30s");
+    }
+
+    #[tokio::test]
+    async fn render_from_dir_and_reload() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let directory = temp_dir.path().to_path_buf();
+        std::fs::create_dir_all(directory.join("partials")).unwrap();
+
+        std::fs::write(
+            directory.join("base.j2"),
+            "[{% block title %}{% endblock %}] {% include 'partials/footer.j2' %}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("partials/footer.j2"),
+            "{% from 'macros.j2' import count %}{{ count(push.alerts) }}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("macros.j2"),
+            "{% macro count(alerts) %}{{ alerts | length }} alerts{% endmacro %}",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("slack.j2"),
+            "{% extends 'base.j2' %}{% block title %}{{ push.receiver }}{% endblock %}",
+        )
+        .unwrap();
+        std::fs::write(directory.join("ignored.txt"), "{{ broken").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&directory, directory.join("partials/loop")).unwrap();
+
+        let push = AlertmanagerPush {
+            receiver: "team-a".to_string(),
+            ..Default::default()
+        };
+
+        let mut renderer = JinjaRenderer::new_from_dir_with_extensions(
+            &directory,
+            "slack.j2",
+            &JinjaExtensions::default(),
+        )
+        .await
+        .expect("failed to create renderer");
+        renderer.watch().expect("failed to watch templates");
+
+        assert_eq!(renderer.render(&push).unwrap(), "[team-a] 0 alerts");
+
+        // A broken edit keeps the last good templates
+        let reloads = Arc::clone(&renderer.directory.as_ref().unwrap().reloads);
+        std::fs::write(directory.join("macros.j2"), "{% macro count(alerts) %}").unwrap();
+        for _ in 0..50 {
+            if reloads.failures.load(Ordering::Relaxed) > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(reloads.attempts.load(Ordering::Relaxed) > 0);
+        assert!(reloads.failures.load(Ordering::Relaxed) > 0);
+        assert_eq!(renderer.render(&push).unwrap(), "[team-a] 0 alerts");
+
+        std::fs::write(
+            directory.join("macros.j2"),
+            "{% macro count(alerts) %}no alerts{% endmacro %}",
+        )
+        .unwrap();
+
+        let mut rendered = String::new();
+        for _ in 0..50 {
+            rendered = renderer.render(&push).unwrap();
+            if rendered == "[team-a] no alerts" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(rendered, "[team-a] no alerts");
+
+        assert!(JinjaRenderer::new_from_dir_with_extensions(
+            &directory,
+            "missing.j2",
+            &JinjaExtensions::default(),
+        )
+        .await
+        .is_err());
+    }
 
     #[ignore]
     #[tokio::test]
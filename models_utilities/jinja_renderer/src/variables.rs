@@ -0,0 +1,31 @@
+//! Static check of the variables used by templates
+
+use minijinja::Environment;
+
+/// Set by minijinja in loops, macros and blocks
+const IMPLICIT: &[&str] = &["loop", "caller", "varargs", "kwargs", "self", "super"];
+
+/// The first variable of the template that is neither `push`
+/// nor a function or global of the environment
+///
+/// Attributes are not checked: labels and annotations are free-form,
+/// and nested lookups hang the static analysis of minijinja 1.0.
+pub(crate) fn unknown_variable(
+    env: &Environment<'static>,
+    name: &str,
+) -> Result<Option<String>, minijinja::Error> {
+    let template = env.get_template(name)?;
+    let state = env.empty_state();
+
+    let mut variables = template
+        .undeclared_variables(false)
+        .into_iter()
+        .collect::<Vec<_>>();
+    variables.sort();
+
+    Ok(variables.into_iter().find(|variable| {
+        variable != "push"
+            && !IMPLICIT.contains(&variable.as_str())
+            && state.lookup(variable).is_none()
+    }))
+}
@@ -90,6 +90,67 @@ pub fn generate_random_alertmanager_pushes(n: usize) -> Vec<AlertmanagerPush> {
     (0..n).map(generate_random_alertmanager_push).collect()
 }
 
+/// Pushes that random generation rarely or never produces, with a short description
+pub fn generate_edge_case_alertmanager_pushes() -> Vec<(&'static str, AlertmanagerPush)> {
+    let firing = Alert {
+        status: Status::Firing,
+        labels: BTreeMap::new(),
+        annotations: BTreeMap::new(),
+        starts_at: generate_random_naive_date_time(),
+        ends_at: None,
+        generator_url: String::new(),
+        fingerprint: generate_uuid(),
+    };
+
+    let resolved = Alert {
+        status: Status::Resolved,
+        ends_at: Some(generate_random_naive_date_time()),
+        labels: generate_random_btreemap_with_alertname_key(),
+        ..firing.clone()
+    };
+
+    let push = AlertmanagerPush {
+        version: String::from("4"),
+        group_key: generate_uuid(),
+        truncated_alerts: 0,
+        status: Status::Firing,
+        receiver: generate_random_string(),
+        group_labels: BTreeMap::new(),
+        common_labels: BTreeMap::new(),
+        common_annotations: BTreeMap::new(),
+        external_url: String::new(),
+        alerts: vec![],
+    };
+
+    vec![
+        ("no alerts", push.clone()),
+        (
+            "empty labels and missing ends_at",
+            AlertmanagerPush {
+                alerts: vec![firing.clone()],
+                ..push.clone()
+            },
+        ),
+        (
+            "resolved",
+            AlertmanagerPush {
+                status: Status::Resolved,
+                common_labels: resolved.labels.clone(),
+                alerts: vec![resolved.clone()],
+                ..push.clone()
+            },
+        ),
+        (
+            "mixed and truncated",
+            AlertmanagerPush {
+                truncated_alerts: 3,
+                alerts: vec![firing, resolved],
+                ..push
+            },
+        ),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
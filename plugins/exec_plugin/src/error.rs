@@ -0,0 +1,85 @@
+use formatter::{FormatError, NewFormatterError};
+use jinja_renderer::{NewJinjaRendererError, RenderError};
+use push_definitions::{ErrorClass, PushError};
+use std::{io::ErrorKind, process::ExitStatus, time::Duration};
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum NewExecPluginError {
+    #[error("Failed to create template: {0}")]
+    Template(
+        #[source]
+        #[from]
+        NewJinjaRendererError,
+    ),
+    #[error("Failed to create formatter: {0}")]
+    Formatter(
+        #[source]
+        #[from]
+        NewFormatterError,
+    ),
+    #[error("max_concurrency must be at least 1")]
+    NoConcurrency,
+    #[error("label_env_prefix must not be empty, labels could override any environment variable")]
+    EmptyLabelEnvPrefix,
+}
+
+#[derive(ThisError, Debug)]
+pub enum InternalPushError {
+    #[error("Failed to render template: {0}")]
+    Render(
+        #[source]
+        #[from]
+        RenderError,
+    ),
+    #[error("Failed to format: {0}")]
+    Format(
+        #[source]
+        #[from]
+        FormatError,
+    ),
+    #[error("Failed to spawn command: command: {command}, {error}")]
+    Spawn {
+        command: String,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("Failed to run command: {0}")]
+    Io(
+        #[source]
+        #[from]
+        std::io::Error,
+    ),
+    #[error("Command failed: {status}, stderr: {stderr}")]
+    Exit {
+        status: ExitStatus,
+        stderr: String,
+        class: ErrorClass,
+    },
+    #[error("Command timed out after {timeout:?}, stderr: {stderr}")]
+    Timeout { timeout: Duration, stderr: String },
+}
+
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InternalPushError::Render(_) | InternalPushError::Format(_) => ErrorClass::Permanent,
+            InternalPushError::Spawn { error, .. }
+                if matches!(
+                    error.kind(),
+                    ErrorKind::NotFound | ErrorKind::PermissionDenied
+                ) =>
+            {
+                ErrorClass::Permanent
+            }
+            InternalPushError::Exit { class, .. } => *class,
+            _ => ErrorClass::Transient,
+        }
+    }
+}
+
+impl From<InternalPushError> for PushError {
+    fn from(error: InternalPushError) -> Self {
+        Self::new(error.class(), error)
+    }
+}
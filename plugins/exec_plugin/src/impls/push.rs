@@ -0,0 +1,203 @@
+use crate::{error::InternalPushError, is_protected_env_name, label_env_name, ExecPlugin};
+use async_trait::async_trait;
+use models::AlertmanagerPush;
+use plugins_definitions::Plugin;
+use push_definitions::{ErrorClass, InitializeError, Push, PushError, PushOutcome};
+use std::{io::ErrorKind, process::Stdio, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    process::Command,
+    task::JoinHandle,
+};
+
+/// Output kept from stdout and stderr. The rest is read and discarded
+const MAX_OUTPUT_BYTES: usize = 4 * 1024;
+
+/// Time for the output readers to finish after the command was killed
+const KILL_GRACE: Duration = Duration::from_millis(500);
+
+/// Reads until the end, keeping at most [`MAX_OUTPUT_BYTES`].
+///
+/// Reading everything keeps a chatty command from blocking on a full pipe.
+async fn read_limited(mut reader: impl AsyncRead + Unpin) -> Vec<u8> {
+    let mut output = vec![];
+    let mut buffer = [0; 1024];
+
+    while let Ok(read @ 1..) = reader.read(&mut buffer).await {
+        let keep = read.min(MAX_OUTPUT_BYTES - output.len());
+        output.extend_from_slice(&buffer[..keep]);
+    }
+
+    output
+}
+
+fn output_to_string(output: &[u8]) -> String {
+    String::from_utf8_lossy(output).trim().to_string()
+}
+
+fn spawn_reader(reader: Option<impl AsyncRead + Unpin + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    tokio::spawn(async move {
+        match reader {
+            Some(reader) => read_limited(reader).await,
+            None => vec![],
+        }
+    })
+}
+
+impl ExecPlugin {
+    fn command(&self, alertmanager_push: &AlertmanagerPush) -> Result<Command, InternalPushError> {
+        let mut command = Command::new(&self.config.command);
+
+        for arg in self.args.iter() {
+            command.arg(arg.render(alertmanager_push)?);
+        }
+
+        if self.config.clear_env {
+            command.env_clear();
+        }
+
+        if let Some(prefix) = &self.config.label_env_prefix {
+            for (label, value) in alertmanager_push.common_labels.iter() {
+                let name = label_env_name(prefix, label);
+                if is_protected_env_name(&name) {
+                    tracing::warn!(%label, %name, "Label would set a protected environment variable. Skipping.");
+                    continue;
+                }
+
+                command.env(name, value);
+            }
+        }
+
+        for (name, value) in self.env.iter() {
+            command.env(name, value.render(alertmanager_push)?);
+        }
+
+        if let Some(working_dir) = &self.config.working_dir {
+            command.current_dir(working_dir);
+        }
+
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        Ok(command)
+    }
+
+    async fn push_alert_with_internal_error(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<(), InternalPushError> {
+        let input = self.formatter.format(alertmanager_push)?.into_bytes();
+        let mut command = self.command(alertmanager_push)?;
+
+        let _slot = self
+            .slots
+            .acquire()
+            .await
+            .expect("semaphore is never closed");
+
+        let mut child = command.spawn().map_err(|error| InternalPushError::Spawn {
+            command: self.config.command.clone(),
+            error,
+        })?;
+
+        let mut stdin = child.stdin.take();
+        let mut stdin_task = tokio::spawn(async move {
+            let Some(stdin) = stdin.as_mut() else {
+                return Ok(());
+            };
+
+            match stdin.write_all(&input).await {
+                // The command does not read its input
+                Err(error) if error.kind() == ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            }
+        });
+        let mut stdout_task = spawn_reader(child.stdout.take());
+        let mut stderr_task = spawn_reader(child.stderr.take());
+
+        let timeout = Duration::from_millis(self.config.timeout_millis);
+        let finished = tokio::time::timeout(timeout, async {
+            let status = child.wait().await?;
+            let stdin = (&mut stdin_task).await.map_err(std::io::Error::other)?;
+            let stdout = (&mut stdout_task).await.map_err(std::io::Error::other)?;
+            let stderr = (&mut stderr_task).await.map_err(std::io::Error::other)?;
+
+            Ok::<_, std::io::Error>((status, stdin, stdout, stderr))
+        })
+        .await;
+
+        let Ok(finished) = finished else {
+            let _ = child.kill().await;
+            stdin_task.abort();
+            stdout_task.abort();
+
+            let stderr = tokio::time::timeout(KILL_GRACE, &mut stderr_task)
+                .await
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            stderr_task.abort();
+
+            return Err(InternalPushError::Timeout {
+                timeout,
+                stderr: output_to_string(&stderr),
+            });
+        };
+
+        let (status, stdin, stdout, stderr) = finished?;
+
+        if !stdout.is_empty() {
+            tracing::debug!(
+                stdout = output_to_string(&stdout),
+                "Command wrote to stdout."
+            );
+        }
+
+        if !status.success() {
+            let class = match status.code() {
+                Some(code) if self.config.permanent_exit_codes.contains(&code) => {
+                    ErrorClass::Permanent
+                }
+                _ => ErrorClass::Transient,
+            };
+
+            return Err(InternalPushError::Exit {
+                status,
+                stderr: output_to_string(&stderr),
+                class,
+            });
+        }
+
+        stdin?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Push for ExecPlugin {
+    #[tracing::instrument(name = "push_initialize", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn initialize(&mut self) -> Result<(), InitializeError> {
+        tracing::trace!("Initializing.");
+
+        tracing::trace!("Successfully initialized.");
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
+        tracing::trace!("Pushing.");
+
+        self.push_alert_with_internal_error(alertmanager_push)
+            .await?;
+
+        tracing::trace!("Successfully pushed.");
+        Ok(PushOutcome::Pushed)
+    }
+}
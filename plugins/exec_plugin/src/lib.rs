@@ -0,0 +1,300 @@
+use error::NewExecPluginError;
+use formatter::{Formatter, FormatterConfig};
+use jinja_renderer::JinjaRenderer;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+use tokio::sync::Semaphore;
+
+mod error;
+mod impls;
+
+/// Variables that decide which programs and libraries are loaded or how a shell starts.
+/// Labels never set them, whatever the prefix
+const PROTECTED_ENV_NAMES: [&str; 5] = ["PATH", "IFS", "ENV", "BASH_ENV", "SHELLOPTS"];
+const PROTECTED_ENV_PREFIXES: [&str; 2] = ["LD_", "DYLD_"];
+
+fn default_timeout_millis() -> u64 {
+    30_000
+}
+
+fn default_max_concurrency() -> usize {
+    4
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Configuration for the Exec plugin
+pub struct ExecPluginConfig {
+    /// Program to run. Looked up in `PATH` if it is not a path
+    pub command: String,
+    /// Jinja templates for the arguments, rendered with `push`
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Jinja templates for environment variables, rendered with `push`
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Exports every common label as an environment variable with this prefix.
+    ///
+    /// With `ALERT_`, `severity` becomes `ALERT_SEVERITY`.
+    /// Names are upper-cased and characters other than letters, digits and `_` are replaced with `_`.
+    /// Must not be empty. Labels that would set `PATH`, `LD_*` or other variables
+    /// deciding how the command is loaded are skipped.
+    pub label_env_prefix: Option<String>,
+    /// Starts the command with only the configured variables instead of the server's environment
+    #[serde(default)]
+    pub clear_env: bool,
+    /// Working directory of the command. The server's if not set
+    pub working_dir: Option<PathBuf>,
+    /// Formatting configuration for stdin
+    pub formatter_config: FormatterConfig,
+    /// The command is killed after this time
+    #[serde(default = "default_timeout_millis")]
+    pub timeout_millis: u64,
+    /// Commands running at the same time. Further pushes wait for a free slot
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Exit codes that fail permanently, so the push is not retried.
+    /// Other non-zero exit codes fail transiently
+    #[serde(default)]
+    pub permanent_exit_codes: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Metadata for the Exec plugin
+pub struct ExecPluginMeta {
+    /// Name of the plugin
+    pub name: String,
+    /// Group of the plugin
+    pub group: String,
+}
+
+/// The Exec plugin
+///
+/// Runs a command for every push and writes the formatted push to its stdin.
+pub struct ExecPlugin {
+    /// Meta information for the plugin
+    meta: ExecPluginMeta,
+    /// Configuration for the plugin
+    config: ExecPluginConfig,
+    /// Render the arguments
+    args: Vec<JinjaRenderer>,
+    /// Render the environment variables
+    env: Vec<(String, JinjaRenderer)>,
+    /// Formats stdin
+    formatter: Formatter,
+    /// Limits the commands running at the same time
+    slots: Semaphore,
+}
+
+impl ExecPlugin {
+    pub async fn new(
+        meta: ExecPluginMeta,
+        config: ExecPluginConfig,
+    ) -> Result<Self, NewExecPluginError> {
+        if config.max_concurrency == 0 {
+            return Err(NewExecPluginError::NoConcurrency);
+        }
+
+        if config.label_env_prefix.as_deref() == Some("") {
+            return Err(NewExecPluginError::EmptyLabelEnvPrefix);
+        }
+
+        let formatter = Formatter::new(config.formatter_config.clone()).await?;
+        let extensions = formatter.jinja_extensions();
+
+        let mut args = vec![];
+        for arg in config.args.iter() {
+            args.push(JinjaRenderer::new_from_str_with_extensions(arg.clone(), extensions).await?);
+        }
+
+        let mut env = vec![];
+        for (name, value) in config.env.iter() {
+            let value =
+                JinjaRenderer::new_from_str_with_extensions(value.clone(), extensions).await?;
+            env.push((name.clone(), value));
+        }
+
+        let slots = Semaphore::new(config.max_concurrency);
+
+        Ok(Self {
+            meta,
+            config,
+            args,
+            env,
+            formatter,
+            slots,
+        })
+    }
+}
+
+/// `team.name` with prefix `ALERT_` -> `ALERT_TEAM_NAME`
+fn label_env_name(prefix: &str, label: &str) -> String {
+    let label = label
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect::<String>();
+
+    format!("{prefix}{label}")
+}
+
+fn is_protected_env_name(name: &str) -> bool {
+    PROTECTED_ENV_NAMES.contains(&name)
+        || PROTECTED_ENV_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use formatter::FormatType;
+    use models::{Alert, AlertmanagerPush, Status};
+    use push_definitions::{ErrorClass, Push, PushOutcome};
+
+    fn config(script: &str) -> ExecPluginConfig {
+        ExecPluginConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: BTreeMap::new(),
+            label_env_prefix: None,
+            clear_env: false,
+            working_dir: None,
+            formatter_config: FormatterConfig::new(FormatType::Json),
+            timeout_millis: default_timeout_millis(),
+            max_concurrency: default_max_concurrency(),
+            permanent_exit_codes: vec![],
+        }
+    }
+
+    async fn plugin(config: ExecPluginConfig) -> ExecPlugin {
+        let meta = ExecPluginMeta {
+            name: "exec".to_string(),
+            group: "test".to_string(),
+        };
+
+        ExecPlugin::new(meta, config)
+            .await
+            .expect("failed to create plugin")
+    }
+
+    fn push() -> AlertmanagerPush {
+        AlertmanagerPush {
+            group_key: "{}:{alertname=\"disk\"}".to_string(),
+            status: Status::Firing,
+            common_labels: [
+                ("severity".to_string(), "critical".to_string()),
+                ("team.name".to_string(), "ops".to_string()),
+            ]
+            .into(),
+            alerts: vec![Alert {
+                fingerprint: "a".to_string(),
+                status: Status::Firing,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn pipe_push_to_stdin() {
+        let dir = tempfile::tempdir().expect("failed to create dir");
+
+        let plugin = plugin(ExecPluginConfig {
+            env: [("STATUS".to_string(), "{{ push.status }}".to_string())].into(),
+            label_env_prefix: Some("ALERT_".to_string()),
+            working_dir: Some(dir.path().to_path_buf()),
+            ..config(
+                r#"test "$STATUS" = firing && test "$ALERT_SEVERITY" = critical && test "$ALERT_TEAM_NAME" = ops && cat > push.json"#,
+            )
+        })
+        .await;
+
+        let outcome = plugin.push_alert(&push()).await.expect("push failed");
+        assert_eq!(outcome, PushOutcome::Pushed);
+
+        let written = std::fs::read_to_string(dir.path().join("push.json")).expect("no output");
+        let written: AlertmanagerPush = serde_json::from_str(&written).expect("invalid push");
+        assert_eq!(written, push());
+    }
+
+    #[tokio::test]
+    async fn labels_cannot_override_path() {
+        let meta = ExecPluginMeta {
+            name: "exec".to_string(),
+            group: "test".to_string(),
+        };
+        let result = ExecPlugin::new(
+            meta,
+            ExecPluginConfig {
+                label_env_prefix: Some(String::new()),
+                ..config("true")
+            },
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(NewExecPluginError::EmptyLabelEnvPrefix)
+        ));
+
+        // `PA` and `th` make `PATH`
+        let plugin = plugin(ExecPluginConfig {
+            label_env_prefix: Some("PA".to_string()),
+            ..config(r#"test "$PATH" != /tmp/evil && test "$PASEVERITY" = critical"#)
+        })
+        .await;
+
+        let mut push = push();
+        push.common_labels
+            .insert("th".to_string(), "/tmp/evil".to_string());
+
+        let outcome = plugin.push_alert(&push).await.expect("push failed");
+        assert_eq!(outcome, PushOutcome::Pushed);
+    }
+
+    #[tokio::test]
+    async fn report_exit_code_and_stderr() {
+        let permanent_plugin = plugin(ExecPluginConfig {
+            permanent_exit_codes: vec![3],
+            ..config("echo 'no route to pager' >&2; exit 3")
+        })
+        .await;
+
+        let error = permanent_plugin
+            .push_alert(&push())
+            .await
+            .expect_err("push succeeded");
+        assert_eq!(error.class, ErrorClass::Permanent);
+        assert!(error.to_string().contains("no route to pager"), "{error}");
+
+        let transient_plugin = plugin(config("exit 1")).await;
+
+        let error = transient_plugin
+            .push_alert(&push())
+            .await
+            .expect_err("push succeeded");
+        assert_eq!(error.class, ErrorClass::Transient);
+    }
+
+    #[tokio::test]
+    async fn kill_on_timeout() {
+        let plugin = plugin(ExecPluginConfig {
+            timeout_millis: 100,
+            ..config("echo started >&2; exec sleep 10")
+        })
+        .await;
+
+        let started = std::time::Instant::now();
+        let error = plugin
+            .push_alert(&push())
+            .await
+            .expect_err("push succeeded");
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(error.class, ErrorClass::Transient);
+        assert!(error.to_string().contains("timed out"), "{error}");
+        assert!(error.to_string().contains("started"), "{error}");
+    }
+}
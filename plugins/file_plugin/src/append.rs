@@ -0,0 +1,465 @@
+//! Appending pushes to a current file, with rotation, compression and retention
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::{io::AsyncWriteExt, sync::Mutex};
+
+fn default_file_name() -> String {
+    String::from("current")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Configuration for appending to a current file
+pub struct AppendConfig {
+    /// Name of the current file, without extension. Rotated files keep it as prefix
+    #[serde(default = "default_file_name")]
+    pub file_name: String,
+    #[serde(default)]
+    pub rotation: RotationConfig,
+    /// Compresses rotated files
+    pub compression: Option<Compression>,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+/// When the current file is rotated
+pub struct RotationConfig {
+    /// Rotates before the current file would exceed this size
+    pub max_bytes: Option<u64>,
+    /// Rotates when the hour or day changes, in UTC
+    pub interval: Option<RotationInterval>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationInterval {
+    Hourly,
+    Daily,
+}
+
+impl RotationInterval {
+    /// Identifies the hour or day the time falls into
+    fn period(&self, time: DateTime<Utc>) -> String {
+        match self {
+            RotationInterval::Hourly => time.format("%Y%m%d%H").to_string(),
+            RotationInterval::Daily => time.format("%Y%m%d").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    fn compressed_path(&self, path: &Path) -> PathBuf {
+        let mut compressed_path = path.as_os_str().to_owned();
+        compressed_path.push(".");
+        compressed_path.push(self.extension());
+        PathBuf::from(compressed_path)
+    }
+
+    /// Compresses the file next to it and removes the original
+    fn compress(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let compressed_path = self.compressed_path(path);
+
+        let mut input = std::fs::File::open(path)?;
+        let output = std::fs::File::create(&compressed_path)?;
+
+        // Leaves no partial compressed file behind, the original is kept
+        if let Err(error) = self.encode(&mut input, output) {
+            let _ = std::fs::remove_file(&compressed_path);
+            return Err(error);
+        }
+
+        std::fs::remove_file(path)?;
+
+        Ok(compressed_path)
+    }
+
+    fn encode(&self, input: &mut std::fs::File, output: std::fs::File) -> std::io::Result<()> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(output, flate2::Compression::default());
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish()?.sync_all()
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(output, 0)?;
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish()?.sync_all()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+/// Which rotated files are kept
+pub struct RetentionConfig {
+    /// Keeps at most this many rotated files
+    pub max_files: Option<usize>,
+    /// Removes rotated files older than this
+    pub max_age_seconds: Option<u64>,
+}
+
+/// State of the current file
+#[derive(Default)]
+struct Current {
+    /// Size, if known
+    size: Option<u64>,
+    /// Period the current file was started in
+    period: Option<String>,
+}
+
+/// Appends to `<dir>/<file_name>.<extension>`.
+///
+/// Rotated files are named `<file_name>.<timestamp>-<n>.<extension>`,
+/// so sorting them by name sorts them by age.
+pub(crate) struct AppendWriter {
+    config: AppendConfig,
+    dir_path: PathBuf,
+    extension: String,
+    current: Mutex<Current>,
+}
+
+impl AppendWriter {
+    pub(crate) fn new(config: AppendConfig, dir_path: PathBuf, extension: String) -> Self {
+        Self {
+            config,
+            dir_path,
+            extension,
+            current: Mutex::default(),
+        }
+    }
+
+    fn current_path(&self) -> PathBuf {
+        self.dir_path
+            .join(format!("{}.{}", self.config.file_name, self.extension))
+    }
+
+    /// Appends the contents as a line, rotating the current file first if needed
+    pub(crate) async fn append(&self, contents: &str) -> std::io::Result<()> {
+        self.append_at(Utc::now(), contents).await
+    }
+
+    async fn append_at(&self, now: DateTime<Utc>, contents: &str) -> std::io::Result<()> {
+        let mut line = contents.to_string();
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+
+        let mut current = self.current.lock().await;
+        let current_path = self.current_path();
+
+        if current.size.is_none() {
+            match tokio::fs::metadata(&current_path).await {
+                Ok(metadata) => {
+                    current.size = Some(metadata.len());
+                    current.period = self.config.rotation.interval.map(|interval| {
+                        let modified = metadata.modified().map(DateTime::<Utc>::from);
+                        interval.period(modified.unwrap_or(now))
+                    });
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                    current.size = Some(0);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        let size = current.size.unwrap_or_default();
+        let period = self
+            .config
+            .rotation
+            .interval
+            .map(|interval| interval.period(now));
+
+        let too_large = self
+            .config
+            .rotation
+            .max_bytes
+            .is_some_and(|max_bytes| size + line.len() as u64 > max_bytes);
+        let new_period = current.period.is_some() && current.period != period;
+
+        if size > 0 && (too_large || new_period) {
+            let rotated_path = self.rotate(now).await?;
+            current.size = Some(0);
+            current.period = period.clone();
+
+            self.compress_and_retain(rotated_path).await;
+        }
+
+        if current.size == Some(0) {
+            current.period = period;
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&current_path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+
+        current.size = Some(current.size.unwrap_or_default() + line.len() as u64);
+
+        Ok(())
+    }
+
+    /// Waits for a running append and syncs the current file to disk
+    pub(crate) async fn sync(&self) -> std::io::Result<()> {
+        let _current = self.current.lock().await;
+
+        match tokio::fs::File::open(self.current_path()).await {
+            Ok(file) => file.sync_all().await,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Renames the current file to a free rotated name
+    async fn rotate(&self, now: DateTime<Utc>) -> std::io::Result<PathBuf> {
+        let stamp = now.format("%Y%m%dT%H%M%SZ");
+
+        let mut n = 0;
+        let rotated_path = loop {
+            let path = self.dir_path.join(format!(
+                "{}.{}-{:03}.{}",
+                self.config.file_name, stamp, n, self.extension
+            ));
+
+            let compressed_exists = self
+                .config
+                .compression
+                .is_some_and(|compression| compression.compressed_path(&path).exists());
+
+            if !path.exists() && !compressed_exists {
+                break path;
+            }
+            n += 1;
+        };
+
+        tokio::fs::rename(self.current_path(), &rotated_path).await?;
+        tracing::debug!(path = %rotated_path.display(), "Rotated file.");
+
+        Ok(rotated_path)
+    }
+
+    /// Compresses the rotated file and applies the retention.
+    ///
+    /// Both are best effort: the file was rotated, so failures are logged and appending goes on.
+    async fn compress_and_retain(&self, rotated_path: PathBuf) {
+        let compression = self.config.compression;
+        let retention = self.config.retention.clone();
+        let dir_path = self.dir_path.clone();
+        let prefix = format!("{}.", self.config.file_name);
+        let current_name = format!("{}.{}", self.config.file_name, self.extension);
+
+        let result = tokio::task::spawn_blocking(move || {
+            if let Some(compression) = compression {
+                if let Err(error) = compression.compress(&rotated_path) {
+                    tracing::error!(%error, path = %rotated_path.display(), "Failed to compress rotated file.");
+                }
+            }
+
+            if let Err(error) = apply_retention(&retention, &dir_path, &prefix, &current_name) {
+                tracing::error!(%error, "Failed to apply retention.");
+            }
+        })
+        .await;
+
+        if let Err(error) = result {
+            tracing::error!(%error, "Failed to compress and retain rotated files.");
+        }
+    }
+}
+
+/// Removes the oldest rotated files beyond `max_files` and those older than `max_age_seconds`
+fn apply_retention(
+    retention: &RetentionConfig,
+    dir_path: &Path,
+    prefix: &str,
+    current_name: &str,
+) -> std::io::Result<()> {
+    if retention.max_files.is_none() && retention.max_age_seconds.is_none() {
+        return Ok(());
+    }
+
+    let mut rotated = vec![];
+    for entry in std::fs::read_dir(dir_path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name.starts_with(prefix) && name != current_name && entry.file_type()?.is_file() {
+            rotated.push((name, entry.path()));
+        }
+    }
+
+    // Oldest first
+    rotated.sort();
+
+    let mut remove = vec![];
+
+    if let Some(max_files) = retention.max_files {
+        let excess = rotated.len().saturating_sub(max_files);
+        remove.extend(rotated.drain(..excess).map(|(_, path)| path));
+    }
+
+    if let Some(max_age_seconds) = retention.max_age_seconds {
+        let max_age = Duration::from_secs(max_age_seconds);
+        for (_, path) in rotated {
+            let modified = std::fs::metadata(&path)?.modified()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+
+            if age > max_age {
+                remove.push(path);
+            }
+        }
+    }
+
+    for path in remove {
+        std::fs::remove_file(&path)?;
+        tracing::debug!(path = %path.display(), "Removed rotated file.");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use std::io::Read;
+
+    fn read_gzip(path: &Path) -> String {
+        let mut contents = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(path).unwrap())
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    fn files(dir_path: &Path) -> Vec<String> {
+        let mut files = std::fs::read_dir(dir_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[tokio::test]
+    async fn append_rotate_compress_and_retain() {
+        let dir_path = tempfile::tempdir().unwrap();
+        let dir_path = dir_path.path();
+
+        let config: AppendConfig = serde_json::from_value(serde_json::json!({
+            "file_name": "audit",
+            "rotation": { "max_bytes": 12, "interval": "daily" },
+            "compression": "gzip",
+            "retention": { "max_files": 2 },
+        }))
+        .unwrap();
+        let writer = AppendWriter::new(config, dir_path.to_path_buf(), "ndjson".to_string());
+
+        let day = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        writer.append_at(day, "{\"n\":1}").await.unwrap();
+        writer.append_at(day, "{\"n\":2}").await.unwrap();
+        writer.append_at(day, "{\"n\":3}").await.unwrap();
+
+        assert_eq!(
+            files(dir_path),
+            vec![
+                "audit.20240101T100000Z-000.ndjson.gz",
+                "audit.20240101T100000Z-001.ndjson.gz",
+                "audit.ndjson",
+            ]
+        );
+        assert_eq!(
+            read_gzip(&dir_path.join("audit.20240101T100000Z-001.ndjson.gz")),
+            "{\"n\":2}\n"
+        );
+
+        // A new day rotates even though the size fits
+        let next_day = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 1).unwrap();
+        writer.append_at(next_day, "1").await.unwrap();
+
+        assert_eq!(
+            files(dir_path),
+            vec![
+                "audit.20240101T100000Z-001.ndjson.gz",
+                "audit.20240102T000001Z-000.ndjson.gz",
+                "audit.ndjson",
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir_path.join("audit.ndjson")).unwrap(),
+            "1\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failed_compression_keeps_appending() {
+        let dir_path = tempfile::tempdir().unwrap();
+        let dir_path = dir_path.path();
+
+        let config: AppendConfig = serde_json::from_value(serde_json::json!({
+            "file_name": "audit",
+            "rotation": { "max_bytes": 3 },
+            "compression": "gzip",
+        }))
+        .unwrap();
+        let writer = AppendWriter::new(config, dir_path.to_path_buf(), "log".to_string());
+
+        // The dangling link does not count as existing, but cannot be created through
+        let compressed_path = dir_path.join("audit.20240101T100000Z-000.log.gz");
+        std::os::unix::fs::symlink(dir_path.join("missing/audit.gz"), &compressed_path).unwrap();
+
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        writer.append_at(now, "1").await.unwrap();
+        writer.append_at(now, "2").await.unwrap();
+        writer.append_at(now, "3").await.unwrap();
+
+        assert_eq!(
+            files(dir_path),
+            vec![
+                "audit.20240101T100000Z-000.log",
+                "audit.20240101T100000Z-000.log.gz",
+                "audit.20240101T100000Z-001.log.gz",
+                "audit.log",
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir_path.join("audit.20240101T100000Z-000.log")).unwrap(),
+            "1\n"
+        );
+        assert_eq!(
+            read_gzip(&dir_path.join("audit.20240101T100000Z-001.log.gz")),
+            "2\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir_path.join("audit.log")).unwrap(),
+            "3\n"
+        );
+    }
+}
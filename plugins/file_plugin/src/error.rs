@@ -1,6 +1,7 @@
+use crate::PathTemplateError;
 use formatter::{FormatError, NewFormatterError};
 use plugins_definitions::HealthError;
-use push_definitions::{InitializeError, PushError};
+use push_definitions::{ErrorClass, InitializeError, PushError, ShutdownError};
 use std::path::PathBuf;
 use thiserror::Error as ThisError;
 
@@ -12,6 +13,12 @@ pub enum NewFilePluginError {
         #[from]
         NewFormatterError,
     ),
+    #[error("Invalid path template: {0}")]
+    PathTemplate(
+        #[source]
+        #[from]
+        PathTemplateError,
+    ),
 }
 
 #[derive(ThisError, Debug)]
@@ -74,8 +81,33 @@ pub enum InternalPushError {
     ),
 }
 
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InternalPushError::Format(_) => ErrorClass::Permanent,
+            InternalPushError::Write(_) => ErrorClass::Transient,
+        }
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
+        Self::new(error.class(), error)
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum InternalShutdownError {
+    #[error("Failed to sync file: {0}")]
+    Sync(
+        #[source]
+        #[from]
+        tokio::io::Error,
+    ),
+}
+
+impl From<InternalShutdownError> for ShutdownError {
+    fn from(error: InternalShutdownError) -> Self {
         Self {
             error: error.into(),
         }
@@ -1,23 +1,58 @@
 use crate::{
-    error::{InternalInitializeError, InternalPushError},
+    error::{InternalInitializeError, InternalPushError, InternalShutdownError},
     FilePlugin,
 };
 use async_trait::async_trait;
+use chrono::Utc;
 use models::AlertmanagerPush;
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
-use std::path::PathBuf;
+use push_definitions::{InitializeError, Push, PushError, PushOutcome, ShutdownError};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::io::AsyncWriteExt;
+
+/// Writes to a temporary file next to the path and renames it,
+/// so readers never see a partially written file
+async fn write_atomically(file_path: &Path, contents: &str) -> Result<(), tokio::io::Error> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    // Rendered file names leave room for this suffix, see `MAX_TEMP_SUFFIX_BYTES`
+    let mut temp_path = file_path.as_os_str().to_owned();
+    temp_path.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        file.write_all(contents.as_bytes()).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temp_path, file_path).await
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+
+    result
+}
 
 impl FilePlugin {
     fn decide_file_path(&self, alertmanager_push: &AlertmanagerPush) -> PathBuf {
-        let dir_path = &self.config.dir_path;
-        let mut file_path = dir_path.clone();
-        file_path.push(format!(
-            "{}.{}",
-            alertmanager_push.group_key, self.config.extension
-        ));
-
-        file_path
+        let relative_path =
+            self.path_template
+                .render(alertmanager_push, &self.config.extension, Utc::now());
+
+        self.config.dir_path.join(relative_path)
     }
 
     fn initialize_with_internal_error(&mut self) -> Result<(), InternalInitializeError> {
@@ -30,11 +65,24 @@ impl FilePlugin {
         &self,
         alertmanager_push: &AlertmanagerPush,
     ) -> Result<(), InternalPushError> {
+        let contents = self.formatter.format(alertmanager_push)?;
+
+        if let Some(append_writer) = &self.append_writer {
+            append_writer.append(&contents).await?;
+            return Ok(());
+        }
+
         let file_path = self.decide_file_path(alertmanager_push);
 
-        let contents = self.formatter.format(alertmanager_push)?;
+        write_atomically(&file_path, &contents).await?;
+
+        Ok(())
+    }
 
-        tokio::fs::write(file_path, contents).await?;
+    async fn shutdown_with_internal_error(&self) -> Result<(), InternalShutdownError> {
+        if let Some(append_writer) = &self.append_writer {
+            append_writer.sync().await?;
+        }
 
         Ok(())
     }
@@ -53,13 +101,26 @@ impl Push for FilePlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         self.push_alert_with_internal_error(alertmanager_push)
             .await?;
 
         tracing::trace!("Successfully pushed.");
+        Ok(PushOutcome::Pushed)
+    }
+
+    #[tracing::instrument(name = "push_shutdown", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        tracing::trace!("Shutting down.");
+
+        self.shutdown_with_internal_error().await?;
+
+        tracing::trace!("Successfully shut down.");
         Ok(())
     }
 }
@@ -1,11 +1,33 @@
+use append::AppendWriter;
 use error::{DirError, NewFilePluginError};
 use formatter::{Formatter, FormatterConfig};
+use path_template::PathTemplate;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+mod append;
 mod error;
 mod impls;
+mod path_template;
+
+pub use append::{AppendConfig, Compression, RetentionConfig, RotationConfig, RotationInterval};
+pub use path_template::PathTemplateError;
+
+fn default_path_template() -> String {
+    String::from("{receiver}-{hash}.{extension}")
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// How pushes are written
+pub enum WriteMode {
+    /// One file per group, overwritten by every push
+    #[default]
+    Overwrite,
+    /// Every push is appended to a current file, for example as NDJSON
+    Append(AppendConfig),
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 /// Configuration for the File plugin
@@ -14,8 +36,18 @@ pub struct FilePluginConfig {
     pub dir_path: PathBuf,
     /// The type of file to write
     pub extension: String,
+    /// Path of the written files, relative to `dir_path`
+    ///
+    /// Placeholders: `{date}`, `{year}`, `{month}`, `{day}`, `{hour}`, `{receiver}`, `{status}`,
+    /// `{group_key}`, `{hash}` of the group key, `{extension}` and `{label.<name>}`.
+    /// Placeholder values are sanitised. Defaults to `{receiver}-{hash}.{extension}`.
+    /// Not used by the append mode.
+    #[serde(default = "default_path_template")]
+    pub path_template: String,
     /// Formatting configuration
     pub formatter_config: FormatterConfig,
+    #[serde(default)]
+    pub write_mode: WriteMode,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -37,6 +69,10 @@ pub struct FilePlugin {
     config: FilePluginConfig,
     /// Formatter
     formatter: Formatter,
+    /// Path of the written files
+    path_template: PathTemplate,
+    /// Writer for the append mode
+    append_writer: Option<AppendWriter>,
 }
 
 impl FilePlugin {
@@ -45,11 +81,23 @@ impl FilePlugin {
         config: FilePluginConfig,
     ) -> Result<Self, NewFilePluginError> {
         let formatter = Formatter::new(config.formatter_config.clone()).await?;
+        let path_template = config.path_template.parse()?;
+
+        let append_writer = match &config.write_mode {
+            WriteMode::Overwrite => None,
+            WriteMode::Append(append_config) => Some(AppendWriter::new(
+                append_config.clone(),
+                config.dir_path.clone(),
+                config.extension.clone(),
+            )),
+        };
 
         Ok(Self {
             meta,
             config,
             formatter,
+            path_template,
+            append_writer,
         })
     }
 
@@ -0,0 +1,321 @@
+//! Templated file paths like `{date}/{receiver}/{label.alertname}-{hash}.json`
+//!
+//! Placeholder values are sanitised, so they can neither add path segments nor leave the directory.
+//! Segments changed by sanitising or shortening get a hash of their unchanged value appended,
+//! so different values still lead to different paths.
+
+use chrono::{DateTime, Utc};
+use models::AlertmanagerPush;
+use sha2::{Digest, Sha256};
+use std::{path::PathBuf, str::FromStr};
+use thiserror::Error as ThisError;
+
+/// Longest file name most file systems accept
+const MAX_SEGMENT_BYTES: usize = 255;
+
+/// Longest temporary suffix `.{pid}-{n}.tmp` added to file names by atomic writes
+pub(crate) const MAX_TEMP_SUFFIX_BYTES: usize = ".4294967295-18446744073709551615.tmp".len();
+
+#[derive(ThisError, Debug, PartialEq, Eq)]
+pub enum PathTemplateError {
+    #[error("Unknown placeholder: {{{0}}}")]
+    UnknownPlaceholder(String),
+    #[error("Unclosed placeholder")]
+    UnclosedPlaceholder,
+    #[error(
+        "Path must be relative to the directory and must not contain empty, `.` or `..` segments"
+    )]
+    InvalidSegment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Placeholder {
+    /// `2024-01-31`
+    Date,
+    Year,
+    Month,
+    Day,
+    Hour,
+    Receiver,
+    Status,
+    GroupKey,
+    /// Hash of the group key
+    Hash,
+    Extension,
+    /// Common label, or group label
+    Label(String),
+}
+
+impl FromStr for Placeholder {
+    type Err = PathTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let placeholder = match s {
+            "date" => Placeholder::Date,
+            "year" => Placeholder::Year,
+            "month" => Placeholder::Month,
+            "day" => Placeholder::Day,
+            "hour" => Placeholder::Hour,
+            "receiver" => Placeholder::Receiver,
+            "status" => Placeholder::Status,
+            "group_key" => Placeholder::GroupKey,
+            "hash" => Placeholder::Hash,
+            "extension" => Placeholder::Extension,
+            _ => match s.strip_prefix("label.") {
+                Some(name) if !name.is_empty() => Placeholder::Label(name.to_string()),
+                _ => return Err(PathTemplateError::UnknownPlaceholder(s.to_string())),
+            },
+        };
+
+        Ok(placeholder)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PathTemplate {
+    /// Parts of each path segment
+    segments: Vec<Vec<Part>>,
+}
+
+impl FromStr for PathTemplate {
+    type Err = PathTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = vec![];
+
+        for segment in s.split('/') {
+            if segment.is_empty() || segment == "." || segment == ".." || segment.contains('\\') {
+                return Err(PathTemplateError::InvalidSegment);
+            }
+
+            let mut parts = vec![];
+            let mut rest = segment;
+            while let Some(start) = rest.find('{') {
+                if start > 0 {
+                    parts.push(Part::Literal(rest[..start].to_string()));
+                }
+
+                let end = rest[start..]
+                    .find('}')
+                    .ok_or(PathTemplateError::UnclosedPlaceholder)?;
+                parts.push(Part::Placeholder(rest[start + 1..start + end].parse()?));
+                rest = &rest[start + end + 1..];
+            }
+
+            if !rest.is_empty() {
+                parts.push(Part::Literal(rest.to_string()));
+            }
+
+            segments.push(parts);
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl PathTemplate {
+    /// Path relative to the directory
+    pub(crate) fn render(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+        extension: &str,
+        now: DateTime<Utc>,
+    ) -> PathBuf {
+        let last = self.segments.len() - 1;
+
+        self.segments
+            .iter()
+            .enumerate()
+            .map(|(index, parts)| {
+                let mut raw = String::new();
+                let mut segment = String::new();
+                for part in parts.iter() {
+                    match part {
+                        Part::Literal(literal) => {
+                            raw.push_str(literal);
+                            segment.push_str(literal);
+                        }
+                        Part::Placeholder(placeholder) => {
+                            let value = value(placeholder, alertmanager_push, extension, now);
+                            segment.push_str(&sanitize(&value));
+                            raw.push_str(&value);
+                        }
+                    }
+                }
+
+                // The file name keeps room for the temporary suffix
+                let max_bytes = match index == last {
+                    true => MAX_SEGMENT_BYTES - MAX_TEMP_SUFFIX_BYTES,
+                    false => MAX_SEGMENT_BYTES,
+                };
+                // A segment with the hash of the group key is already unique per group
+                let hashed = parts.contains(&Part::Placeholder(Placeholder::Hash));
+
+                confine(&raw, segment, hashed, extension, max_bytes)
+            })
+            .collect()
+    }
+}
+
+fn value(
+    placeholder: &Placeholder,
+    alertmanager_push: &AlertmanagerPush,
+    extension: &str,
+    now: DateTime<Utc>,
+) -> String {
+    match placeholder {
+        Placeholder::Date => now.format("%Y-%m-%d").to_string(),
+        Placeholder::Year => now.format("%Y").to_string(),
+        Placeholder::Month => now.format("%m").to_string(),
+        Placeholder::Day => now.format("%d").to_string(),
+        Placeholder::Hour => now.format("%H").to_string(),
+        Placeholder::Receiver => alertmanager_push.receiver.clone(),
+        Placeholder::Status => alertmanager_push.status.to_string(),
+        Placeholder::GroupKey => alertmanager_push.group_key.clone(),
+        Placeholder::Hash => short_hash(&alertmanager_push.group_key),
+        Placeholder::Extension => extension.to_string(),
+        Placeholder::Label(name) => alertmanager_push
+            .common_labels
+            .get(name)
+            .or_else(|| alertmanager_push.group_labels.get(name))
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+/// Replaces everything but ASCII letters, digits, `.`, `_` and `-` with `_`
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// First 8 bytes of the SHA-256 of the value, in hex
+fn short_hash(value: &str) -> String {
+    hex::encode(&Sha256::digest(value.as_bytes())[..8])
+}
+
+/// Keeps a rendered segment a single, normal path component of at most `max_bytes`.
+///
+/// If that changes the segment, the hash of the `raw` segment is appended,
+/// before a trailing `.<extension>`, which is kept.
+fn confine(raw: &str, segment: String, hashed: bool, extension: &str, max_bytes: usize) -> String {
+    let segment = match segment.as_str() {
+        "" | "." | ".." => String::from("_"),
+        _ => segment,
+    };
+
+    if segment.len() <= max_bytes && (segment == raw || hashed) {
+        return segment;
+    }
+
+    let suffix = format!(".{}", extension);
+    let (stem, suffix) = match segment.strip_suffix(&suffix) {
+        Some(stem) if !stem.is_empty() => (stem, suffix.as_str()),
+        _ => (segment.as_str(), ""),
+    };
+    let hash = short_hash(raw);
+
+    let mut end = max_bytes
+        .saturating_sub(suffix.len() + 1 + hash.len())
+        .min(stem.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{}-{}{}", &stem[..end], hash, suffix)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use models::Status;
+    use std::path::{Component, Path};
+
+    #[test]
+    fn render_path() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 31, 9, 0, 0).unwrap();
+        let push = AlertmanagerPush {
+            receiver: "team/a".to_string(),
+            status: Status::Firing,
+            group_key: "{}/{alertname=\"x\"}:{instance=\"../../etc\"}".to_string(),
+            common_labels: [("alertname".to_string(), "..".to_string())].into(),
+            group_labels: [("team".to_string(), "ops".to_string())].into(),
+            ..Default::default()
+        };
+
+        let template: PathTemplate =
+            "{date}/{receiver}/{label.alertname}/{label.team}-{label.missing}-{hash}.{extension}"
+                .parse()
+                .unwrap();
+        let path = template.render(&push, "json", now);
+
+        // Sanitised segments get the hash of their value, those with `{hash}` already have one
+        assert_eq!(
+            path,
+            Path::new(&format!(
+                "2024-01-31/team_a-{}/_-{}/ops--{}.json",
+                short_hash("team/a"),
+                short_hash(".."),
+                short_hash(&push.group_key)
+            ))
+        );
+
+        let path: PathTemplate = "{group_key}.{extension}".parse().unwrap();
+        let path = path.render(&push, "json", now);
+        assert_eq!(path.components().count(), 1);
+        assert!(path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_))));
+
+        // Long values are shortened, keeping room for the temporary suffix and the extension
+        let long = |value: &str| AlertmanagerPush {
+            common_labels: [("name".to_string(), value.repeat(300))].into(),
+            ..Default::default()
+        };
+        let path: PathTemplate = "{label.name}/{label.name}.{extension}".parse().unwrap();
+        let a = path.render(&long("a"), "json", now);
+        let b = path.render(&long("b"), "json", now);
+
+        let directory = a.parent().unwrap().to_str().unwrap();
+        assert_eq!(directory.len(), MAX_SEGMENT_BYTES);
+        assert!(directory.ends_with(&short_hash(&"a".repeat(300))));
+
+        let file_name = a.file_name().unwrap().to_str().unwrap();
+        assert_eq!(file_name.len() + MAX_TEMP_SUFFIX_BYTES, MAX_SEGMENT_BYTES);
+        assert!(file_name.ends_with(&format!(
+            "-{}.json",
+            short_hash(&format!("{}.json", "a".repeat(300)))
+        )));
+        assert_ne!(a.file_name(), b.file_name());
+
+        assert_eq!(
+            "{unknown}".parse::<PathTemplate>().err(),
+            Some(PathTemplateError::UnknownPlaceholder("unknown".to_string()))
+        );
+        assert_eq!(
+            "{date".parse::<PathTemplate>().err(),
+            Some(PathTemplateError::UnclosedPlaceholder)
+        );
+        for invalid in ["/abs/{hash}", "../{hash}", "a//b", "a/./b"] {
+            assert_eq!(
+                invalid.parse::<PathTemplate>().err(),
+                Some(PathTemplateError::InvalidSegment)
+            );
+        }
+    }
+}
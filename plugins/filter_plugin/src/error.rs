@@ -1,4 +1,4 @@
-use push_definitions::PushError;
+use push_definitions::{ErrorClass, PushError};
 use regex::Error as RegexError;
 use thiserror::Error as ThisError;
 
@@ -10,6 +10,12 @@ pub enum NewFilterPluginError {
         #[from]
         RegexError,
     ),
+    #[error("Failed to create http client: {0}")]
+    HttpClient(
+        #[source]
+        #[from]
+        http_client::NewHttpClientError,
+    ),
 }
 
 #[derive(ThisError, Debug)]
@@ -29,10 +35,24 @@ pub enum InternalPushError {
     },
 }
 
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InternalPushError::Reqwest(error) if http_client::is_transient_error(error) => {
+                ErrorClass::Transient
+            }
+            InternalPushError::ErrorResponse { status_code, .. }
+                if http_client::is_transient_status(*status_code) =>
+            {
+                ErrorClass::Transient
+            }
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
-        Self {
-            error: error.into(),
-        }
+        Self::new(error.class(), error)
     }
 }
@@ -2,27 +2,55 @@ use crate::{error::InternalPushError, FilterPlugin};
 use async_trait::async_trait;
 use models::AlertmanagerPush;
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
+use push_definitions::{AlertOutcome, AlertResult, InitializeError, Push, PushError, PushOutcome};
 
 impl FilterPlugin {
     async fn push_alert_with_internal_error(
         &self,
         alertmanager_push: &AlertmanagerPush,
-    ) -> Result<(), InternalPushError> {
+    ) -> Result<PushOutcome, InternalPushError> {
         if self.is_signature_present(alertmanager_push) {
             tracing::warn!("Signature present. Loop detected.");
             return Err(InternalPushError::LoopDetected)?;
         }
 
-        let mut alertmanager_push = self.filter(alertmanager_push);
+        let mut filtered_push = self.filter(alertmanager_push);
 
-        self.add_signature(&mut alertmanager_push);
+        if filtered_push.alerts.is_empty() {
+            tracing::trace!("All alerts were dropped. Nothing to forward.");
+            return Ok(PushOutcome::Skipped {
+                reason: String::from("All alerts were dropped"),
+            });
+        }
+
+        let results = alertmanager_push
+            .alerts
+            .iter()
+            .map(|alert| {
+                let forwarded = filtered_push
+                    .alerts
+                    .iter()
+                    .any(|filtered| filtered.fingerprint == alert.fingerprint);
+
+                AlertResult {
+                    fingerprint: alert.fingerprint.clone(),
+                    outcome: match forwarded {
+                        true => AlertOutcome::Pushed,
+                        false => AlertOutcome::Skipped {
+                            reason: String::from("Dropped by filter"),
+                        },
+                    },
+                }
+            })
+            .collect();
 
-        let response = reqwest::Client::new()
+        self.add_signature(&mut filtered_push);
+
+        let request = self
+            .client
             .post(self.config.webhook_url.clone())
-            .json(&alertmanager_push)
-            .send()
-            .await?;
+            .json(&filtered_push);
+        let response = self.client.send(request).await?;
 
         let status_code = response.status();
         if !status_code.is_success() {
@@ -31,10 +59,14 @@ impl FilterPlugin {
             return Err(InternalPushError::ErrorResponse { status_code, body });
         };
 
-        Ok(())
+        Ok(PushOutcome::from_alerts(results))
     }
 }
 
+/// Keeps the default `shutdown`: requests are sent right away and not buffered,
+/// and idle connections are closed when the client is dropped.
+///
+/// Either the client or the server retries, see `retries_itself`.
 #[async_trait]
 impl Push for FilterPlugin {
     #[tracing::instrument(name = "push_initialize", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
@@ -46,13 +78,21 @@ impl Push for FilterPlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
-        self.push_alert_with_internal_error(alertmanager_push)
+        let outcome = self
+            .push_alert_with_internal_error(alertmanager_push)
             .await?;
 
         tracing::trace!("Successfully pushed.");
-        Ok(())
+        Ok(outcome)
+    }
+
+    fn retries_itself(&self) -> bool {
+        self.client.retries()
     }
 }
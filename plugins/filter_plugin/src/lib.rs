@@ -1,11 +1,15 @@
 use error::NewFilterPluginError;
-use models::AlertmanagerPush;
+use http_client::{HttpClient, HttpClientConfig};
+use md5::{Digest, Md5};
+use models::{Alert, AlertmanagerPush, Status};
 use plugins_definitions::Plugin;
+use plugins_filter::ast::AlertExpr;
 use regex::Error as RegexError;
 use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::num::NonZeroU64;
 use std::ops::Deref;
 use std::str::FromStr;
 use url::Url;
@@ -54,6 +58,57 @@ impl Deref for RegexHolder {
     }
 }
 
+/// A regex that must match the whole value, like in Prometheus' `relabel_config`
+///
+/// Serialized as the regex it was created from, without the anchors.
+#[derive(Debug, Clone, serde_with::SerializeDisplay, serde_with::DeserializeFromStr)]
+pub struct AnchoredRegex {
+    source: String,
+    regex: Regex,
+}
+
+impl JsonSchema for AnchoredRegex {
+    fn schema_name() -> String {
+        "AnchoredRegex".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl std::fmt::Display for AnchoredRegex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl FromStr for AnchoredRegex {
+    type Err = RegexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            source: s.to_string(),
+            regex: Regex::new(&format!("^(?:{s})$"))?,
+        })
+    }
+}
+
+impl Default for AnchoredRegex {
+    /// Matches everything and captures it in `$1`
+    fn default() -> Self {
+        Self::from_str("(.*)").expect("default regex is valid")
+    }
+}
+
+impl Deref for AnchoredRegex {
+    type Target = Regex;
+
+    fn deref(&self) -> &Self::Target {
+        &self.regex
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum RegexActionTarget {
     Name,
@@ -76,29 +131,178 @@ pub struct ReplaceRegexAction {
     pub replacement_target: RegexActionTarget,
 }
 
+/// Prometheus' `replace`
+///
+/// Joins the values of `source_labels` with `separator`. If `regex` matches the result,
+/// `target_label` is set to `replacement` with the capture groups (`$1`, `${name}`) expanded.
+/// An empty result removes `target_label`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelabelReplaceAction {
+    #[serde(default)]
+    pub source_labels: Vec<String>,
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    #[serde(default)]
+    pub regex: AnchoredRegex,
+    pub target_label: String,
+    #[serde(default = "default_replacement")]
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ReplaceAction {
+    /// Replaces whole names or values matching a regex
+    Regex(ReplaceRegexAction),
+    /// Sets a label from other labels
+    Relabel(RelabelReplaceAction),
+}
+
+/// Prometheus' `labelkeep` and `labeldrop`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LabelRegexAction {
+    pub regex: AnchoredRegex,
+}
+
+/// Prometheus' `labelmap`
+///
+/// Copies every label whose name matches `regex` to the name given by `replacement`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LabelMapAction {
+    pub regex: AnchoredRegex,
+    #[serde(default = "default_replacement")]
+    pub replacement: String,
+}
+
+/// Prometheus' `hashmod`
+///
+/// Sets `target_label` to the MD5 hash of the joined `source_labels` modulo `modulus`.
+/// A `modulus` of 0 is rejected when the config is loaded.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HashModAction {
+    pub source_labels: Vec<String>,
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    pub target_label: String,
+    pub modulus: NonZeroU64,
+}
+
+/// Prometheus' `lowercase` and `uppercase`
+///
+/// Sets `target_label` to the joined `source_labels` with the case changed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CaseAction {
+    pub source_labels: Vec<String>,
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    pub target_label: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AddAction {
     pub name: String,
     pub value: String,
 }
 
+fn default_separator() -> String {
+    ";".to_string()
+}
+
+fn default_replacement() -> String {
+    "$1".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "action")]
 pub enum Action {
     Drop(DropRegexAction),
-    Replace(ReplaceRegexAction),
+    Replace(ReplaceAction),
     Add(AddAction),
+    LabelKeep(LabelRegexAction),
+    LabelDrop(LabelRegexAction),
+    LabelMap(LabelMapAction),
+    HashMod(HashModAction),
+    Lowercase(CaseAction),
+    Uppercase(CaseAction),
+    If(ConditionalAction),
 }
 
+/// Applies `then` only if `when` matches
+///
+/// In `alerts_labels` and `alerts_annotations`, `when` is evaluated against the alert being filtered.
+/// In the other maps, it matches if any alert left by the alert actions matches.
+/// Matchers see the labels as they were received, before any label action was applied.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-/// Configuration for the Filter plugin
-pub struct FilterPluginConfig {
-    pub webhook_url: Url,
+pub struct ConditionalAction {
+    pub when: AlertExpr,
+    pub then: Vec<Action>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AlertMatcherAction {
+    pub matcher: AlertExpr,
+}
+
+/// Prometheus' `keep` and `drop`
+///
+/// Matches if `regex` matches the values of `source_labels` joined with `separator`.
+/// Labels are looked up in the alert's labels, then in `common_labels` and `group_labels`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelabelMatcherAction {
+    pub source_labels: Vec<String>,
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    #[serde(default)]
+    pub regex: AnchoredRegex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum AlertMatcher {
+    /// Matches alerts with a filter expression
+    Expr(AlertMatcherAction),
+    /// Matches alerts like Prometheus' `relabel_config`
+    Relabel(RelabelMatcherAction),
+}
+
+/// Actions on whole alerts
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "action")]
+pub enum AlertAction {
+    /// Drops resolved alerts
+    DropResolved,
+    /// Drops alerts matching the matcher
+    Drop(AlertMatcher),
+    /// Keeps only alerts matching the matcher
+    Keep(AlertMatcher),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+/// Actions applied to a push
+pub struct FilterActions {
+    #[serde(default)]
     pub group_labels: Vec<Action>,
+    #[serde(default)]
     pub common_labels: Vec<Action>,
+    #[serde(default)]
     pub common_annotations: Vec<Action>,
+    #[serde(default)]
     pub alerts_labels: Vec<Action>,
+    #[serde(default)]
     pub alerts_annotations: Vec<Action>,
+    /// Applied before the label and annotation actions
+    #[serde(default)]
+    pub alerts: Vec<AlertAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Configuration for the Filter plugin
+pub struct FilterPluginConfig {
+    pub webhook_url: Url,
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    #[serde(flatten)]
+    pub actions: FilterActions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -116,14 +320,22 @@ pub struct FilterPlugin {
     meta: FilterPluginMeta,
     /// Configuration for the plugin
     config: FilterPluginConfig,
+    /// Client used to forward the filtered push
+    client: HttpClient,
 }
 
 impl FilterPlugin {
-    pub fn new(
+    pub async fn new(
         meta: FilterPluginMeta,
         config: FilterPluginConfig,
     ) -> Result<Self, NewFilterPluginError> {
-        Ok(Self { meta, config })
+        let client = HttpClient::new(config.http_client.clone()).await?;
+
+        Ok(Self {
+            meta,
+            config,
+            client,
+        })
     }
 
     fn add_signature(&self, push: &mut AlertmanagerPush) {
@@ -138,22 +350,77 @@ impl FilterPlugin {
             .unwrap_or(false)
     }
 
-    fn filter(&self, push: &AlertmanagerPush) -> AlertmanagerPush {
-        let mut push = push.clone();
+    fn filter(&self, original: &AlertmanagerPush) -> AlertmanagerPush {
+        self.config.actions.apply(original)
+    }
+}
+
+impl FilterActions {
+    /// Applies the actions to a copy of the push.
+    ///
+    /// Alert actions run first. Removed alerts are counted in `truncated_alerts`,
+    /// so that `alerts.len() + truncated_alerts` is still the size of the group,
+    /// and `status` is recomputed from the remaining alerts.
+    /// Conditions of the other maps only look at the remaining alerts.
+    ///
+    /// Actions that read labels look them up in the map being filtered first,
+    /// then in the related maps that were already filtered:
+    ///
+    /// - `common_labels`: `group_labels`
+    /// - `common_annotations`: `common_labels`, `group_labels`
+    /// - `alerts_labels`: `common_labels`, `group_labels`
+    /// - `alerts_annotations`: the alert's labels, `common_annotations`, `common_labels`
+    pub fn apply(&self, original: &AlertmanagerPush) -> AlertmanagerPush {
+        let mut push = original.clone();
 
-        push.group_labels = Self::filter_btree_map(&self.config.group_labels, push.group_labels);
-        push.common_labels = Self::filter_btree_map(&self.config.common_labels, push.common_labels);
-        push.common_annotations =
-            Self::filter_btree_map(&self.config.common_annotations, push.common_annotations);
+        push.retain_alerts(|alert| Self::keep_alert(&self.alerts, original, alert));
+
+        let remaining_alerts = push.alerts.clone();
+        let any_alert_matches = |expr: &AlertExpr| {
+            remaining_alerts
+                .iter()
+                .any(|alert| expr.is_match(original, alert))
+        };
+
+        push.group_labels = Self::filter_btree_map(
+            &self.group_labels,
+            push.group_labels,
+            &[],
+            &any_alert_matches,
+        );
+        push.common_labels = Self::filter_btree_map(
+            &self.common_labels,
+            push.common_labels,
+            &[&push.group_labels],
+            &any_alert_matches,
+        );
+        push.common_annotations = Self::filter_btree_map(
+            &self.common_annotations,
+            push.common_annotations,
+            &[&push.common_labels, &push.group_labels],
+            &any_alert_matches,
+        );
 
         push.alerts = push
             .alerts
             .into_iter()
             .map(|alert| {
+                let original_alert = alert.clone();
+                let alert_matches = |expr: &AlertExpr| expr.is_match(original, &original_alert);
+
                 let mut alert = alert;
-                alert.labels = Self::filter_btree_map(&self.config.alerts_labels, alert.labels);
-                alert.annotations =
-                    Self::filter_btree_map(&self.config.alerts_annotations, alert.annotations);
+                alert.labels = Self::filter_btree_map(
+                    &self.alerts_labels,
+                    alert.labels,
+                    &[&push.common_labels, &push.group_labels],
+                    &alert_matches,
+                );
+                alert.annotations = Self::filter_btree_map(
+                    &self.alerts_annotations,
+                    alert.annotations,
+                    &[&alert.labels, &push.common_annotations, &push.common_labels],
+                    &alert_matches,
+                );
                 alert
             })
             .collect();
@@ -161,9 +428,35 @@ impl FilterPlugin {
         push
     }
 
+    fn keep_alert(actions: &[AlertAction], push: &AlertmanagerPush, alert: &Alert) -> bool {
+        actions.iter().all(|action| match action {
+            AlertAction::DropResolved => alert.status != Status::Resolved,
+            AlertAction::Drop(matcher) => !Self::is_alert_match(matcher, push, alert),
+            AlertAction::Keep(matcher) => Self::is_alert_match(matcher, push, alert),
+        })
+    }
+
+    fn is_alert_match(matcher: &AlertMatcher, push: &AlertmanagerPush, alert: &Alert) -> bool {
+        match matcher {
+            AlertMatcher::Expr(matcher_action) => matcher_action.matcher.is_match(push, alert),
+            AlertMatcher::Relabel(relabel_action) => {
+                let source = Self::join_source_labels(
+                    &alert.labels,
+                    &[&push.common_labels, &push.group_labels],
+                    &relabel_action.source_labels,
+                    &relabel_action.separator,
+                );
+
+                relabel_action.regex.is_match(&source)
+            }
+        }
+    }
+
     fn filter_btree_map(
         actions: &Vec<Action>,
         mut btree_map: BTreeMap<String, String>,
+        sources: &[&BTreeMap<String, String>],
+        matches: &dyn Fn(&AlertExpr) -> bool,
     ) -> BTreeMap<String, String> {
         for action in actions {
             match action {
@@ -173,7 +466,7 @@ impl FilterPlugin {
                         RegexActionTarget::Value => !regex_action.regex.is_match(value),
                     });
                 }
-                Action::Replace(regex_action) => {
+                Action::Replace(ReplaceAction::Regex(regex_action)) => {
                     btree_map = btree_map
                         .into_iter()
                         .map(|(key, value)| match regex_action.regex_target {
@@ -208,14 +501,128 @@ impl FilterPlugin {
                         })
                         .collect();
                 }
+                Action::Replace(ReplaceAction::Relabel(relabel_action)) => {
+                    let source = Self::join_source_labels(
+                        &btree_map,
+                        sources,
+                        &relabel_action.source_labels,
+                        &relabel_action.separator,
+                    );
+
+                    if let Some(captures) = relabel_action.regex.captures(&source) {
+                        let mut target_label = String::new();
+                        captures.expand(&relabel_action.target_label, &mut target_label);
+
+                        let mut value = String::new();
+                        captures.expand(&relabel_action.replacement, &mut value);
+
+                        if value.is_empty() {
+                            btree_map.remove(&target_label);
+                        } else {
+                            btree_map.insert(target_label, value);
+                        }
+                    }
+                }
                 Action::Add(add_action) => {
                     btree_map.insert(add_action.name.clone(), add_action.value.clone());
                 }
+                Action::LabelKeep(label_action) => {
+                    btree_map.retain(|key, _| label_action.regex.is_match(key));
+                }
+                Action::LabelDrop(label_action) => {
+                    btree_map.retain(|key, _| !label_action.regex.is_match(key));
+                }
+                Action::LabelMap(label_map_action) => {
+                    let mapped: Vec<(String, String)> = btree_map
+                        .iter()
+                        .filter_map(|(key, value)| {
+                            let captures = label_map_action.regex.captures(key)?;
+
+                            let mut mapped_key = String::new();
+                            captures.expand(&label_map_action.replacement, &mut mapped_key);
+
+                            Some((mapped_key, value.clone()))
+                        })
+                        .collect();
+
+                    btree_map.extend(mapped);
+                }
+                Action::HashMod(hash_mod_action) => {
+                    let source = Self::join_source_labels(
+                        &btree_map,
+                        sources,
+                        &hash_mod_action.source_labels,
+                        &hash_mod_action.separator,
+                    );
+
+                    // Same as Prometheus: the last 8 bytes of the MD5 sum as a big-endian integer
+                    let hash = Md5::digest(source.as_bytes());
+                    let mut last_eight_bytes = [0; 8];
+                    last_eight_bytes.copy_from_slice(&hash[8..]);
+                    let hash = u64::from_be_bytes(last_eight_bytes);
+
+                    btree_map.insert(
+                        hash_mod_action.target_label.clone(),
+                        (hash % hash_mod_action.modulus.get()).to_string(),
+                    );
+                }
+                Action::Lowercase(case_action) => {
+                    let source = Self::join_source_labels(
+                        &btree_map,
+                        sources,
+                        &case_action.source_labels,
+                        &case_action.separator,
+                    );
+
+                    btree_map.insert(case_action.target_label.clone(), source.to_lowercase());
+                }
+                Action::Uppercase(case_action) => {
+                    let source = Self::join_source_labels(
+                        &btree_map,
+                        sources,
+                        &case_action.source_labels,
+                        &case_action.separator,
+                    );
+
+                    btree_map.insert(case_action.target_label.clone(), source.to_uppercase());
+                }
+                Action::If(conditional_action) => {
+                    if matches(&conditional_action.when) {
+                        btree_map = Self::filter_btree_map(
+                            &conditional_action.then,
+                            btree_map,
+                            sources,
+                            matches,
+                        );
+                    }
+                }
             }
         }
 
         btree_map
     }
+
+    /// Joins the values of the given labels
+    ///
+    /// Missing labels are treated as empty strings.
+    fn join_source_labels(
+        btree_map: &BTreeMap<String, String>,
+        sources: &[&BTreeMap<String, String>],
+        source_labels: &[String],
+        separator: &str,
+    ) -> String {
+        source_labels
+            .iter()
+            .map(|label| {
+                std::iter::once(btree_map)
+                    .chain(sources.iter().copied())
+                    .find_map(|map| map.get(label))
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
 }
 
 #[cfg(test)]
@@ -226,52 +633,56 @@ mod test {
     fn create_group_labels_configs() -> FilterPluginConfig {
         FilterPluginConfig {
             webhook_url: Url::parse("http://localhost:8080").unwrap(),
-            group_labels: vec![
-                Action::Drop(DropRegexAction {
-                    regex: RegexHolder::from_str("^foo$").unwrap(),
-                    regex_target: RegexActionTarget::Name,
-                }),
-                Action::Drop(DropRegexAction {
-                    regex: RegexHolder::from_str("^warning.*").unwrap(),
-                    regex_target: RegexActionTarget::Value,
-                }),
-                Action::Replace(ReplaceRegexAction {
-                    regex: RegexHolder::from_str("^inst.*").unwrap(),
-                    regex_target: RegexActionTarget::Name,
-                    replace_with: "instagram".to_string(),
-                    replacement_target: RegexActionTarget::Name,
-                }),
-                Action::Replace(ReplaceRegexAction {
-                    regex: RegexHolder::from_str("^node$").unwrap(),
-                    regex_target: RegexActionTarget::Value,
-                    replace_with: "christmas".to_string(),
-                    replacement_target: RegexActionTarget::Value,
-                }),
-                Action::Replace(ReplaceRegexAction {
-                    regex: RegexHolder::from_str("^replace_my_value$").unwrap(),
-                    regex_target: RegexActionTarget::Name,
-                    replace_with: "replaced!".to_string(),
-                    replacement_target: RegexActionTarget::Value,
-                }),
-                Action::Replace(ReplaceRegexAction {
-                    regex: RegexHolder::from_str("^replace_my_name$").unwrap(),
-                    regex_target: RegexActionTarget::Value,
-                    replace_with: "replaced!".to_string(),
-                    replacement_target: RegexActionTarget::Name,
-                }),
-                Action::Add(AddAction {
-                    name: "baz".to_string(),
-                    value: "baaz".to_string(),
-                }),
-                Action::Add(AddAction {
-                    name: "test".to_string(),
-                    value: "test".to_string(),
-                }),
-            ],
-            common_labels: vec![],
-            common_annotations: vec![],
-            alerts_labels: vec![],
-            alerts_annotations: vec![],
+            http_client: Default::default(),
+            actions: FilterActions {
+                group_labels: vec![
+                    Action::Drop(DropRegexAction {
+                        regex: RegexHolder::from_str("^foo$").unwrap(),
+                        regex_target: RegexActionTarget::Name,
+                    }),
+                    Action::Drop(DropRegexAction {
+                        regex: RegexHolder::from_str("^warning.*").unwrap(),
+                        regex_target: RegexActionTarget::Value,
+                    }),
+                    Action::Replace(ReplaceAction::Regex(ReplaceRegexAction {
+                        regex: RegexHolder::from_str("^inst.*").unwrap(),
+                        regex_target: RegexActionTarget::Name,
+                        replace_with: "instagram".to_string(),
+                        replacement_target: RegexActionTarget::Name,
+                    })),
+                    Action::Replace(ReplaceAction::Regex(ReplaceRegexAction {
+                        regex: RegexHolder::from_str("^node$").unwrap(),
+                        regex_target: RegexActionTarget::Value,
+                        replace_with: "christmas".to_string(),
+                        replacement_target: RegexActionTarget::Value,
+                    })),
+                    Action::Replace(ReplaceAction::Regex(ReplaceRegexAction {
+                        regex: RegexHolder::from_str("^replace_my_value$").unwrap(),
+                        regex_target: RegexActionTarget::Name,
+                        replace_with: "replaced!".to_string(),
+                        replacement_target: RegexActionTarget::Value,
+                    })),
+                    Action::Replace(ReplaceAction::Regex(ReplaceRegexAction {
+                        regex: RegexHolder::from_str("^replace_my_name$").unwrap(),
+                        regex_target: RegexActionTarget::Value,
+                        replace_with: "replaced!".to_string(),
+                        replacement_target: RegexActionTarget::Name,
+                    })),
+                    Action::Add(AddAction {
+                        name: "baz".to_string(),
+                        value: "baaz".to_string(),
+                    }),
+                    Action::Add(AddAction {
+                        name: "test".to_string(),
+                        value: "test".to_string(),
+                    }),
+                ],
+                common_labels: vec![],
+                common_annotations: vec![],
+                alerts_labels: vec![],
+                alerts_annotations: vec![],
+                alerts: vec![],
+            },
         }
     }
 
@@ -300,6 +711,7 @@ mod test {
                 group: "test".to_string(),
             },
             config: create_group_labels_configs(),
+            client: HttpClient::default(),
         };
 
         let push = plugin.filter(&push);
@@ -327,6 +739,294 @@ mod test {
         assert_eq!(push.group_labels.get("test"), Some(&"test".to_string()));
     }
 
+    #[test]
+    fn relabel_actions() {
+        let config: FilterPluginConfig = serde_yaml::from_str(
+            r#"
+            webhook_url: http://localhost:8080
+            group_labels: []
+            common_labels:
+              - action: Lowercase
+                source_labels: [env]
+                target_label: env
+            common_annotations: []
+            alerts_labels:
+              - action: Replace
+                source_labels: [namespace]
+                regex: team-(.*)
+                target_label: team
+              - action: Replace
+                source_labels: [job, instance]
+                separator: "@"
+                target_label: address
+                replacement: $2
+                regex: (.*)@(.*):.*
+              - action: HashMod
+                source_labels: [c]
+                target_label: shard
+                modulus: 1000
+              - action: LabelMap
+                regex: __meta_(.+)
+              - action: LabelDrop
+                regex: __meta_.*
+              - action: Drop
+                regex: ^$
+                regex_target: Value
+              - action: Replace
+                regex: ^old$
+                regex_target: Name
+                replace_with: new
+                replacement_target: Name
+            alerts_annotations:
+              - action: Uppercase
+                source_labels: [team]
+                target_label: owner
+              - action: LabelKeep
+                regex: owner|summary
+            "#,
+        )
+        .unwrap();
+
+        let push = AlertmanagerPush {
+            common_labels: [
+                ("env".to_string(), "PROD".to_string()),
+                ("namespace".to_string(), "team-db".to_string()),
+            ]
+            .into(),
+            alerts: vec![models::Alert {
+                labels: [
+                    ("c".to_string(), "baz".to_string()),
+                    ("job".to_string(), "node".to_string()),
+                    ("instance".to_string(), "host:9100".to_string()),
+                    ("__meta_zone".to_string(), "eu".to_string()),
+                    ("old".to_string(), "value".to_string()),
+                    ("empty".to_string(), "".to_string()),
+                ]
+                .into(),
+                annotations: [
+                    ("summary".to_string(), "summary".to_string()),
+                    ("description".to_string(), "description".to_string()),
+                ]
+                .into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let plugin = FilterPlugin {
+            meta: FilterPluginMeta {
+                name: "test".to_string(),
+                group: "test".to_string(),
+            },
+            config,
+            client: HttpClient::default(),
+        };
+
+        let push = plugin.filter(&push);
+
+        assert_eq!(push.common_labels.get("env"), Some(&"prod".to_string()));
+
+        let alert = &push.alerts[0];
+        let expected_labels: BTreeMap<String, String> = [
+            ("c".to_string(), "baz".to_string()),
+            ("job".to_string(), "node".to_string()),
+            ("instance".to_string(), "host:9100".to_string()),
+            ("team".to_string(), "db".to_string()),
+            ("address".to_string(), "host".to_string()),
+            ("shard".to_string(), "976".to_string()),
+            ("zone".to_string(), "eu".to_string()),
+            ("new".to_string(), "value".to_string()),
+        ]
+        .into();
+
+        assert_eq!(alert.labels, expected_labels);
+
+        let expected_annotations: BTreeMap<String, String> = [
+            ("summary".to_string(), "summary".to_string()),
+            ("owner".to_string(), "DB".to_string()),
+        ]
+        .into();
+
+        assert_eq!(alert.annotations, expected_annotations);
+    }
+
+    #[test]
+    fn hash_mod_rejects_zero_modulus() {
+        let error = serde_yaml::from_str::<Action>(
+            r#"
+            action: HashMod
+            source_labels: [c]
+            target_label: shard
+            modulus: 0
+            "#,
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("nonzero"), "{error}");
+    }
+
+    #[test]
+    fn conditional_and_alert_actions() {
+        let config: FilterPluginConfig = serde_yaml::from_str(
+            r#"
+            webhook_url: http://localhost:8080
+            group_labels: []
+            common_labels:
+              - action: If
+                when: label severity is critical
+                then:
+                  - action: Add
+                    name: has_critical
+                    value: "true"
+            common_annotations: []
+            alerts_labels:
+              - action: If
+                when: label severity is critical
+                then:
+                  - action: Add
+                    name: pager
+                    value: "true"
+            alerts_annotations: []
+            alerts:
+              - action: DropResolved
+              - action: Drop
+                matcher: label team is noisy
+            "#,
+        )
+        .unwrap();
+
+        let alert = |status: Status, severity: &str, team: &str| Alert {
+            status,
+            labels: [
+                ("severity".to_string(), severity.to_string()),
+                ("team".to_string(), team.to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let push = AlertmanagerPush {
+            status: Status::Firing,
+            truncated_alerts: 2,
+            alerts: vec![
+                alert(Status::Firing, "critical", "db"),
+                alert(Status::Firing, "warning", "db"),
+                alert(Status::Resolved, "critical", "db"),
+                alert(Status::Firing, "critical", "noisy"),
+            ],
+            ..Default::default()
+        };
+
+        let plugin = FilterPlugin {
+            meta: FilterPluginMeta {
+                name: "test".to_string(),
+                group: "test".to_string(),
+            },
+            config,
+            client: HttpClient::default(),
+        };
+
+        let filtered = plugin.filter(&push);
+
+        assert_eq!(filtered.alerts.len(), 2);
+        assert_eq!(filtered.truncated_alerts, 4);
+        assert_eq!(filtered.status, Status::Firing);
+        assert_eq!(
+            filtered.common_labels.get("has_critical"),
+            Some(&"true".to_string())
+        );
+        assert_eq!(
+            filtered.alerts[0].labels.get("pager"),
+            Some(&"true".to_string())
+        );
+        assert_eq!(filtered.alerts[1].labels.get("pager"), None);
+
+        let push = AlertmanagerPush {
+            status: Status::Firing,
+            alerts: vec![
+                alert(Status::Firing, "warning", "noisy"),
+                alert(Status::Resolved, "warning", "db"),
+            ],
+            ..Default::default()
+        };
+
+        let filtered = plugin.filter(&push);
+
+        assert!(filtered.alerts.is_empty());
+        assert_eq!(filtered.truncated_alerts, 2);
+        assert_eq!(filtered.status, Status::Resolved);
+        assert_eq!(filtered.common_labels.get("has_critical"), None);
+
+        // The only critical alert is dropped, so the condition must not see it
+        let push = AlertmanagerPush {
+            status: Status::Firing,
+            alerts: vec![
+                alert(Status::Firing, "critical", "noisy"),
+                alert(Status::Firing, "warning", "db"),
+            ],
+            ..Default::default()
+        };
+
+        let filtered = plugin.filter(&push);
+
+        assert_eq!(filtered.alerts.len(), 1);
+        assert_eq!(filtered.common_labels.get("has_critical"), None);
+    }
+
+    #[test]
+    fn relabel_keep_and_drop_alerts() {
+        let config: FilterPluginConfig = serde_yaml::from_str(
+            r#"
+            webhook_url: http://localhost:8080
+            alerts:
+              - action: Keep
+                source_labels: [env, severity]
+                regex: prod;(critical|warning)
+              - action: Drop
+                source_labels: [team]
+                regex: noisy
+            "#,
+        )
+        .unwrap();
+
+        let alert = |severity: &str, team: &str| Alert {
+            status: Status::Firing,
+            labels: [
+                ("severity".to_string(), severity.to_string()),
+                ("team".to_string(), team.to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let push = AlertmanagerPush {
+            status: Status::Firing,
+            common_labels: [("env".to_string(), "prod".to_string())].into(),
+            alerts: vec![
+                alert("critical", "db"),
+                alert("info", "db"),
+                alert("warning", "noisy"),
+                // The regex must match the whole joined value
+                alert("critical-ish", "db"),
+            ],
+            ..Default::default()
+        };
+
+        let plugin = FilterPlugin {
+            meta: FilterPluginMeta {
+                name: "test".to_string(),
+                group: "test".to_string(),
+            },
+            config,
+            client: HttpClient::default(),
+        };
+
+        let filtered = plugin.filter(&push);
+
+        assert_eq!(filtered.alerts, vec![alert("critical", "db")]);
+        assert_eq!(filtered.truncated_alerts, 3);
+    }
+
     #[ignore]
     #[test]
     fn serialize_and_print() {
@@ -1,6 +1,8 @@
-use mongodb::error::Error as MongoError;
+use mongodb::error::{
+    Error as MongoError, ErrorKind, RETRYABLE_WRITE_ERROR, TRANSIENT_TRANSACTION_ERROR,
+};
 use plugins_definitions::HealthError;
-use push_definitions::PushError;
+use push_definitions::{ErrorClass, PushError};
 use thiserror::Error as ThisError;
 
 #[derive(ThisError, Debug)]
@@ -93,10 +95,33 @@ pub enum InternalPushError {
     },
 }
 
+impl InternalPushError {
+    /// Network errors and errors the server labels as retryable are transient
+    fn class(&self) -> ErrorClass {
+        let Some(error) =
+            std::error::Error::source(self).and_then(|source| source.downcast_ref::<MongoError>())
+        else {
+            return ErrorClass::Permanent;
+        };
+
+        let transient = error.contains_label(TRANSIENT_TRANSACTION_ERROR)
+            || error.contains_label(RETRYABLE_WRITE_ERROR)
+            || matches!(
+                *error.kind,
+                ErrorKind::Io(_)
+                    | ErrorKind::ConnectionPoolCleared { .. }
+                    | ErrorKind::ServerSelection { .. }
+            );
+
+        match transient {
+            true => ErrorClass::Transient,
+            false => ErrorClass::Permanent,
+        }
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
-        PushError {
-            error: error.into(),
-        }
+        Self::new(error.class(), error)
     }
 }
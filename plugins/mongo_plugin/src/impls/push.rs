@@ -12,7 +12,7 @@ use crate::{
 use async_trait::async_trait;
 use models::AlertmanagerPush;
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
+use push_definitions::{InitializeError, Push, PushError, PushOutcome, ShutdownError};
 
 impl MongoPlugin {
     async fn push_alert_with_internal_error(
@@ -202,13 +202,27 @@ impl Push for MongoPlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         self.push_alert_with_internal_error(alertmanager_push)
             .await?;
 
         tracing::trace!("Successfully pushed.");
+        Ok(PushOutcome::Pushed)
+    }
+
+    #[tracing::instrument(name = "push_shutdown", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        tracing::trace!("Shutting down.");
+
+        // Waits for open sessions and cursors to be dropped, then closes the connections
+        self.client.clone().shutdown().await;
+
+        tracing::trace!("Successfully shut down.");
         Ok(())
     }
 }
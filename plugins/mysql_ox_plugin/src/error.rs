@@ -1,4 +1,4 @@
-use push_definitions::PushError;
+use push_definitions::{ErrorClass, PushError};
 use sqlx::Error as SqlxError;
 use thiserror::Error as ThisError;
 
@@ -153,10 +153,37 @@ pub enum InternalPushError {
     },
 }
 
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        std::error::Error::source(self)
+            .and_then(|source| source.downcast_ref::<SqlxError>())
+            .map(sqlx_error_class)
+            .unwrap_or_default()
+    }
+}
+
+/// Lost connections and an exhausted pool are transient, and so are
+/// connection exceptions and transaction rollbacks like deadlocks, by their SQLSTATE class
+fn sqlx_error_class(error: &SqlxError) -> ErrorClass {
+    match error {
+        SqlxError::Io(_)
+        | SqlxError::Tls(_)
+        | SqlxError::Protocol(_)
+        | SqlxError::PoolTimedOut
+        | SqlxError::PoolClosed
+        | SqlxError::WorkerCrashed => ErrorClass::Transient,
+        SqlxError::Database(error) => match error.code() {
+            Some(code) if ["08", "40"].iter().any(|class| code.starts_with(class)) => {
+                ErrorClass::Transient
+            }
+            _ => ErrorClass::Permanent,
+        },
+        _ => ErrorClass::Permanent,
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
-        Self {
-            error: error.into(),
-        }
+        Self::new(error.class(), error)
     }
 }
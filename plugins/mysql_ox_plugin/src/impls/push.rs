@@ -13,7 +13,7 @@ use async_trait::async_trait;
 use models::AlertmanagerPush;
 use ormx::Insert;
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
+use push_definitions::{InitializeError, Push, PushError, PushOutcome};
 use sqlx::Connection;
 
 #[async_trait]
@@ -34,7 +34,10 @@ impl Push for MysqlOXPlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         let mut conn = self
@@ -244,6 +247,6 @@ impl Push for MysqlOXPlugin {
             .map_err(InternalPushError::TransactionCommit)?;
 
         tracing::trace!("Successfully pushed.");
-        Ok(())
+        Ok(PushOutcome::Pushed)
     }
 }
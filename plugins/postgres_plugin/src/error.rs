@@ -1,9 +1,12 @@
 use bb8::RunError;
-use diesel::{result::Error as DieselError, ConnectionError};
+use diesel::{
+    result::{DatabaseErrorKind, Error as DieselError},
+    ConnectionError,
+};
 use diesel_async::pooled_connection::PoolError;
 use plugins_definitions::HealthError;
 use pull_definitions::PullError;
-use push_definitions::{InitializeError, PushError};
+use push_definitions::{ErrorClass, InitializeError, PushError};
 use thiserror::Error as ThisError;
 
 #[derive(ThisError, Debug)]
@@ -16,6 +19,13 @@ pub enum NewPostgresPluginError {
     ),
 }
 
+#[derive(ThisError, Debug)]
+#[error("Inserted row not returned. table: {table}, key: {key}")]
+pub struct MissingIdError {
+    pub table: &'static str,
+    pub key: String,
+}
+
 #[derive(ThisError, Debug)]
 pub enum InternalPushError {
     #[error("Error getting connection from pool: {0}")]
@@ -32,6 +42,25 @@ pub enum InternalPushError {
         #[source]
         error: DieselError,
     },
+    #[error("Error inserting {count} alert groups. error: {error}")]
+    GroupsInsertion {
+        count: usize,
+        #[source]
+        error: DieselError,
+    },
+    #[error("Error inserting {count} rows into {table}. error: {error}")]
+    BatchInsertion {
+        table: &'static str,
+        count: usize,
+        #[source]
+        error: DieselError,
+    },
+    #[error("{0}")]
+    MissingId(
+        #[source]
+        #[from]
+        MissingIdError,
+    ),
     #[error("Error getting group label id. group_key: {group_key}, label_name: {label_name}, label_value: {label_value}, error: {error}")]
     GroupLabelId {
         group_key: String,
@@ -169,11 +198,27 @@ pub enum InternalPushError {
     },
 }
 
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        if let InternalPushError::Acquire(_) = self {
+            return ErrorClass::Transient;
+        }
+
+        match std::error::Error::source(self)
+            .and_then(|source| source.downcast_ref::<DieselError>())
+        {
+            Some(DieselError::DatabaseError(
+                DatabaseErrorKind::UnableToSendCommand | DatabaseErrorKind::SerializationFailure,
+                _,
+            )) => ErrorClass::Transient,
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
-        Self {
-            error: error.into(),
-        }
+        Self::new(error.class(), error)
     }
 }
 
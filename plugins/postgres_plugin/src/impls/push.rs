@@ -12,21 +12,112 @@ use crate::{
             labels::{InsertableCommonLabel, InsertableLabel},
         },
     },
-    error::{InternalInitializeError, InternalPushError, LablelInsertionError},
+    error::{InternalInitializeError, InternalPushError, LablelInsertionError, MissingIdError},
     PostgresPlugin, MIGRATIONS,
 };
 use async_trait::async_trait;
 use diesel::{
-    BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl,
+    upsert::excluded, BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension,
+    PgConnection, QueryDsl,
 };
 use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use diesel_migrations::MigrationHarness;
 use models::{Alert as AlertmanagerPushAlert, AlertmanagerPush};
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
+use push_definitions::{InitializeError, Push, PushError, PushOutcome, ShutdownError};
 use scoped_futures::ScopedFutureExt;
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration,
+};
 use tokio::task::JoinHandle;
 
+/// Most bind parameters Postgres accepts in one statement
+const MAX_PARAMETERS: usize = u16::MAX as usize;
+
+/// Inserts the rows in chunks of at most [`MAX_PARAMETERS`] bind parameters
+macro_rules! insert_rows {
+    ($conn:expr, $table:ident, $columns:expr, $rows:expr) => {
+        async {
+            for chunk in $rows.chunks(MAX_PARAMETERS / $columns) {
+                diesel::insert_into(database::schema::$table::table)
+                    .values(chunk)
+                    .execute($conn)
+                    .await
+                    .map_err(|error| InternalPushError::BatchInsertion {
+                        table: stringify!($table),
+                        count: $rows.len(),
+                        error,
+                    })?;
+            }
+
+            Ok::<(), InternalPushError>(())
+        }
+    };
+}
+
+/// Inserts the missing name-value pairs into a labels or annotations table, in chunks,
+/// and returns the ids of all pairs
+macro_rules! upsert_name_values {
+    ($conn:expr, $table:ident, $insertable:ident, $pairs:expr) => {
+        async {
+            let rows = $pairs
+                .iter()
+                .map(|(name, value)| $insertable { name, value })
+                .collect::<Vec<_>>();
+
+            let mut ids = HashMap::new();
+            for chunk in rows.chunks(MAX_PARAMETERS / 2) {
+                let inserted = diesel::insert_into(database::schema::$table::table)
+                    .values(chunk)
+                    .on_conflict((
+                        database::schema::$table::name,
+                        database::schema::$table::value,
+                    ))
+                    // Changes nothing, but returns the existing rows as well
+                    .do_update()
+                    .set(
+                        database::schema::$table::name.eq(excluded(database::schema::$table::name)),
+                    )
+                    .returning((
+                        database::schema::$table::name,
+                        database::schema::$table::value,
+                        database::schema::$table::id,
+                    ))
+                    .get_results::<(String, String, i32)>($conn)
+                    .await
+                    .map_err(|error| InternalPushError::BatchInsertion {
+                        table: stringify!($table),
+                        count: rows.len(),
+                        error,
+                    })?;
+
+                ids.extend(
+                    inserted
+                        .into_iter()
+                        .map(|(name, value, id)| ((name, value), id)),
+                );
+            }
+
+            Ok::<HashMap<(String, String), i32>, InternalPushError>(ids)
+        }
+    };
+}
+
+/// Id of an inserted row by its unique pair
+fn id<A: ToString + ?Sized, B: ToString + ?Sized>(
+    ids: &HashMap<(String, String), i32>,
+    table: &'static str,
+    (a, b): (&A, &B),
+) -> Result<i32, MissingIdError> {
+    let key = (a.to_string(), b.to_string());
+
+    ids.get(&key).copied().ok_or_else(|| MissingIdError {
+        table,
+        key: format!("{}, {}", key.0, key.1),
+    })
+}
+
 impl PostgresPlugin {
     async fn insert_group(
         conn: &mut AsyncPgConnection,
@@ -52,6 +143,61 @@ impl PostgresPlugin {
         Ok(group_id)
     }
 
+    /// Inserts the groups in chunks and returns their ids in order.
+    ///
+    /// The ids are matched by the unique group key, since the order of `RETURNING` is not guaranteed.
+    async fn insert_groups(
+        conn: &mut AsyncPgConnection,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<Vec<i32>, InternalPushError> {
+        let statuses = alertmanager_pushes
+            .iter()
+            .map(|alertmanager_push| AlertStatusModel::from(&alertmanager_push.status))
+            .collect::<Vec<_>>();
+
+        let groups = alertmanager_pushes
+            .iter()
+            .zip(statuses.iter())
+            .map(|(alertmanager_push, status)| InsertableGroup {
+                receiver: &alertmanager_push.receiver,
+                status,
+                external_url: &alertmanager_push.external_url,
+                group_key: &alertmanager_push.group_key,
+            })
+            .collect::<Vec<_>>();
+
+        let mut group_ids = HashMap::new();
+        for chunk in groups.chunks(MAX_PARAMETERS / 4) {
+            let rows = diesel::insert_into(database::schema::groups::table)
+                .values(chunk)
+                .returning((
+                    database::schema::groups::group_key,
+                    database::schema::groups::id,
+                ))
+                .get_results::<(String, i32)>(conn)
+                .await
+                .map_err(|error| InternalPushError::GroupsInsertion {
+                    count: groups.len(),
+                    error,
+                })?;
+            group_ids.extend(rows);
+        }
+
+        alertmanager_pushes
+            .iter()
+            .map(|alertmanager_push| {
+                group_ids
+                    .get(&alertmanager_push.group_key)
+                    .copied()
+                    .ok_or_else(|| MissingIdError {
+                        table: "groups",
+                        key: alertmanager_push.group_key.clone(),
+                    })
+            })
+            .collect::<Result<_, _>>()
+            .map_err(InternalPushError::MissingId)
+    }
+
     async fn assign_group_label(
         conn: &mut AsyncPgConnection,
         group_id: i32,
@@ -566,6 +712,171 @@ impl PostgresPlugin {
         Ok(())
     }
 
+    /// Inserts the labels, annotations, alerts and assignments of the inserted groups,
+    /// with one statement per table and chunk
+    async fn insert_batch_details(
+        conn: &mut AsyncPgConnection,
+        group_ids: &[i32],
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<(), InternalPushError> {
+        let groups = || group_ids.iter().copied().zip(alertmanager_pushes.iter());
+        let alerts = || {
+            groups().flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .alerts
+                    .iter()
+                    .map(move |alert| (group_id, alertmanager_push, alert))
+            })
+        };
+
+        let labels = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.group_labels.iter())
+            .chain(alerts().flat_map(|(_, _, alert)| alert.labels.iter()))
+            .collect::<BTreeSet<_>>();
+        let label_ids = &upsert_name_values!(conn, labels, InsertableLabel, labels).await?;
+
+        let common_labels = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.common_labels.iter())
+            .collect::<BTreeSet<_>>();
+        let common_label_ids =
+            &upsert_name_values!(conn, common_labels, InsertableCommonLabel, common_labels).await?;
+
+        let common_annotations = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.common_annotations.iter())
+            .collect::<BTreeSet<_>>();
+        let common_annotation_ids = &upsert_name_values!(
+            conn,
+            common_annotations,
+            InsertableCommonAnnotation,
+            common_annotations
+        )
+        .await?;
+
+        let annotations = alerts()
+            .flat_map(|(_, _, alert)| alert.annotations.iter())
+            .collect::<BTreeSet<_>>();
+        let annotation_ids =
+            &upsert_name_values!(conn, annotations, InsertableAnnotation, annotations).await?;
+
+        let group_labels = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push.group_labels.iter().map(move |label| {
+                    Ok(InsertableGroupLabel {
+                        group_id,
+                        label_id: id(label_ids, "labels", label)?,
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows!(conn, groups_labels, 2, group_labels).await?;
+
+        let group_common_labels = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push.common_labels.iter().map(move |label| {
+                    Ok(InsertableGroupCommonLabel {
+                        group_id,
+                        common_label_id: id(common_label_ids, "common_labels", label)?,
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows!(conn, groups_common_labels, 2, group_common_labels).await?;
+
+        let group_common_annotations = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .common_annotations
+                    .iter()
+                    .map(move |annotation| {
+                        Ok(InsertableGroupCommonAnnotation {
+                            group_id,
+                            common_annotation_id: id(
+                                common_annotation_ids,
+                                "common_annotations",
+                                annotation,
+                            )?,
+                        })
+                    })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows!(conn, groups_common_annotations, 2, group_common_annotations).await?;
+
+        let statuses = alerts()
+            .map(|(_, _, alert)| AlertStatusModel::from(&alert.status))
+            .collect::<Vec<_>>();
+        let insertable_alerts = alerts()
+            .zip(statuses.iter())
+            .map(
+                |((group_id, alertmanager_push, alert), status)| InsertableAlert {
+                    group_id,
+                    group_key: &alertmanager_push.group_key,
+                    status,
+                    starts_at: alert.starts_at,
+                    ends_at: alert.ends_at,
+                    generator_url: &alert.generator_url,
+                    fingerprint: &alert.fingerprint,
+                },
+            )
+            .collect::<Vec<_>>();
+
+        // Matched by the unique group key and fingerprint
+        let mut alert_ids = HashMap::new();
+        for chunk in insertable_alerts.chunks(MAX_PARAMETERS / 7) {
+            let rows = diesel::insert_into(database::schema::alerts::table)
+                .values(chunk)
+                .returning((
+                    database::schema::alerts::group_key,
+                    database::schema::alerts::fingerprint,
+                    database::schema::alerts::id,
+                ))
+                .get_results::<(String, String, i32)>(conn)
+                .await
+                .map_err(|error| InternalPushError::BatchInsertion {
+                    table: "alerts",
+                    count: insertable_alerts.len(),
+                    error,
+                })?;
+            alert_ids.extend(
+                rows.into_iter()
+                    .map(|(group_key, fingerprint, id)| ((group_key, fingerprint), id)),
+            );
+        }
+
+        let alert_id = |alertmanager_push: &AlertmanagerPush, alert: &AlertmanagerPushAlert| {
+            id(
+                &alert_ids,
+                "alerts",
+                (&alertmanager_push.group_key, &alert.fingerprint),
+            )
+        };
+
+        let alert_labels = alerts()
+            .flat_map(|(_, alertmanager_push, alert)| {
+                alert.labels.iter().map(move |label| {
+                    Ok(InsertableAlertLabel {
+                        alert_id: alert_id(alertmanager_push, alert)?,
+                        label_id: id(label_ids, "labels", label)?,
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows!(conn, alerts_labels, 2, alert_labels).await?;
+
+        let alert_annotations = alerts()
+            .flat_map(|(_, alertmanager_push, alert)| {
+                alert.annotations.iter().map(move |annotation| {
+                    Ok(InsertableAlertAnnotation {
+                        alert_id: alert_id(alertmanager_push, alert)?,
+                        annotation_id: id(annotation_ids, "annotations", annotation)?,
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows!(conn, alerts_annotations, 2, alert_annotations).await?;
+
+        Ok(())
+    }
+
     async fn push_alert_with_internal_error(
         &self,
         alertmanager_push: &AlertmanagerPush,
@@ -593,6 +904,30 @@ impl PostgresPlugin {
         Ok(())
     }
 
+    async fn push_alerts_with_internal_error(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<(), InternalPushError> {
+        let mut conn = self.pool.get().await.map_err(InternalPushError::Acquire)?;
+
+        conn.transaction::<(), InternalPushError, _>(|conn| {
+            async move {
+                tracing::trace!(count = alertmanager_pushes.len(), "Beginning transaction.");
+
+                let group_ids = Self::insert_groups(conn, alertmanager_pushes).await?;
+                Self::insert_batch_details(conn, &group_ids, alertmanager_pushes).await?;
+
+                tracing::trace!("Committing transaction.");
+
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+        Ok(())
+    }
+
     async fn initialize_with_internal_error(&mut self) -> Result<(), InternalInitializeError> {
         // Always be nice and give memory back to the OS. ;)
         let config = self
@@ -630,13 +965,61 @@ impl Push for PostgresPlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         self.push_alert_with_internal_error(alertmanager_push)
             .await?;
 
         tracing::trace!("Successfully pushed.");
+        Ok(PushOutcome::Pushed)
+    }
+
+    /// Pushes all in a single transaction. If one push fails, none is stored
+    #[tracing::instrument(name = "push_alerts", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn push_alerts(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Vec<Result<PushOutcome, PushError>> {
+        if alertmanager_pushes.is_empty() {
+            return vec![];
+        }
+
+        tracing::trace!(count = alertmanager_pushes.len(), "Pushing.");
+
+        if let Err(error) = self
+            .push_alerts_with_internal_error(alertmanager_pushes)
+            .await
+        {
+            return PushError::from(error).for_batch(alertmanager_pushes.len());
+        }
+
+        tracing::trace!("Successfully pushed.");
+        alertmanager_pushes
+            .iter()
+            .map(|_| Ok(PushOutcome::Pushed))
+            .collect()
+    }
+
+    /// Waits for the connections in use to be returned.
+    /// bb8 pools cannot be closed, the idle connections are closed when the plugin is dropped
+    #[tracing::instrument(name = "push_shutdown", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        tracing::trace!("Shutting down.");
+
+        loop {
+            let state = self.pool.state();
+            if state.connections == state.idle_connections {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        tracing::trace!("Successfully shut down.");
         Ok(())
     }
 }
@@ -0,0 +1,147 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+enum Alerts {
+    Table,
+    GroupKey,
+    Fingerprint,
+}
+
+#[derive(DeriveIden)]
+enum Labels {
+    Table,
+    Name,
+    Value,
+}
+
+#[derive(DeriveIden)]
+enum Annotations {
+    Table,
+    Name,
+    Value,
+}
+
+#[derive(DeriveIden)]
+enum CommonLabels {
+    Table,
+    Name,
+    Value,
+}
+
+#[derive(DeriveIden)]
+enum CommonAnnotations {
+    Table,
+    Name,
+    Value,
+}
+
+const ALERTS_INDEX: &str = "alerts_unique_group_key_fingerprint";
+const LABELS_INDEX: &str = "labels_unique_name_value";
+const ANNOTATIONS_INDEX: &str = "annotations_unique_name_value";
+const COMMON_LABELS_INDEX: &str = "common_labels_unique_name_value";
+const COMMON_ANNOTATIONS_INDEX: &str = "common_annotations_unique_name_value";
+
+/// Recreates the natural key indexes of the initial migration as unique indexes,
+/// which `ON CONFLICT` of the batch inserts requires
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+impl Migration {
+    /// The indexes of the initial migration, which were not unique
+    fn initial_index_create_statements() -> Vec<IndexCreateStatement> {
+        let alerts_unique_group_key_fingerprint = Index::create()
+            .table(Alerts::Table)
+            .name(ALERTS_INDEX)
+            .col(Alerts::GroupKey)
+            .col(Alerts::Fingerprint)
+            .to_owned();
+
+        let labels_unique_name_value = Index::create()
+            .table(Labels::Table)
+            .name(LABELS_INDEX)
+            .col(Labels::Name)
+            .col(Labels::Value)
+            .to_owned();
+
+        let annotations_unique_name_value = Index::create()
+            .table(Annotations::Table)
+            .name(ANNOTATIONS_INDEX)
+            .col(Annotations::Name)
+            .col(Annotations::Value)
+            .to_owned();
+
+        let common_labels_unique_name_value = Index::create()
+            .table(CommonLabels::Table)
+            .name(COMMON_LABELS_INDEX)
+            .col(CommonLabels::Name)
+            .col(CommonLabels::Value)
+            .to_owned();
+
+        vec![
+            alerts_unique_group_key_fingerprint,
+            labels_unique_name_value,
+            annotations_unique_name_value,
+            common_labels_unique_name_value,
+        ]
+    }
+
+    fn index_create_statements() -> Vec<IndexCreateStatement> {
+        let common_annotations_unique_name_value = Index::create()
+            .table(CommonAnnotations::Table)
+            .name(COMMON_ANNOTATIONS_INDEX)
+            .col(CommonAnnotations::Name)
+            .col(CommonAnnotations::Value)
+            .to_owned();
+
+        Self::initial_index_create_statements()
+            .into_iter()
+            .chain([common_annotations_unique_name_value])
+            .map(|mut statement| statement.unique().to_owned())
+            .collect()
+    }
+
+    fn index_drop_statements(names: &[&str]) -> Vec<IndexDropStatement> {
+        names
+            .iter()
+            .map(|name| Index::drop().name(*name).to_owned())
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in Self::index_drop_statements(&[
+            ALERTS_INDEX,
+            LABELS_INDEX,
+            ANNOTATIONS_INDEX,
+            COMMON_LABELS_INDEX,
+        ]) {
+            manager.drop_index(statement).await?;
+        }
+
+        for statement in Self::index_create_statements() {
+            manager.create_index(statement).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in Self::index_drop_statements(&[
+            ALERTS_INDEX,
+            LABELS_INDEX,
+            ANNOTATIONS_INDEX,
+            COMMON_LABELS_INDEX,
+            COMMON_ANNOTATIONS_INDEX,
+        ]) {
+            manager.drop_index(statement).await?;
+        }
+
+        for statement in Self::initial_index_create_statements() {
+            manager.create_index(statement).await?;
+        }
+
+        Ok(())
+    }
+}
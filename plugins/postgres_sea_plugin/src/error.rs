@@ -1,7 +1,14 @@
-use push_definitions::{InitializeError, PushError};
+use push_definitions::{ErrorClass, InitializeError, PushError, ShutdownError};
 use sea_orm::error::DbErr;
 use thiserror::Error as ThisError;
 
+#[derive(ThisError, Debug)]
+#[error("Inserted row not returned. table: {table}, key: {key}")]
+pub struct MissingIdError {
+    pub table: &'static str,
+    pub key: String,
+}
+
 #[derive(ThisError, Debug)]
 pub enum InternalPushError {
     #[error("Error beginning transaction error: {0}")]
@@ -14,6 +21,25 @@ pub enum InternalPushError {
         #[source]
         error: DbErr,
     },
+    #[error("Error inserting {count} alert groups. error: {error}")]
+    GroupsInsertion {
+        count: usize,
+        #[source]
+        error: DbErr,
+    },
+    #[error("Error inserting {count} rows into {table}. error: {error}")]
+    BatchInsertion {
+        table: &'static str,
+        count: usize,
+        #[source]
+        error: DbErr,
+    },
+    #[error("{0}")]
+    MissingId(
+        #[source]
+        #[from]
+        MissingIdError,
+    ),
     #[error("Error getting group label id. group_key: {group_key}, label_name: {label_name}, label_value: {label_value}, error: {error}")]
     GroupLabelId {
         group_key: String,
@@ -151,11 +177,18 @@ pub enum InternalPushError {
     },
 }
 
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match std::error::Error::source(self).and_then(|source| source.downcast_ref::<DbErr>()) {
+            Some(DbErr::ConnectionAcquire(_) | DbErr::Conn(_)) => ErrorClass::Transient,
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
-        Self {
-            error: error.into(),
-        }
+        Self::new(error.class(), error)
     }
 }
 
@@ -184,3 +217,21 @@ impl From<InternalInitializeError> for InitializeError {
         }
     }
 }
+
+#[derive(ThisError, Debug)]
+pub enum InternalShutdownError {
+    #[error("Failed to close connections: {0}")]
+    Close(
+        #[from]
+        #[source]
+        DbErr,
+    ),
+}
+
+impl From<InternalShutdownError> for ShutdownError {
+    fn from(error: InternalShutdownError) -> Self {
+        Self {
+            error: error.into(),
+        }
+    }
+}
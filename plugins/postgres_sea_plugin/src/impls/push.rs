@@ -4,17 +4,153 @@ use crate::{
         groups, groups_common_annotations, groups_common_labels, groups_labels, labels,
         sea_orm_active_enums::AlertStatus,
     },
-    error::{InternalInitializeError, InternalPushError, LablelInsertionError},
+    error::{
+        InternalInitializeError, InternalPushError, InternalShutdownError, LablelInsertionError,
+        MissingIdError,
+    },
     PostgresSeaPlugin,
 };
 use async_trait::async_trait;
 use migration::{Migrator, MigratorTrait};
 use models::{Alert as AlertmanagerPushAlert, AlertmanagerPush};
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
+use push_definitions::{InitializeError, Push, PushError, PushOutcome, ShutdownError};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+    sea_query::{OnConflict, Query},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryTrait, Set, TransactionTrait, TryGetableMany,
 };
+use std::collections::{BTreeSet, HashMap};
+
+/// Most bind parameters Postgres accepts in one statement
+const MAX_PARAMETERS: usize = u16::MAX as usize;
+
+/// Inserts the rows in chunks of at most [`MAX_PARAMETERS`] bind parameters
+async fn insert_rows<A, C>(
+    db: &C,
+    table: &'static str,
+    columns: usize,
+    rows: &[A],
+) -> Result<(), InternalPushError>
+where
+    A: ActiveModelTrait,
+    C: ConnectionTrait,
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+{
+    for chunk in rows.chunks(MAX_PARAMETERS / columns) {
+        A::Entity::insert_many(chunk.iter().cloned())
+            .exec_without_returning(db)
+            .await
+            .map_err(|error| InternalPushError::BatchInsertion {
+                table,
+                count: rows.len(),
+                error,
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Inserts the rows in chunks like [`insert_rows`] and returns the `returning` columns of the returned rows.
+///
+/// `Insert::exec_with_returning` only returns one row, so the statement is run as a raw query.
+async fn insert_rows_returning<A, C, R, I>(
+    db: &C,
+    table: &'static str,
+    columns: usize,
+    rows: &[A],
+    on_conflict: Option<OnConflict>,
+    returning: I,
+) -> Result<Vec<R>, InternalPushError>
+where
+    A: ActiveModelTrait,
+    C: ConnectionTrait,
+    R: TryGetableMany,
+    I: IntoIterator<Item = <A::Entity as EntityTrait>::Column> + Clone,
+{
+    let batch_insertion_error = |error| InternalPushError::BatchInsertion {
+        table,
+        count: rows.len(),
+        error,
+    };
+
+    let mut returned = Vec::with_capacity(rows.len());
+    for chunk in rows.chunks(MAX_PARAMETERS / columns) {
+        let mut insert = A::Entity::insert_many(chunk.iter().cloned());
+        if let Some(on_conflict) = on_conflict.clone() {
+            insert = insert.on_conflict(on_conflict);
+        }
+        insert
+            .query()
+            .returning(Query::returning().columns(returning.clone()));
+
+        let statement = db.get_database_backend().build(insert.query());
+        for row in db
+            .query_all(statement)
+            .await
+            .map_err(batch_insertion_error)?
+        {
+            returned.push(row.try_get_many_by_index().map_err(batch_insertion_error)?);
+        }
+    }
+
+    Ok(returned)
+}
+
+/// Inserts the missing name-value pairs into a labels or annotations table
+/// and returns the ids of all pairs
+macro_rules! upsert_name_values {
+    ($db:expr, $table:ident, $pairs:expr) => {
+        async {
+            let rows = $pairs
+                .iter()
+                .map(|(name, value)| $table::ActiveModel {
+                    name: Set(name.to_string()),
+                    value: Set(value.to_string()),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+
+            insert_rows_returning(
+                $db,
+                stringify!($table),
+                2,
+                &rows,
+                Some(
+                    OnConflict::columns([$table::Column::Name, $table::Column::Value])
+                        // Changes nothing, but returns the existing rows as well
+                        .update_column($table::Column::Name)
+                        .to_owned(),
+                ),
+                [
+                    $table::Column::Name,
+                    $table::Column::Value,
+                    $table::Column::Id,
+                ],
+            )
+            .await
+            .map(|rows: Vec<(String, String, i32)>| {
+                rows.into_iter()
+                    .map(|(name, value, id)| ((name, value), id))
+                    .collect::<HashMap<_, _>>()
+            })
+        }
+    };
+}
+
+/// Id of an inserted row by its unique pair
+fn id<A: ToString + ?Sized, B: ToString + ?Sized>(
+    ids: &HashMap<(String, String), i32>,
+    table: &'static str,
+    (a, b): (&A, &B),
+) -> Result<i32, MissingIdError> {
+    let key = (a.to_string(), b.to_string());
+
+    ids.get(&key).copied().ok_or_else(|| MissingIdError {
+        table,
+        key: format!("{}, {}", key.0, key.1),
+    })
+}
 
 impl PostgresSeaPlugin {
     async fn initialize_with_internal_error(&mut self) -> Result<(), InternalInitializeError> {
@@ -561,6 +697,210 @@ impl PostgresSeaPlugin {
         Ok(())
     }
 
+    /// Inserts the groups in chunks and returns their ids in order.
+    ///
+    /// The ids are matched by the unique group key, since the order of `RETURNING` is not guaranteed.
+    async fn insert_groups<'a, C: ConnectionTrait>(
+        db: &'a C,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<Vec<i32>, InternalPushError> {
+        let groups = alertmanager_pushes
+            .iter()
+            .map(|alertmanager_push| groups::ActiveModel {
+                group_key: Set(alertmanager_push.group_key.clone()),
+                receiver: Set(alertmanager_push.receiver.clone()),
+                status: Set(AlertStatus::from(&alertmanager_push.status)),
+                external_url: Set(alertmanager_push.external_url.clone()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let group_ids = insert_rows_returning(
+            db,
+            "groups",
+            4,
+            &groups,
+            None,
+            [groups::Column::GroupKey, groups::Column::Id],
+        )
+        .await
+        .map(|rows: Vec<(String, i32)>| rows.into_iter().collect::<HashMap<_, _>>())
+        .map_err(|error| match error {
+            InternalPushError::BatchInsertion { count, error, .. } => {
+                InternalPushError::GroupsInsertion { count, error }
+            }
+            error => error,
+        })?;
+
+        alertmanager_pushes
+            .iter()
+            .map(|alertmanager_push| {
+                group_ids
+                    .get(&alertmanager_push.group_key)
+                    .copied()
+                    .ok_or_else(|| MissingIdError {
+                        table: "groups",
+                        key: alertmanager_push.group_key.clone(),
+                    })
+            })
+            .collect::<Result<_, _>>()
+            .map_err(InternalPushError::MissingId)
+    }
+
+    /// Inserts the labels, annotations, alerts and assignments of the inserted groups,
+    /// with one statement per table and chunk
+    async fn insert_batch_details<'a, C: ConnectionTrait>(
+        db: &'a C,
+        group_ids: &[i32],
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<(), InternalPushError> {
+        let groups = || group_ids.iter().copied().zip(alertmanager_pushes.iter());
+        let alerts = || {
+            groups().flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .alerts
+                    .iter()
+                    .map(move |alert| (group_id, alertmanager_push, alert))
+            })
+        };
+
+        let labels = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.group_labels.iter())
+            .chain(alerts().flat_map(|(_, _, alert)| alert.labels.iter()))
+            .collect::<BTreeSet<_>>();
+        let label_ids = &upsert_name_values!(db, labels, labels).await?;
+
+        let common_labels = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.common_labels.iter())
+            .collect::<BTreeSet<_>>();
+        let common_label_ids = &upsert_name_values!(db, common_labels, common_labels).await?;
+
+        let common_annotations = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.common_annotations.iter())
+            .collect::<BTreeSet<_>>();
+        let common_annotation_ids =
+            &upsert_name_values!(db, common_annotations, common_annotations).await?;
+
+        let annotations = alerts()
+            .flat_map(|(_, _, alert)| alert.annotations.iter())
+            .collect::<BTreeSet<_>>();
+        let annotation_ids = &upsert_name_values!(db, annotations, annotations).await?;
+
+        let group_labels = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push.group_labels.iter().map(move |label| {
+                    Ok(groups_labels::ActiveModel {
+                        group_id: Set(group_id),
+                        label_id: Set(id(label_ids, "labels", label)?),
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows(db, "groups_labels", 2, &group_labels).await?;
+
+        let group_common_labels = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push.common_labels.iter().map(move |label| {
+                    Ok(groups_common_labels::ActiveModel {
+                        group_id: Set(group_id),
+                        common_label_id: Set(id(common_label_ids, "common_labels", label)?),
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows(db, "groups_common_labels", 2, &group_common_labels).await?;
+
+        let group_common_annotations = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .common_annotations
+                    .iter()
+                    .map(move |annotation| {
+                        Ok(groups_common_annotations::ActiveModel {
+                            group_id: Set(group_id),
+                            common_annotation_id: Set(id(
+                                common_annotation_ids,
+                                "common_annotations",
+                                annotation,
+                            )?),
+                        })
+                    })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows(
+            db,
+            "groups_common_annotations",
+            2,
+            &group_common_annotations,
+        )
+        .await?;
+
+        let insertable_alerts = alerts()
+            .map(|(group_id, alertmanager_push, alert)| alerts::ActiveModel {
+                group_id: Set(group_id),
+                group_key: Set(alertmanager_push.group_key.clone()),
+                status: Set(AlertStatus::from(&alert.status)),
+                starts_at: Set(alert.starts_at),
+                ends_at: Set(alert.ends_at),
+                generator_url: Set(alert.generator_url.clone()),
+                fingerprint: Set(alert.fingerprint.clone()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        // Matched by the unique group key and fingerprint
+        let alert_ids = insert_rows_returning(
+            db,
+            "alerts",
+            7,
+            &insertable_alerts,
+            None,
+            [
+                alerts::Column::GroupKey,
+                alerts::Column::Fingerprint,
+                alerts::Column::Id,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|(group_key, fingerprint, id): (String, String, i32)| ((group_key, fingerprint), id))
+        .collect::<HashMap<_, _>>();
+
+        let alert_id = |alertmanager_push: &AlertmanagerPush, alert: &AlertmanagerPushAlert| {
+            id(
+                &alert_ids,
+                "alerts",
+                (&alertmanager_push.group_key, &alert.fingerprint),
+            )
+        };
+
+        let alert_labels = alerts()
+            .flat_map(|(_, alertmanager_push, alert)| {
+                alert.labels.iter().map(move |label| {
+                    Ok(alerts_labels::ActiveModel {
+                        alert_id: Set(alert_id(alertmanager_push, alert)?),
+                        label_id: Set(id(label_ids, "labels", label)?),
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows(db, "alerts_labels", 2, &alert_labels).await?;
+
+        let alert_annotations = alerts()
+            .flat_map(|(_, alertmanager_push, alert)| {
+                alert.annotations.iter().map(move |annotation| {
+                    Ok(alerts_annotations::ActiveModel {
+                        alert_id: Set(alert_id(alertmanager_push, alert)?),
+                        annotation_id: Set(id(annotation_ids, "annotations", annotation)?),
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_rows(db, "alerts_annotations", 2, &alert_annotations).await?;
+
+        Ok(())
+    }
+
     async fn push_alert_with_internal_error(
         &self,
         alertmanager_push: &AlertmanagerPush,
@@ -587,6 +927,30 @@ impl PostgresSeaPlugin {
 
         Ok(())
     }
+
+    async fn push_alerts_with_internal_error(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<(), InternalPushError> {
+        tracing::trace!(count = alertmanager_pushes.len(), "Beginning transaction.");
+
+        let txn = self
+            .db
+            .begin()
+            .await
+            .map_err(InternalPushError::TransactionBegin)?;
+
+        let group_ids = Self::insert_groups(&txn, alertmanager_pushes).await?;
+        Self::insert_batch_details(&txn, &group_ids, alertmanager_pushes).await?;
+
+        tracing::trace!("Committing transaction.");
+
+        txn.commit()
+            .await
+            .map_err(InternalPushError::TransactionCommit)?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -602,13 +966,57 @@ impl Push for PostgresSeaPlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         self.push_alert_with_internal_error(alertmanager_push)
             .await?;
 
         tracing::trace!("Successfully pushed.");
+        Ok(PushOutcome::Pushed)
+    }
+
+    /// Pushes all in a single transaction. If one push fails, none is stored
+    #[tracing::instrument(name = "push_alerts", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn push_alerts(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Vec<Result<PushOutcome, PushError>> {
+        if alertmanager_pushes.is_empty() {
+            return vec![];
+        }
+
+        tracing::trace!(count = alertmanager_pushes.len(), "Pushing.");
+
+        if let Err(error) = self
+            .push_alerts_with_internal_error(alertmanager_pushes)
+            .await
+        {
+            return PushError::from(error).for_batch(alertmanager_pushes.len());
+        }
+
+        tracing::trace!("Successfully pushed.");
+        alertmanager_pushes
+            .iter()
+            .map(|_| Ok(PushOutcome::Pushed))
+            .collect()
+    }
+
+    #[tracing::instrument(name = "push_shutdown", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        tracing::trace!("Shutting down.");
+
+        // Waits for the connections in use to be returned
+        self.db
+            .clone()
+            .close()
+            .await
+            .map_err(InternalShutdownError::from)?;
+
+        tracing::trace!("Successfully shut down.");
         Ok(())
     }
 }
@@ -1,9 +1,16 @@
 use plugins_definitions::HealthError;
 use pull_definitions::PullError;
-use push_definitions::{InitializeError, PushError};
+use push_definitions::{ErrorClass, InitializeError, PushError};
 use sqlx::{migrate::MigrateError, Error as SqlxError};
 use thiserror::Error as ThisError;
 
+#[derive(ThisError, Debug)]
+#[error("Inserted row not returned. table: {table}, key: {key}")]
+pub struct MissingIdError {
+    pub table: &'static str,
+    pub key: String,
+}
+
 #[derive(ThisError, Debug)]
 pub enum InternalPushError {
     #[error("Error getting connection from pool: {0}")]
@@ -18,6 +25,25 @@ pub enum InternalPushError {
         #[source]
         error: SqlxError,
     },
+    #[error("Error inserting {count} alert groups. error: {error}")]
+    GroupsInsertion {
+        count: usize,
+        #[source]
+        error: SqlxError,
+    },
+    #[error("Error inserting {count} rows into {table}. error: {error}")]
+    BatchInsertion {
+        table: &'static str,
+        count: usize,
+        #[source]
+        error: SqlxError,
+    },
+    #[error("{0}")]
+    MissingId(
+        #[source]
+        #[from]
+        MissingIdError,
+    ),
     #[error("Error getting group label id. group_key: {group_key}, label_name: {label_name}, label_value: {label_value}, error: {error}")]
     GroupLabelId {
         group_key: String,
@@ -155,11 +181,43 @@ pub enum InternalPushError {
     },
 }
 
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        std::error::Error::source(self)
+            .and_then(|source| source.downcast_ref::<SqlxError>())
+            .map(sqlx_error_class)
+            .unwrap_or_default()
+    }
+}
+
+/// Lost connections and an exhausted pool are transient, and so are
+/// connection exceptions, transaction rollbacks like serialization failures and deadlocks,
+/// insufficient resources and operator intervention, by their SQLSTATE class
+fn sqlx_error_class(error: &SqlxError) -> ErrorClass {
+    match error {
+        SqlxError::Io(_)
+        | SqlxError::Tls(_)
+        | SqlxError::Protocol(_)
+        | SqlxError::PoolTimedOut
+        | SqlxError::PoolClosed
+        | SqlxError::WorkerCrashed => ErrorClass::Transient,
+        SqlxError::Database(error) => match error.code() {
+            Some(code)
+                if ["08", "40", "53", "57"]
+                    .iter()
+                    .any(|class| code.starts_with(class)) =>
+            {
+                ErrorClass::Transient
+            }
+            _ => ErrorClass::Permanent,
+        },
+        _ => ErrorClass::Permanent,
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
-        Self {
-            error: error.into(),
-        }
+        Self::new(error.class(), error)
     }
 }
 
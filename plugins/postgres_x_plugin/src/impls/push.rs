@@ -1,13 +1,93 @@
 use crate::{
     database::models::alert_status::AlertStatusModel,
-    error::{InternalInitializeError, InternalPushError},
+    error::{InternalInitializeError, InternalPushError, MissingIdError},
     PostgresXPlugin,
 };
 use async_trait::async_trait;
-use models::AlertmanagerPush;
+use models::{Alert, AlertmanagerPush};
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
-use sqlx::Connection;
+use push_definitions::{InitializeError, Push, PushError, PushOutcome, ShutdownError};
+use sqlx::{Connection, PgConnection, Postgres, QueryBuilder};
+use std::collections::{BTreeSet, HashMap};
+
+/// Most bind parameters Postgres accepts in one statement
+const MAX_PARAMETERS: usize = u16::MAX as usize;
+
+/// Inserts the missing name-value pairs into a labels or annotations table, in chunks,
+/// and returns the ids of all pairs
+async fn upsert_name_values(
+    conn: &mut PgConnection,
+    table: &'static str,
+    pairs: &BTreeSet<(&String, &String)>,
+) -> Result<HashMap<(String, String), i32>, InternalPushError> {
+    let pairs = pairs.iter().collect::<Vec<_>>();
+
+    let mut ids = HashMap::new();
+    for chunk in pairs.chunks(MAX_PARAMETERS / 2) {
+        let mut query =
+            QueryBuilder::<Postgres>::new(format!("INSERT INTO {table} (name, value) "));
+        query.push_values(chunk, |mut row, (name, value)| {
+            row.push_bind(*name).push_bind(*value);
+        });
+        // Changes nothing, but returns the existing rows as well
+        query.push(" ON CONFLICT (name, value) DO UPDATE SET name = EXCLUDED.name RETURNING name, value, id");
+
+        let rows = query
+            .build_query_as::<(String, String, i32)>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|error| InternalPushError::BatchInsertion {
+                table,
+                count: pairs.len(),
+                error,
+            })?;
+        ids.extend(
+            rows.into_iter()
+                .map(|(name, value, id)| ((name, value), id)),
+        );
+    }
+
+    Ok(ids)
+}
+
+/// Inserts the id pairs into an assignment table, in chunks
+async fn insert_assignments(
+    conn: &mut PgConnection,
+    table: &'static str,
+    columns: &str,
+    rows: &[(i32, i32)],
+) -> Result<(), InternalPushError> {
+    for chunk in rows.chunks(MAX_PARAMETERS / 2) {
+        let mut query = QueryBuilder::<Postgres>::new(format!("INSERT INTO {table} ({columns}) "));
+        query.push_values(chunk, |mut row, (a, b)| {
+            row.push_bind(*a).push_bind(*b);
+        });
+
+        query.build().execute(&mut *conn).await.map_err(|error| {
+            InternalPushError::BatchInsertion {
+                table,
+                count: rows.len(),
+                error,
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Id of an inserted row by its unique pair
+fn id(
+    ids: &HashMap<(String, String), i32>,
+    table: &'static str,
+    (a, b): (&String, &String),
+) -> Result<i32, MissingIdError> {
+    let key = (a.clone(), b.clone());
+
+    ids.get(&key).copied().ok_or_else(|| MissingIdError {
+        table,
+        key: format!("{}, {}", key.0, key.1),
+    })
+}
 
 impl PostgresXPlugin {
     async fn initialize_with_internal_error(&mut self) -> Result<(), InternalInitializeError> {
@@ -16,39 +96,220 @@ impl PostgresXPlugin {
         Ok(())
     }
 
-    async fn push_alert_with_internal_error(
-        &self,
-        alertmanager_push: &AlertmanagerPush,
+    /// Inserts the groups in chunks and returns their ids in order.
+    ///
+    /// The ids are matched by the unique group key, since the order of `RETURNING` is not guaranteed.
+    async fn insert_groups(
+        conn: &mut PgConnection,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<Vec<i32>, InternalPushError> {
+        let mut group_ids = HashMap::new();
+        for chunk in alertmanager_pushes.chunks(MAX_PARAMETERS / 4) {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "INSERT INTO groups (group_key, receiver, status, external_url) ",
+            );
+            query.push_values(chunk, |mut row, alertmanager_push| {
+                row.push_bind(&alertmanager_push.group_key)
+                    .push_bind(&alertmanager_push.receiver)
+                    .push_bind(AlertStatusModel::from(&alertmanager_push.status))
+                    .push_bind(&alertmanager_push.external_url);
+            });
+            query.push(" RETURNING group_key, id");
+
+            let rows = query
+                .build_query_as::<(String, i32)>()
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(|error| InternalPushError::GroupsInsertion {
+                    count: alertmanager_pushes.len(),
+                    error,
+                })?;
+            group_ids.extend(rows);
+        }
+
+        alertmanager_pushes
+            .iter()
+            .map(|alertmanager_push| {
+                group_ids
+                    .get(&alertmanager_push.group_key)
+                    .copied()
+                    .ok_or_else(|| MissingIdError {
+                        table: "groups",
+                        key: alertmanager_push.group_key.clone(),
+                    })
+            })
+            .collect::<Result<_, _>>()
+            .map_err(InternalPushError::MissingId)
+    }
+
+    /// Inserts the labels, annotations, alerts and assignments of the inserted groups,
+    /// with one statement per table and chunk
+    async fn insert_batch_details(
+        conn: &mut PgConnection,
+        group_ids: &[i32],
+        alertmanager_pushes: &[AlertmanagerPush],
     ) -> Result<(), InternalPushError> {
-        let mut conn = self
-            .pool
-            .acquire()
-            .await
-            .map_err(InternalPushError::Acquire)?;
+        let groups = || group_ids.iter().copied().zip(alertmanager_pushes.iter());
+        let alerts = || {
+            groups().flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .alerts
+                    .iter()
+                    .map(move |alert| (group_id, alertmanager_push, alert))
+            })
+        };
 
-        tracing::trace!("Beginning transaction.");
-        let mut tx = conn
-            .begin()
-            .await
-            .map_err(InternalPushError::TransactionBegin)?;
+        let labels = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.group_labels.iter())
+            .chain(alerts().flat_map(|(_, _, alert)| alert.labels.iter()))
+            .collect::<BTreeSet<_>>();
+        let label_ids = &upsert_name_values(conn, "labels", &labels).await?;
 
-        let status = AlertStatusModel::from(&alertmanager_push.status);
-        let group_id = sqlx::query!(
-        r#"
-        INSERT INTO groups (group_key, receiver, status, external_url) VALUES ($1, $2, $3, $4) RETURNING id
-        "#, 
-        alertmanager_push.group_key,
-        alertmanager_push.receiver,
-        status as AlertStatusModel,
-        alertmanager_push.external_url
-    )
-    .fetch_one(&mut *tx)
-    .await.map_err(|error| InternalPushError::GroupInsertion{
-        group_key: alertmanager_push.group_key.clone(),
-        error
-    })?
-    .id;
+        let common_labels = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.common_labels.iter())
+            .collect::<BTreeSet<_>>();
+        let common_label_ids = &upsert_name_values(conn, "common_labels", &common_labels).await?;
+
+        let common_annotations = groups()
+            .flat_map(|(_, alertmanager_push)| alertmanager_push.common_annotations.iter())
+            .collect::<BTreeSet<_>>();
+        let common_annotation_ids =
+            &upsert_name_values(conn, "common_annotations", &common_annotations).await?;
+
+        let annotations = alerts()
+            .flat_map(|(_, _, alert)| alert.annotations.iter())
+            .collect::<BTreeSet<_>>();
+        let annotation_ids = &upsert_name_values(conn, "annotations", &annotations).await?;
+
+        let group_labels = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .group_labels
+                    .iter()
+                    .map(move |label| Ok((group_id, id(label_ids, "labels", label)?)))
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_assignments(conn, "groups_labels", "group_id, label_id", &group_labels).await?;
+
+        let group_common_labels = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .common_labels
+                    .iter()
+                    .map(move |label| Ok((group_id, id(common_label_ids, "common_labels", label)?)))
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_assignments(
+            conn,
+            "groups_common_labels",
+            "group_id, common_label_id",
+            &group_common_labels,
+        )
+        .await?;
+
+        let group_common_annotations = groups()
+            .flat_map(|(group_id, alertmanager_push)| {
+                alertmanager_push
+                    .common_annotations
+                    .iter()
+                    .map(move |annotation| {
+                        Ok((
+                            group_id,
+                            id(common_annotation_ids, "common_annotations", annotation)?,
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_assignments(
+            conn,
+            "groups_common_annotations",
+            "group_id, common_annotation_id",
+            &group_common_annotations,
+        )
+        .await?;
+
+        let alert_rows = alerts().collect::<Vec<_>>();
+        // Matched by the unique group key and fingerprint
+        let mut alert_ids = HashMap::new();
+        for chunk in alert_rows.chunks(MAX_PARAMETERS / 7) {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "INSERT INTO alerts (group_id, group_key, status, starts_at, ends_at, generator_url, fingerprint) ",
+            );
+            query.push_values(chunk, |mut row, (group_id, alertmanager_push, alert)| {
+                row.push_bind(*group_id)
+                    .push_bind(&alertmanager_push.group_key)
+                    .push_bind(AlertStatusModel::from(&alert.status))
+                    .push_bind(alert.starts_at)
+                    .push_bind(alert.ends_at)
+                    .push_bind(&alert.generator_url)
+                    .push_bind(&alert.fingerprint);
+            });
+            query.push(" RETURNING group_key, fingerprint, id");
+
+            let rows = query
+                .build_query_as::<(String, String, i32)>()
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(|error| InternalPushError::BatchInsertion {
+                    table: "alerts",
+                    count: alert_rows.len(),
+                    error,
+                })?;
+            alert_ids.extend(
+                rows.into_iter()
+                    .map(|(group_key, fingerprint, id)| ((group_key, fingerprint), id)),
+            );
+        }
+
+        let alert_id = |alertmanager_push: &AlertmanagerPush, alert: &Alert| {
+            id(
+                &alert_ids,
+                "alerts",
+                (&alertmanager_push.group_key, &alert.fingerprint),
+            )
+        };
+
+        let alert_labels = alert_rows
+            .iter()
+            .flat_map(|(_, alertmanager_push, alert)| {
+                alert.labels.iter().map(move |label| {
+                    Ok((
+                        alert_id(alertmanager_push, alert)?,
+                        id(label_ids, "labels", label)?,
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_assignments(conn, "alerts_labels", "alert_id, label_id", &alert_labels).await?;
+
+        let alert_annotations = alert_rows
+            .iter()
+            .flat_map(|(_, alertmanager_push, alert)| {
+                alert.annotations.iter().map(move |annotation| {
+                    Ok((
+                        alert_id(alertmanager_push, alert)?,
+                        id(annotation_ids, "annotations", annotation)?,
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, MissingIdError>>()?;
+        insert_assignments(
+            conn,
+            "alerts_annotations",
+            "alert_id, annotation_id",
+            &alert_annotations,
+        )
+        .await?;
+
+        Ok(())
+    }
 
+    /// Inserts the labels, annotations and alerts of an inserted group
+    async fn insert_push_details(
+        conn: &mut PgConnection,
+        group_id: i32,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<(), InternalPushError> {
         for (label_name, label_value) in alertmanager_push.group_labels.iter() {
             let label_id_opt = sqlx::query!(
                 r#"
@@ -57,7 +318,7 @@ impl PostgresXPlugin {
                 label_name,
                 label_value
             )
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut *conn)
             .await
             .map_err(|error| InternalPushError::GroupLabelId {
                 group_key: alertmanager_push.group_key.clone(),
@@ -84,7 +345,7 @@ impl PostgresXPlugin {
                         label_name,
                         label_value
                     )
-                    .fetch_one(&mut *tx)
+                    .fetch_one(&mut *conn)
                     .await
                     .map_err(|error| InternalPushError::GroupLabelInsertion {
                         group_key: alertmanager_push.group_key.clone(),
@@ -103,7 +364,7 @@ impl PostgresXPlugin {
                 group_id,
                 label_id
             )
-            .execute(&mut *tx)
+            .execute(&mut *conn)
             .await
             .map_err(|error| InternalPushError::GroupLabelAssignment {
                 group_key: alertmanager_push.group_key.clone(),
@@ -121,7 +382,7 @@ impl PostgresXPlugin {
                 common_label_name,
                 common_label_value
             )
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut *conn)
             .await
             .map_err(|error| InternalPushError::CommonLabelId {
                 group_key: alertmanager_push.group_key.clone(),
@@ -148,7 +409,7 @@ impl PostgresXPlugin {
                         common_label_name,
                         common_label_value
                     )
-                    .fetch_one(&mut *tx)
+                    .fetch_one(&mut *conn)
                     .await
                     .map_err(|error| InternalPushError::CommonLabelInsertion {
                         group_key: alertmanager_push.group_key.clone(),
@@ -167,7 +428,7 @@ impl PostgresXPlugin {
                 group_id,
                 common_label_id
             )
-            .execute(&mut *tx)
+            .execute(&mut *conn)
             .await
             .map_err(|error| InternalPushError::CommonLabelAssignment {
                 group_key: alertmanager_push.group_key.clone(),
@@ -187,7 +448,7 @@ impl PostgresXPlugin {
                 common_annotation_name,
                 common_annotation_value
             )
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut *conn)
             .await
             .map_err(|error| InternalPushError::CommonAnnotationId {
                 group_key: alertmanager_push.group_key.clone(),
@@ -214,7 +475,7 @@ impl PostgresXPlugin {
                         common_annotation_name,
                         common_annotation_value
                     )
-                    .fetch_one(&mut *tx)
+                    .fetch_one(&mut *conn)
                     .await
                     .map_err(|error| InternalPushError::CommonAnnotationInsertion {
                         group_key: alertmanager_push.group_key.clone(),
@@ -233,7 +494,7 @@ impl PostgresXPlugin {
                 group_id,
                 common_annotation_id
             )
-            .execute(&mut *tx)
+            .execute(&mut *conn)
             .await
             .map_err(|error| InternalPushError::CommonAnnotationAssignment {
                 group_key: alertmanager_push.group_key.clone(),
@@ -257,7 +518,7 @@ impl PostgresXPlugin {
             alert.generator_url,
             alert.fingerprint
         )
-        .fetch_one(&mut *tx)
+        .fetch_one(&mut *conn)
         .await.map_err(|error| InternalPushError::AlertInsertion{
             group_key: alertmanager_push.group_key.clone(),
             fingerprint: alert.fingerprint.clone(),
@@ -273,7 +534,7 @@ impl PostgresXPlugin {
                     label_name,
                     label_value
                 )
-                .fetch_optional(&mut *tx)
+                .fetch_optional(&mut *conn)
                 .await
                 .map_err(|error| InternalPushError::AlertLabelId {
                     group_key: alertmanager_push.group_key.clone(),
@@ -301,7 +562,7 @@ impl PostgresXPlugin {
                             label_name,
                             label_value
                         )
-                        .fetch_one(&mut *tx)
+                        .fetch_one(&mut *conn)
                         .await
                         .map_err(|error| InternalPushError::AlertLabelInsertion {
                             group_key: alertmanager_push.group_key.clone(),
@@ -321,7 +582,7 @@ impl PostgresXPlugin {
                     alert_id,
                     label_id
                 )
-                .execute(&mut *tx)
+                .execute(&mut *conn)
                 .await
                 .map_err(|error| InternalPushError::AlertLabelAssignment {
                     group_key: alertmanager_push.group_key.clone(),
@@ -340,7 +601,7 @@ impl PostgresXPlugin {
                     annotation_name,
                     annotation_value
                 )
-                .fetch_optional(&mut *tx)
+                .fetch_optional(&mut *conn)
                 .await
                 .map_err(|error| InternalPushError::AlertAnnotationId {
                     group_key: alertmanager_push.group_key.clone(),
@@ -368,7 +629,7 @@ impl PostgresXPlugin {
                             annotation_name,
                             annotation_value
                         )
-                        .fetch_one(&mut *tx)
+                        .fetch_one(&mut *conn)
                         .await
                         .map_err(|error| InternalPushError::AlertAnnotationInsertion {
                             group_key: alertmanager_push.group_key.clone(),
@@ -388,7 +649,7 @@ impl PostgresXPlugin {
                     alert_id,
                     annotation_id
                 )
-                .execute(&mut *tx)
+                .execute(&mut *conn)
                 .await
                 .map_err(|error| InternalPushError::AlertAnnotationAssignment {
                     group_key: alertmanager_push.group_key.clone(),
@@ -400,6 +661,71 @@ impl PostgresXPlugin {
             }
         }
 
+        Ok(())
+    }
+
+    async fn push_alert_with_internal_error(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<(), InternalPushError> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(InternalPushError::Acquire)?;
+
+        tracing::trace!("Beginning transaction.");
+        let mut tx = conn
+            .begin()
+            .await
+            .map_err(InternalPushError::TransactionBegin)?;
+
+        let status = AlertStatusModel::from(&alertmanager_push.status);
+        let group_id = sqlx::query!(
+        r#"
+        INSERT INTO groups (group_key, receiver, status, external_url) VALUES ($1, $2, $3, $4) RETURNING id
+        "#, 
+        alertmanager_push.group_key,
+        alertmanager_push.receiver,
+        status as AlertStatusModel,
+        alertmanager_push.external_url
+    )
+    .fetch_one(&mut *tx)
+    .await.map_err(|error| InternalPushError::GroupInsertion{
+        group_key: alertmanager_push.group_key.clone(),
+        error
+    })?
+    .id;
+
+        Self::insert_push_details(&mut tx, group_id, alertmanager_push).await?;
+
+        tracing::trace!("Committing transaction.");
+        tx.commit()
+            .await
+            .map_err(InternalPushError::TransactionCommit)?;
+
+        Ok(())
+    }
+
+    async fn push_alerts_with_internal_error(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Result<(), InternalPushError> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(InternalPushError::Acquire)?;
+
+        tracing::trace!(count = alertmanager_pushes.len(), "Beginning transaction.");
+        let mut tx = conn
+            .begin()
+            .await
+            .map_err(InternalPushError::TransactionBegin)?;
+
+        let group_ids = Self::insert_groups(&mut tx, alertmanager_pushes).await?;
+        Self::insert_batch_details(&mut tx, &group_ids, alertmanager_pushes).await?;
+
         tracing::trace!("Committing transaction.");
         tx.commit()
             .await
@@ -422,13 +748,53 @@ impl Push for PostgresXPlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         self.push_alert_with_internal_error(alertmanager_push)
             .await?;
 
         tracing::trace!("Successfully pushed.");
+        Ok(PushOutcome::Pushed)
+    }
+
+    /// Pushes all in a single transaction. If one push fails, none is stored
+    #[tracing::instrument(name = "push_alerts", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn push_alerts(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Vec<Result<PushOutcome, PushError>> {
+        if alertmanager_pushes.is_empty() {
+            return vec![];
+        }
+
+        tracing::trace!(count = alertmanager_pushes.len(), "Pushing.");
+
+        if let Err(error) = self
+            .push_alerts_with_internal_error(alertmanager_pushes)
+            .await
+        {
+            return PushError::from(error).for_batch(alertmanager_pushes.len());
+        }
+
+        tracing::trace!("Successfully pushed.");
+        alertmanager_pushes
+            .iter()
+            .map(|_| Ok(PushOutcome::Pushed))
+            .collect()
+    }
+
+    #[tracing::instrument(name = "push_shutdown", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        tracing::trace!("Shutting down.");
+
+        // Waits for the connections in use to be returned
+        self.pool.close().await;
+
+        tracing::trace!("Successfully shut down.");
         Ok(())
     }
 }
@@ -100,6 +100,18 @@ mod test {
         }
     }
 
+    #[ignore]
+    #[tokio::test]
+    #[traced_test]
+    // cargo test --package postgres_x_plugin --lib --release -- test::push_random_alerts_batch --exact --nocapture --ignored
+    async fn push_random_alerts_batch() {
+        let plugin = create_and_init_plugin().await;
+        let pushes = generate_random_alertmanager_pushes(100);
+        for result in plugin.push_alerts(&pushes).await {
+            result.expect("Failed to push alerts.");
+        }
+    }
+
     #[ignore]
     #[tokio::test]
     #[traced_test]
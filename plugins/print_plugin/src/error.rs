@@ -1,5 +1,5 @@
 use formatter::{FormatError, NewFormatterError};
-use push_definitions::PushError;
+use push_definitions::{ErrorClass, PushError};
 use thiserror::Error as ThisError;
 
 #[derive(ThisError, Debug)]
@@ -28,10 +28,17 @@ pub enum InternalPushError {
     ),
 }
 
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InternalPushError::Format(_) => ErrorClass::Permanent,
+            InternalPushError::Write(_) => ErrorClass::Transient,
+        }
+    }
+}
+
 impl From<InternalPushError> for PushError {
     fn from(error: InternalPushError) -> Self {
-        Self {
-            error: error.into(),
-        }
+        Self::new(error.class(), error)
     }
 }
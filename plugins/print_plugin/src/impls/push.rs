@@ -2,7 +2,7 @@ use crate::{error::InternalPushError, PrintPlugin};
 use async_trait::async_trait;
 use models::AlertmanagerPush;
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
+use push_definitions::{InitializeError, Push, PushError, PushOutcome};
 use tokio::io::{self, AsyncWriteExt};
 
 impl PrintPlugin {
@@ -33,13 +33,16 @@ impl Push for PrintPlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         self.push_alert_with_internal_error(alertmanager_push)
             .await?;
 
         tracing::trace!("Successfully pushed.");
-        Ok(())
+        Ok(PushOutcome::Pushed)
     }
 }
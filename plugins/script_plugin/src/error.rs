@@ -0,0 +1,117 @@
+use push_definitions::{ErrorClass, PushError};
+use rhai::{EvalAltResult, ParseError};
+use std::path::PathBuf;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum NewScriptError {
+    #[error("Failed to read script: path: {path}, {error}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("Failed to compile script: path: {path}, {error}")]
+    Compile {
+        path: PathBuf,
+        #[source]
+        error: ParseError,
+    },
+    #[error("Failed to watch script: {0}")]
+    Watch(
+        #[source]
+        #[from]
+        notify::Error,
+    ),
+}
+
+#[derive(ThisError, Debug)]
+pub enum NewScriptPluginError {
+    #[error("Failed to create http client: {0}")]
+    HttpClient(
+        #[source]
+        #[from]
+        http_client::NewHttpClientError,
+    ),
+    #[error("Failed to create script: {0}")]
+    Script(
+        #[source]
+        #[from]
+        NewScriptError,
+    ),
+}
+
+#[derive(ThisError, Debug)]
+pub enum ScriptError {
+    #[error("Failed to convert push: {0}")]
+    Convert(#[source] Box<EvalAltResult>),
+    /// Also raised when the script exceeds a limit
+    #[error("Script failed: {0}")]
+    Eval(#[source] Box<EvalAltResult>),
+    #[error("Script timed out")]
+    Timeout,
+    #[error("Script returned {0}, expected (), a bool or an array of plugin names")]
+    Result(String),
+    #[error("Script run was cancelled or panicked: {0}")]
+    Join(
+        #[source]
+        #[from]
+        tokio::task::JoinError,
+    ),
+}
+
+impl ScriptError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            ScriptError::Join(_) => ErrorClass::Transient,
+            // Same push, same script, same failure
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum InternalPushError {
+    #[error("Loop detected")]
+    LoopDetected,
+    #[error("Failed to run script: {0}")]
+    Script(
+        #[source]
+        #[from]
+        ScriptError,
+    ),
+    #[error("Reqwest error: {0}")]
+    Reqwest(
+        #[source]
+        #[from]
+        reqwest::Error,
+    ),
+    #[error("Got error response: status code: {status_code}, body: {body}")]
+    ErrorResponse {
+        status_code: reqwest::StatusCode,
+        body: String,
+    },
+}
+
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InternalPushError::Script(error) => error.class(),
+            InternalPushError::Reqwest(error) if http_client::is_transient_error(error) => {
+                ErrorClass::Transient
+            }
+            InternalPushError::ErrorResponse { status_code, .. }
+                if http_client::is_transient_status(*status_code) =>
+            {
+                ErrorClass::Transient
+            }
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
+impl From<InternalPushError> for PushError {
+    fn from(error: InternalPushError) -> Self {
+        Self::new(error.class(), error)
+    }
+}
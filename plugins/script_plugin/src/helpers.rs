@@ -0,0 +1,177 @@
+//! Functions available to scripts
+//!
+//! Label helpers work on an alert map and can be called as methods, like `alert.get_label("instance")`.
+//! Matchers take expressions of the alert filter, like `matches(push, alert, "status is firing")`.
+
+use models::{Alert, AlertmanagerPush};
+use plugins_filter::ast::AlertExpr;
+use regex::Regex;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Compiled regexes and parsed alert filters kept between runs. Cleared when full
+const CACHE_SIZE: usize = 256;
+
+type EvalResult<T> = Result<T, Box<EvalAltResult>>;
+
+pub(crate) fn register(engine: &mut Engine) {
+    engine
+        .register_fn("get_label", get_label)
+        .register_fn("has_label", has_label)
+        .register_fn("set_label", set_label)
+        .register_fn("remove_label", remove_label);
+
+    let exprs = Cache::default();
+    let any_exprs = exprs.clone();
+
+    engine
+        .register_fn("matches", move |push: Map, alert: Map, expr: &str| {
+            matches(&exprs, push, alert, expr)
+        })
+        .register_fn("any_matches", move |push: Map, expr: &str| {
+            any_matches(&any_exprs, push, expr)
+        });
+
+    let regexes = Cache::default();
+    let captures_regexes = regexes.clone();
+
+    engine
+        .register_fn("regex_match", move |text: &str, pattern: &str| {
+            Ok::<_, Box<EvalAltResult>>(regex(&regexes, pattern)?.is_match(text))
+        })
+        .register_fn("regex_captures", move |text: &str, pattern: &str| {
+            regex_captures(&captures_regexes, text, pattern)
+        });
+}
+
+/// Labels of an alert map. Missing labels are empty maps
+fn labels(alert: &Map) -> Map {
+    alert
+        .get("labels")
+        .and_then(|labels| labels.read_lock::<Map>().map(|labels| labels.clone()))
+        .unwrap_or_default()
+}
+
+/// Missing labels are empty strings, like in the alert filter
+fn get_label(alert: &mut Map, name: &str) -> String {
+    labels(alert)
+        .get(name)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+fn has_label(alert: &mut Map, name: &str) -> bool {
+    labels(alert).contains_key(name)
+}
+
+fn set_label(alert: &mut Map, name: &str, value: &str) {
+    let mut labels = labels(alert);
+    labels.insert(name.into(), value.into());
+    alert.insert("labels".into(), labels.into());
+}
+
+/// Returns whether the label was present
+fn remove_label(alert: &mut Map, name: &str) -> bool {
+    let mut labels = labels(alert);
+    let removed = labels.remove(name).is_some();
+    alert.insert("labels".into(), labels.into());
+    removed
+}
+
+fn alert_expr(exprs: &Cache<Arc<AlertExpr>>, expr: &str) -> EvalResult<Arc<AlertExpr>> {
+    exprs.get(expr, |expr| {
+        expr.parse::<AlertExpr>()
+            .map(Arc::new)
+            .map_err(|error| format!("Invalid alert filter: {error}").into())
+    })
+}
+
+fn regex(regexes: &Cache<Regex>, pattern: &str) -> EvalResult<Regex> {
+    regexes.get(pattern, |pattern| {
+        Regex::new(pattern).map_err(|error| format!("Invalid regex: {error}").into())
+    })
+}
+
+/// Usually called for every alert of the push,
+/// so the alerts of the push, which the filter does not look at, are not converted
+fn matches(
+    exprs: &Cache<Arc<AlertExpr>>,
+    mut push: Map,
+    alert: Map,
+    expr: &str,
+) -> EvalResult<bool> {
+    let expr = alert_expr(exprs, expr)?;
+    push.insert("alerts".into(), Dynamic::from_array(Array::new()));
+    let push: AlertmanagerPush = rhai::serde::from_dynamic(&Dynamic::from_map(push))?;
+    let alert: Alert = rhai::serde::from_dynamic(&Dynamic::from_map(alert))?;
+
+    Ok(expr.is_match(&push, &alert))
+}
+
+fn any_matches(exprs: &Cache<Arc<AlertExpr>>, push: Map, expr: &str) -> EvalResult<bool> {
+    let expr = alert_expr(exprs, expr)?;
+    let push: AlertmanagerPush = rhai::serde::from_dynamic(&Dynamic::from_map(push))?;
+
+    Ok(push.alerts.iter().any(|alert| expr.is_match(&push, alert)))
+}
+
+/// The whole match and every group. Unmatched groups are empty strings.
+/// Empty if the regex does not match
+fn regex_captures(regexes: &Cache<Regex>, text: &str, pattern: &str) -> EvalResult<Array> {
+    let regex = regex(regexes, pattern)?;
+
+    let Some(captures) = regex.captures(text) else {
+        return Ok(Array::new());
+    };
+
+    Ok(captures
+        .iter()
+        .map(|group| group.map(|group| group.as_str()).unwrap_or_default().into())
+        .collect())
+}
+
+/// Values parsed from strings, shared by all runs
+struct Cache<T> {
+    entries: Arc<Mutex<HashMap<String, T>>>,
+}
+
+impl<T> Clone for Cache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries),
+        }
+    }
+}
+
+impl<T> Default for Cache<T> {
+    fn default() -> Self {
+        Self {
+            entries: Arc::default(),
+        }
+    }
+}
+
+impl<T: Clone> Cache<T> {
+    fn get(&self, key: &str, parse: impl FnOnce(&str) -> EvalResult<T>) -> EvalResult<T> {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        if let Some(value) = entries.get(key) {
+            return Ok(value.clone());
+        }
+
+        let value = parse(key)?;
+
+        if entries.len() >= CACHE_SIZE {
+            entries.clear();
+        }
+        entries.insert(key.to_string(), value.clone());
+
+        Ok(value)
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{error::InternalPushError, ScriptOutcome, ScriptPlugin};
+use async_trait::async_trait;
+use models::AlertmanagerPush;
+use plugins_definitions::Plugin;
+use plugins_filter::ast::{Expr, SingleOp};
+use push_definitions::{AlertOutcome, AlertResult, InitializeError, Push, PushError, PushOutcome};
+
+impl ScriptPlugin {
+    async fn push_alert_with_internal_error(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, InternalPushError> {
+        if self.is_signature_present(alertmanager_push) {
+            tracing::warn!("Signature present. Loop detected.");
+            return Err(InternalPushError::LoopDetected)?;
+        }
+
+        let (mut scripted_push, targets) = match self.runtime.run(alertmanager_push).await? {
+            ScriptOutcome::Keep(push) => (push, None),
+            ScriptOutcome::Route { push, targets } => (push, Some(targets)),
+            ScriptOutcome::Drop => {
+                tracing::trace!("Push was dropped by the script. Nothing to forward.");
+                return Ok(PushOutcome::Skipped {
+                    reason: String::from("Dropped by script"),
+                });
+            }
+        };
+
+        if scripted_push.alerts.is_empty() {
+            tracing::trace!("All alerts were dropped. Nothing to forward.");
+            return Ok(PushOutcome::Skipped {
+                reason: String::from("All alerts were dropped"),
+            });
+        }
+
+        let results = alertmanager_push
+            .alerts
+            .iter()
+            .map(|alert| {
+                let forwarded = scripted_push
+                    .alerts
+                    .iter()
+                    .any(|scripted| scripted.fingerprint == alert.fingerprint);
+
+                AlertResult {
+                    fingerprint: alert.fingerprint.clone(),
+                    outcome: match forwarded {
+                        true => AlertOutcome::Pushed,
+                        false => AlertOutcome::Skipped {
+                            reason: String::from("Dropped by script"),
+                        },
+                    },
+                }
+            })
+            .collect();
+
+        self.add_signature(&mut scripted_push);
+
+        let mut url = self.config.webhook_url.clone();
+        if let Some(targets) = targets {
+            let filter = Expr::Name(SingleOp::In(targets));
+            url.query_pairs_mut()
+                .append_pair("filter", &filter.to_string());
+        }
+
+        let request = self.client.post(url).json(&scripted_push);
+        let response = self.client.send(request).await?;
+
+        let status_code = response.status();
+        if !status_code.is_success() {
+            let body = response.text().await?;
+
+            return Err(InternalPushError::ErrorResponse { status_code, body });
+        };
+
+        Ok(PushOutcome::from_alerts(results))
+    }
+}
+
+#[async_trait]
+impl Push for ScriptPlugin {
+    #[tracing::instrument(name = "push_initialize", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn initialize(&mut self) -> Result<(), InitializeError> {
+        tracing::trace!("Initializing.");
+
+        tracing::trace!("Successfully initialized.");
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
+        tracing::trace!("Pushing.");
+
+        let outcome = self
+            .push_alert_with_internal_error(alertmanager_push)
+            .await?;
+
+        tracing::trace!("Successfully pushed.");
+        Ok(outcome)
+    }
+}
@@ -0,0 +1,331 @@
+use error::NewScriptPluginError;
+use http_client::{HttpClient, HttpClientConfig};
+use models::AlertmanagerPush;
+use plugins_definitions::Plugin;
+use runtime::Runtime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use url::Url;
+
+mod error;
+mod helpers;
+mod impls;
+mod runtime;
+mod transform;
+
+pub use error::{NewScriptError, ScriptError};
+
+fn default_max_operations() -> u64 {
+    1_000_000
+}
+
+fn default_max_call_levels() -> usize {
+    32
+}
+
+fn default_max_string_size() -> usize {
+    1024 * 1024
+}
+
+fn default_max_collection_size() -> usize {
+    10_000
+}
+
+fn default_timeout_millis() -> u64 {
+    1_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Limits for every run of the script
+pub struct ScriptLimits {
+    /// Operations for a single run. Roughly one per expression or statement
+    #[serde(default = "default_max_operations")]
+    pub max_operations: u64,
+    /// Depth of nested function calls
+    #[serde(default = "default_max_call_levels")]
+    pub max_call_levels: usize,
+    /// Length of a single string in bytes
+    #[serde(default = "default_max_string_size")]
+    pub max_string_size: usize,
+    /// Number of items in a single array
+    #[serde(default = "default_max_collection_size")]
+    pub max_array_size: usize,
+    /// Number of entries in a single map
+    #[serde(default = "default_max_collection_size")]
+    pub max_map_size: usize,
+    /// Wall time for a single run
+    #[serde(default = "default_timeout_millis")]
+    pub timeout_millis: u64,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: default_max_operations(),
+            max_call_levels: default_max_call_levels(),
+            max_string_size: default_max_string_size(),
+            max_array_size: default_max_collection_size(),
+            max_map_size: default_max_collection_size(),
+            timeout_millis: default_timeout_millis(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// A Rhai script run on every push
+///
+/// The push is available as the mutable map `push`, with the keys of the Alertmanager payload
+/// (`commonLabels`, `alerts`, ...). The value of the script decides what happens to it:
+///
+/// - `()` or `true`: the modified push is kept
+/// - `false`: the push is dropped
+/// - an array of plugin names: the modified push is only sent to these plugins
+pub struct ScriptConfig {
+    /// Path to the Rhai script
+    pub script_path: PathBuf,
+    #[serde(default)]
+    pub limits: ScriptLimits,
+    /// Recompile the script when the file changes.
+    /// If it fails to compile, the last good one is kept
+    #[serde(default)]
+    pub watch: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Configuration for the Script plugin
+pub struct ScriptPluginConfig {
+    /// Where the push is forwarded to, usually the `/push` route of another instance
+    pub webhook_url: Url,
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    #[serde(flatten)]
+    pub script: ScriptConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Metadata for the Script plugin
+pub struct ScriptPluginMeta {
+    /// Name of the plugin
+    pub name: String,
+    /// Group of the plugin
+    pub group: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// What the script decided to do with a push
+pub enum ScriptOutcome {
+    /// Send the push to all plugins
+    Keep(AlertmanagerPush),
+    /// Send the push nowhere
+    Drop,
+    /// Send the push only to the plugins with these names
+    Route {
+        push: AlertmanagerPush,
+        targets: Vec<String>,
+    },
+}
+
+/// The Script plugin
+///
+/// Runs the script and forwards the result to `webhook_url`.
+/// Routed pushes are forwarded with a plugin filter selecting the targets by name.
+pub struct ScriptPlugin {
+    /// Meta information for the plugin
+    meta: ScriptPluginMeta,
+    /// Configuration for the plugin
+    config: ScriptPluginConfig,
+    /// Client used to forward the push
+    client: HttpClient,
+    /// Compiled script
+    runtime: Runtime,
+}
+
+impl ScriptPlugin {
+    pub async fn new(
+        meta: ScriptPluginMeta,
+        config: ScriptPluginConfig,
+    ) -> Result<Self, NewScriptPluginError> {
+        let client = HttpClient::new(config.http_client.clone()).await?;
+        let runtime = Runtime::new(config.script.clone()).await?;
+
+        Ok(Self {
+            meta,
+            config,
+            client,
+            runtime,
+        })
+    }
+
+    fn add_signature(&self, push: &mut AlertmanagerPush) {
+        push.common_labels
+            .insert(self.name().to_string(), "scripted".to_string());
+    }
+
+    fn is_signature_present(&self, push: &AlertmanagerPush) -> bool {
+        push.common_labels
+            .get(self.name())
+            .map(|value| value == "scripted")
+            .unwrap_or(false)
+    }
+}
+
+/// A pipeline stage running a script
+pub struct ScriptTransform {
+    /// Compiled script
+    runtime: Runtime,
+}
+
+impl ScriptTransform {
+    pub async fn new(config: ScriptConfig) -> Result<Self, NewScriptError> {
+        Ok(Self {
+            runtime: Runtime::new(config).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use models::{Alert, Status};
+    use std::{io::Write, path::Path};
+    use tempfile::NamedTempFile;
+
+    fn script_file(source: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().expect("failed to create script");
+        file.write_all(source.as_bytes())
+            .expect("failed to write script");
+        file
+    }
+
+    async fn transform(path: &Path, limits: ScriptLimits) -> ScriptTransform {
+        ScriptTransform::new(ScriptConfig {
+            script_path: path.to_path_buf(),
+            limits,
+            watch: false,
+        })
+        .await
+        .expect("failed to compile script")
+    }
+
+    fn push() -> AlertmanagerPush {
+        let alert = |fingerprint: &str, status: Status, instance: &str| Alert {
+            fingerprint: fingerprint.to_string(),
+            status,
+            labels: [("instance".to_string(), instance.to_string())].into(),
+            ..Default::default()
+        };
+
+        AlertmanagerPush {
+            status: Status::Firing,
+            alerts: vec![
+                alert(
+                    "a",
+                    Status::Firing,
+                    "ip-10-0-0-1.eu-west-1.compute.internal",
+                ),
+                alert("b", Status::Resolved, "db-1:9100"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn modify_push() {
+        let file = script_file(
+            r#"
+            for i in 0..push.alerts.len() {
+                let captures = regex_captures(push.alerts[i].get_label("instance"), "^ip-[0-9-]+\\.([a-z0-9-]+)\\.compute\\.internal$");
+                if captures.len() > 0 {
+                    push.alerts[i].set_label("cluster", captures[1]);
+                }
+            }
+
+            let firing = [];
+            for alert in push.alerts {
+                if matches(push, alert, "status is firing") {
+                    firing.push(alert);
+                }
+            }
+            push.alerts = firing;
+            "#,
+        );
+
+        let outcome = transform(file.path(), ScriptLimits::default())
+            .await
+            .transform(&push())
+            .await
+            .expect("script failed");
+
+        let ScriptOutcome::Keep(push) = outcome else {
+            panic!("expected keep, got {outcome:?}");
+        };
+
+        assert_eq!(push.alerts.len(), 1);
+        assert_eq!(push.truncated_alerts, 1);
+        assert_eq!(push.status, Status::Firing);
+        assert_eq!(
+            push.alerts[0].labels.get("cluster"),
+            Some(&"eu-west-1".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn drop_and_route() {
+        let file = script_file(
+            r#"
+            if push.alerts.len() > 2 {
+                return false;
+            }
+
+            if any_matches(push, "label instance like \"db-*\"") {
+                return ["postgres", "print"];
+            }
+            "#,
+        );
+
+        let script = transform(file.path(), ScriptLimits::default()).await;
+
+        let outcome = script.transform(&push()).await.expect("script failed");
+        assert_eq!(
+            outcome,
+            ScriptOutcome::Route {
+                push: push(),
+                targets: vec!["postgres".to_string(), "print".to_string()],
+            }
+        );
+
+        let mut large_push = push();
+        large_push.alerts.extend(push().alerts);
+        let outcome = script.transform(&large_push).await.expect("script failed");
+        assert_eq!(outcome, ScriptOutcome::Drop);
+    }
+
+    #[tokio::test]
+    async fn enforce_limits() {
+        let file = script_file("loop { }");
+
+        let limits = ScriptLimits {
+            max_operations: 1_000,
+            ..Default::default()
+        };
+        let error = transform(file.path(), limits)
+            .await
+            .transform(&push())
+            .await
+            .expect_err("script should fail");
+        assert!(matches!(error, ScriptError::Eval(_)), "{error}");
+
+        let limits = ScriptLimits {
+            max_operations: 0,
+            timeout_millis: 10,
+            ..Default::default()
+        };
+        let error = transform(file.path(), limits)
+            .await
+            .transform(&push())
+            .await
+            .expect_err("script should time out");
+        assert!(matches!(error, ScriptError::Timeout), "{error}");
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{
+    error::{NewScriptError, ScriptError},
+    helpers, ScriptConfig, ScriptLimits, ScriptOutcome,
+};
+use models::AlertmanagerPush;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Operations between two checks of the deadline
+const DEADLINE_CHECK_INTERVAL: u64 = 1_024;
+
+thread_local! {
+    /// Deadline of the run on this thread. The engine is shared, so it can't hold it
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Compiled script with its limits
+pub(crate) struct Runtime {
+    engine: Arc<Engine>,
+    /// Swapped when the script is reloaded
+    ast: Arc<RwLock<Arc<AST>>>,
+    timeout: Duration,
+    /// Recompiles the script while alive
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl Runtime {
+    pub(crate) async fn new(config: ScriptConfig) -> Result<Self, NewScriptError> {
+        let engine = Arc::new(engine(&config.limits));
+
+        let source = tokio::fs::read_to_string(&config.script_path)
+            .await
+            .map_err(|error| NewScriptError::Io {
+                path: config.script_path.clone(),
+                error,
+            })?;
+        let ast = compile(&engine, &config.script_path, &source)?;
+        let ast = Arc::new(RwLock::new(Arc::new(ast)));
+
+        let watcher = match config.watch {
+            true => Some(watch(
+                Arc::clone(&engine),
+                config.script_path,
+                Arc::clone(&ast),
+            )?),
+            false => None,
+        };
+
+        Ok(Self {
+            engine,
+            ast,
+            timeout: Duration::from_millis(config.limits.timeout_millis),
+            _watcher: watcher,
+        })
+    }
+
+    /// Runs the script on a blocking thread, so a slow script does not block other pushes
+    pub(crate) async fn run(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<ScriptOutcome, ScriptError> {
+        let engine = Arc::clone(&self.engine);
+        let ast = Arc::clone(&self.ast.read().unwrap_or_else(|error| error.into_inner()));
+        let deadline = Instant::now() + self.timeout;
+        let alertmanager_push = alertmanager_push.clone();
+
+        tokio::task::spawn_blocking(move || run(&engine, &ast, deadline, alertmanager_push)).await?
+    }
+}
+
+fn engine(limits: &ScriptLimits) -> Engine {
+    let mut engine = Engine::new();
+
+    engine
+        .set_max_operations(limits.max_operations)
+        .set_max_call_levels(limits.max_call_levels)
+        .set_max_string_size(limits.max_string_size)
+        .set_max_array_size(limits.max_array_size)
+        .set_max_map_size(limits.max_map_size);
+
+    engine.disable_symbol("eval");
+
+    engine.on_progress(|operations| {
+        if operations % DEADLINE_CHECK_INTERVAL != 0 {
+            return None;
+        }
+
+        let timed_out = DEADLINE.with(|deadline| {
+            deadline
+                .get()
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        });
+
+        timed_out.then_some(Dynamic::UNIT)
+    });
+
+    engine.on_print(|text| tracing::info!(text, "Script printed."));
+    engine.on_debug(|text, _, position| tracing::debug!(text, %position, "Script debugged."));
+
+    helpers::register(&mut engine);
+
+    engine
+}
+
+fn compile(engine: &Engine, path: &Path, source: &str) -> Result<AST, NewScriptError> {
+    engine
+        .compile(source)
+        .map_err(|error| NewScriptError::Compile {
+            path: path.to_path_buf(),
+            error,
+        })
+}
+
+/// Recompiles the script whenever it changes.
+///
+/// Watches the parent directory, because editors often replace the file instead of writing to it.
+/// If the script fails to compile, the last good one is kept.
+/// Watching stops when the returned watcher is dropped.
+fn watch(
+    engine: Arc<Engine>,
+    path: PathBuf,
+    ast: Arc<RwLock<Arc<AST>>>,
+) -> Result<RecommendedWatcher, NewScriptError> {
+    let (sender, receiver) = mpsc::channel();
+
+    let file_name = path.file_name().map(ToOwned::to_owned);
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event)
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) && event
+                    .paths
+                    .iter()
+                    .any(|changed| changed.file_name() == file_name.as_deref()) =>
+            {
+                let _ = sender.send(());
+            }
+            Ok(_) => {}
+            Err(error) => tracing::error!(%error, "Failed to watch script."),
+        })?;
+
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+    std::thread::spawn(move || {
+        // Ends when the watcher and with it the sender is dropped
+        while receiver.recv().is_ok() {
+            while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+
+            let compiled = std::fs::read_to_string(&path)
+                .map_err(|error| NewScriptError::Io {
+                    path: path.clone(),
+                    error,
+                })
+                .and_then(|source| compile(&engine, &path, &source));
+
+            match compiled {
+                Ok(new_ast) => {
+                    *ast.write().unwrap_or_else(|error| error.into_inner()) = Arc::new(new_ast);
+                    tracing::info!(path = %path.display(), "Reloaded script.");
+                }
+                Err(error) => {
+                    tracing::error!(%error, path = %path.display(), "Failed to reload script. Keeping the last good one.");
+                }
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+fn run(
+    engine: &Engine,
+    ast: &AST,
+    deadline: Instant,
+    original: AlertmanagerPush,
+) -> Result<ScriptOutcome, ScriptError> {
+    let mut scope = Scope::new();
+    scope.push(
+        "push",
+        rhai::serde::to_dynamic(&original).map_err(ScriptError::Convert)?,
+    );
+
+    DEADLINE.with(|cell| cell.set(Some(deadline)));
+    let result = engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast);
+    DEADLINE.with(|cell| cell.set(None));
+
+    let value = result.map_err(|error| match *error {
+        EvalAltResult::ErrorTerminated(..) => ScriptError::Timeout,
+        _ => ScriptError::Eval(error),
+    })?;
+
+    if value.as_bool() == Ok(false) {
+        return Ok(ScriptOutcome::Drop);
+    }
+
+    let push = scope.get_value::<Dynamic>("push").unwrap_or_default();
+    let mut push: AlertmanagerPush =
+        rhai::serde::from_dynamic(&push).map_err(ScriptError::Convert)?;
+    push.count_removed_alerts(original.alerts.len());
+
+    if value.is_unit() || value.as_bool() == Ok(true) {
+        return Ok(ScriptOutcome::Keep(push));
+    }
+
+    let type_name = value.type_name().to_string();
+    let targets = value
+        .into_array()
+        .map_err(|_| ScriptError::Result(type_name.clone()))?
+        .into_iter()
+        .map(|target| {
+            target
+                .into_string()
+                .map_err(|_| ScriptError::Result(type_name.clone()))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(ScriptOutcome::Route { push, targets })
+}
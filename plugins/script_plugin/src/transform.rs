@@ -0,0 +1,12 @@
+use crate::{ScriptError, ScriptOutcome, ScriptTransform};
+use models::AlertmanagerPush;
+
+impl ScriptTransform {
+    /// Runs the script on the push
+    pub async fn transform(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<ScriptOutcome, ScriptError> {
+        self.runtime.run(alertmanager_push).await
+    }
+}
@@ -4,7 +4,7 @@ use diesel::{Connection, SqliteConnection};
 use diesel_migrations::MigrationHarness;
 use models::AlertmanagerPush;
 use plugins_definitions::Plugin;
-use push_definitions::{InitializeError, Push, PushError};
+use push_definitions::{InitializeError, Push, PushError, PushOutcome, ShutdownError};
 use tokio::task::JoinHandle;
 
 impl SqlitePlugin {
@@ -45,13 +45,28 @@ impl Push for SqlitePlugin {
     }
 
     #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError> {
+    async fn push_alert(
+        &self,
+        _alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
         tracing::trace!("Pushing.");
 
         // TODO
         tracing::warn!("Not implemented yet.");
 
-        tracing::trace!("Successfully pushed.");
+        Ok(PushOutcome::Skipped {
+            reason: String::from("Not implemented yet"),
+        })
+    }
+
+    #[tracing::instrument(name = "push_shutdown", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        tracing::trace!("Shutting down.");
+
+        // Drops the idle connections, and those in use once they are returned
+        self.pool.close();
+
+        tracing::trace!("Successfully shut down.");
         Ok(())
     }
 }
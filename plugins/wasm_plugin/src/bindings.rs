@@ -0,0 +1,20 @@
+pub(crate) mod sink {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "sink",
+        async: true,
+    });
+}
+
+pub(crate) mod transform {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "transform",
+        async: true,
+        with: {
+            "alertmanager-ext:plugin/types": super::sink::alertmanager_ext::plugin::types,
+        },
+    });
+}
+
+pub(crate) use sink::alertmanager_ext::plugin::types;
@@ -0,0 +1,183 @@
+//! Conversions between the `models` crate and the WIT types
+
+use crate::bindings::types;
+use chrono::{DateTime, NaiveDateTime, ParseError};
+use models::{Alert, AlertmanagerPush, Status};
+use push_definitions::{AlertOutcome, AlertResult, ErrorClass, PushOutcome};
+use std::collections::BTreeMap;
+
+fn to_rfc3339(date_time: &NaiveDateTime) -> String {
+    date_time.and_utc().to_rfc3339()
+}
+
+fn from_rfc3339(date_time: &str) -> Result<NaiveDateTime, ParseError> {
+    Ok(DateTime::parse_from_rfc3339(date_time)?.naive_utc())
+}
+
+fn to_key_values(map: &BTreeMap<String, String>) -> types::KeyValues {
+    map.iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+impl From<&Status> for types::Status {
+    fn from(status: &Status) -> Self {
+        match status {
+            Status::Resolved => types::Status::Resolved,
+            Status::Firing => types::Status::Firing,
+        }
+    }
+}
+
+impl From<types::Status> for Status {
+    fn from(status: types::Status) -> Self {
+        match status {
+            types::Status::Resolved => Status::Resolved,
+            types::Status::Firing => Status::Firing,
+        }
+    }
+}
+
+impl From<&Alert> for types::Alert {
+    fn from(alert: &Alert) -> Self {
+        Self {
+            status: (&alert.status).into(),
+            labels: to_key_values(&alert.labels),
+            annotations: to_key_values(&alert.annotations),
+            starts_at: to_rfc3339(&alert.starts_at),
+            ends_at: alert.ends_at.as_ref().map(to_rfc3339),
+            generator_url: alert.generator_url.clone(),
+            fingerprint: alert.fingerprint.clone(),
+        }
+    }
+}
+
+impl TryFrom<types::Alert> for Alert {
+    type Error = ParseError;
+
+    fn try_from(alert: types::Alert) -> Result<Self, Self::Error> {
+        Ok(Self {
+            status: alert.status.into(),
+            labels: alert.labels.into_iter().collect(),
+            annotations: alert.annotations.into_iter().collect(),
+            starts_at: from_rfc3339(&alert.starts_at)?,
+            ends_at: alert.ends_at.as_deref().map(from_rfc3339).transpose()?,
+            generator_url: alert.generator_url,
+            fingerprint: alert.fingerprint,
+        })
+    }
+}
+
+impl From<&AlertmanagerPush> for types::AlertmanagerPush {
+    fn from(push: &AlertmanagerPush) -> Self {
+        Self {
+            version: push.version.clone(),
+            group_key: push.group_key.clone(),
+            truncated_alerts: push.truncated_alerts,
+            status: (&push.status).into(),
+            receiver: push.receiver.clone(),
+            group_labels: to_key_values(&push.group_labels),
+            common_labels: to_key_values(&push.common_labels),
+            common_annotations: to_key_values(&push.common_annotations),
+            external_url: push.external_url.clone(),
+            alerts: push.alerts.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<types::AlertmanagerPush> for AlertmanagerPush {
+    type Error = ParseError;
+
+    fn try_from(push: types::AlertmanagerPush) -> Result<Self, Self::Error> {
+        Ok(Self {
+            version: push.version,
+            group_key: push.group_key,
+            truncated_alerts: push.truncated_alerts,
+            status: push.status.into(),
+            receiver: push.receiver,
+            group_labels: push.group_labels.into_iter().collect(),
+            common_labels: push.common_labels.into_iter().collect(),
+            common_annotations: push.common_annotations.into_iter().collect(),
+            external_url: push.external_url,
+            alerts: push
+                .alerts
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<types::ErrorClass> for ErrorClass {
+    fn from(class: types::ErrorClass) -> Self {
+        match class {
+            types::ErrorClass::Transient => ErrorClass::Transient,
+            types::ErrorClass::Permanent => ErrorClass::Permanent,
+        }
+    }
+}
+
+impl From<types::AlertOutcome> for AlertOutcome {
+    fn from(outcome: types::AlertOutcome) -> Self {
+        match outcome {
+            types::AlertOutcome::Pushed => AlertOutcome::Pushed,
+            types::AlertOutcome::Skipped(reason) => AlertOutcome::Skipped { reason },
+            types::AlertOutcome::Failed(error) => AlertOutcome::Failed {
+                class: error.class.into(),
+                message: error.message,
+            },
+        }
+    }
+}
+
+impl From<types::PushOutcome> for PushOutcome {
+    fn from(outcome: types::PushOutcome) -> Self {
+        match outcome {
+            types::PushOutcome::Pushed => PushOutcome::Pushed,
+            types::PushOutcome::Skipped(reason) => PushOutcome::Skipped { reason },
+            types::PushOutcome::Alerts(results) => PushOutcome::Alerts(
+                results
+                    .into_iter()
+                    .map(|result| AlertResult {
+                        fingerprint: result.fingerprint,
+                        outcome: result.outcome.into(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn push_round_trip() {
+        let starts_at = NaiveDate::from_ymd_opt(2024, 2, 1)
+            .unwrap()
+            .and_hms_opt(12, 30, 0)
+            .unwrap();
+
+        let push = AlertmanagerPush {
+            group_key: String::from("{}:{alertname=\"Disk\"}"),
+            status: Status::Firing,
+            common_labels: [(String::from("env"), String::from("prod"))].into(),
+            alerts: vec![Alert {
+                status: Status::Firing,
+                labels: [(String::from("alertname"), String::from("Disk"))].into(),
+                starts_at,
+                ends_at: Some(starts_at),
+                fingerprint: String::from("abc"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let wit_push = types::AlertmanagerPush::from(&push);
+        assert_eq!(wit_push.alerts[0].starts_at, "2024-02-01T12:30:00+00:00");
+
+        assert_eq!(AlertmanagerPush::try_from(wit_push).unwrap(), push);
+    }
+}
@@ -0,0 +1,170 @@
+use plugins_definitions::HealthError;
+use push_definitions::{ErrorClass, InitializeError, PushError};
+use std::{path::PathBuf, time::Duration};
+use thiserror::Error as ThisError;
+
+/// [`wasmtime::Error`] does not implement [`std::error::Error`]
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(ThisError, Debug)]
+pub enum NewWasmPluginError {
+    #[error("Failed to create engine: {0}")]
+    Engine(#[source] BoxError),
+    #[error("Failed to load module: path: {path}, {error}")]
+    Module {
+        path: PathBuf,
+        #[source]
+        error: BoxError,
+    },
+    #[error("Failed to link module: {0}")]
+    Link(#[source] BoxError),
+    #[error("Module does not implement the world: {0}")]
+    World(#[source] BoxError),
+}
+
+impl NewWasmPluginError {
+    pub(crate) fn world(error: wasmtime::Error) -> Self {
+        Self::World(error.into())
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum StoreError {
+    #[error("Failed to open directory: path: {path}, {error}")]
+    Dir {
+        path: PathBuf,
+        #[source]
+        error: BoxError,
+    },
+    #[error("Failed to set fuel: {0}")]
+    Fuel(#[source] BoxError),
+}
+
+#[derive(ThisError, Debug)]
+pub enum CallError {
+    #[error("Failed to create store: {0}")]
+    Store(
+        #[source]
+        #[from]
+        StoreError,
+    ),
+    #[error("Failed to instantiate module: {0}")]
+    Instantiate(#[source] BoxError),
+    /// Also raised when the module runs out of fuel or memory
+    #[error("Module trapped: {0}")]
+    Trap(#[source] BoxError),
+    #[error("Module timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+impl CallError {
+    pub(crate) fn instantiate(error: wasmtime::Error) -> Self {
+        Self::Instantiate(error.into())
+    }
+
+    pub(crate) fn trap(error: wasmtime::Error) -> Self {
+        Self::Trap(error.into())
+    }
+
+    fn class(&self) -> ErrorClass {
+        match self {
+            CallError::Store(_) | CallError::Timeout(_) => ErrorClass::Transient,
+            // Same input, same trap
+            CallError::Instantiate(_) | CallError::Trap(_) => ErrorClass::Permanent,
+        }
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum InternalHealthError {
+    #[error("Failed to call module: {0}")]
+    Call(
+        #[source]
+        #[from]
+        CallError,
+    ),
+    #[error("Module is not healthy: {0}")]
+    Module(String),
+}
+
+impl From<InternalHealthError> for HealthError {
+    fn from(error: InternalHealthError) -> Self {
+        Self {
+            error: error.into(),
+        }
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum InternalInitializeError {
+    #[error("Failed to call module: {0}")]
+    Call(
+        #[source]
+        #[from]
+        CallError,
+    ),
+    #[error("Module failed to initialize: {0}")]
+    Module(String),
+}
+
+impl From<InternalInitializeError> for InitializeError {
+    fn from(error: InternalInitializeError) -> Self {
+        Self {
+            error: error.into(),
+        }
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum InternalPushError {
+    #[error("Failed to call module: {0}")]
+    Call(
+        #[source]
+        #[from]
+        CallError,
+    ),
+    #[error("Module failed to push: {message}")]
+    Module { class: ErrorClass, message: String },
+}
+
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InternalPushError::Call(error) => error.class(),
+            InternalPushError::Module { class, .. } => *class,
+        }
+    }
+}
+
+impl From<InternalPushError> for PushError {
+    fn from(error: InternalPushError) -> Self {
+        Self::new(error.class(), error)
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum TransformError {
+    #[error("Failed to call module: {0}")]
+    Call(
+        #[source]
+        #[from]
+        CallError,
+    ),
+    #[error("Module failed to transform: {0}")]
+    Module(String),
+    #[error("Module returned an invalid timestamp: {0}")]
+    Timestamp(
+        #[source]
+        #[from]
+        chrono::ParseError,
+    ),
+}
+
+impl TransformError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            TransformError::Call(error) => error.class(),
+            TransformError::Module(_) | TransformError::Timestamp(_) => ErrorClass::Permanent,
+        }
+    }
+}
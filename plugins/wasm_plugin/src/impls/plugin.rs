@@ -0,0 +1,42 @@
+use crate::{
+    error::{CallError, InternalHealthError},
+    WasmPlugin,
+};
+use async_trait::async_trait;
+use plugins_definitions::{HealthError, Plugin, PluginMeta};
+
+impl WasmPlugin {
+    async fn health_with_internal_error(&self) -> Result<(), InternalHealthError> {
+        self.runtime
+            .call(async {
+                let (mut store, sink) = self.instantiate().await?;
+
+                sink.call_health(&mut store).await.map_err(CallError::trap)
+            })
+            .await?
+            .map_err(InternalHealthError::Module)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Plugin for WasmPlugin {
+    fn meta(&self) -> PluginMeta<'_> {
+        PluginMeta {
+            name: &self.meta.name,
+            type_: "wasm",
+            group: &self.meta.group,
+        }
+    }
+
+    #[tracing::instrument(name = "health", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn health(&self) -> Result<(), HealthError> {
+        tracing::trace!("Checking health.");
+
+        self.health_with_internal_error().await?;
+
+        tracing::trace!("Successfully checked health.");
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{
+    bindings::types,
+    error::{CallError, InternalInitializeError, InternalPushError},
+    WasmPlugin,
+};
+use async_trait::async_trait;
+use models::AlertmanagerPush;
+use plugins_definitions::Plugin;
+use push_definitions::{InitializeError, Push, PushError, PushOutcome};
+
+impl WasmPlugin {
+    async fn initialize_with_internal_error(&mut self) -> Result<(), InternalInitializeError> {
+        self.runtime
+            .call(async {
+                let (mut store, sink) = self.instantiate().await?;
+
+                sink.call_initialize(&mut store)
+                    .await
+                    .map_err(CallError::trap)
+            })
+            .await?
+            .map_err(InternalInitializeError::Module)?;
+
+        Ok(())
+    }
+
+    async fn push_alert_with_internal_error(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, InternalPushError> {
+        let outcome = self
+            .runtime
+            .call(async {
+                let (mut store, sink) = self.instantiate().await?;
+
+                sink.call_push_alert(
+                    &mut store,
+                    &types::AlertmanagerPush::from(alertmanager_push),
+                )
+                .await
+                .map_err(CallError::trap)
+            })
+            .await?
+            .map_err(|error| InternalPushError::Module {
+                class: error.class.into(),
+                message: error.message,
+            })?;
+
+        Ok(outcome.into())
+    }
+}
+
+#[async_trait]
+impl Push for WasmPlugin {
+    #[tracing::instrument(name = "push_initialize", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn initialize(&mut self) -> Result<(), InitializeError> {
+        tracing::trace!("Initializing.");
+
+        self.initialize_with_internal_error().await?;
+
+        tracing::trace!("Successfully initialized.");
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
+        tracing::trace!("Pushing.");
+
+        let outcome = self
+            .push_alert_with_internal_error(alertmanager_push)
+            .await?;
+
+        tracing::trace!("Successfully pushed.");
+        Ok(outcome)
+    }
+}
@@ -0,0 +1,176 @@
+use bindings::{
+    sink::{Sink, SinkPre},
+    transform::{Transform, TransformPre},
+};
+use error::CallError;
+use runtime::{HostState, Runtime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+use wasmtime::Store;
+
+mod bindings;
+mod convert;
+mod error;
+mod impls;
+mod runtime;
+mod transform;
+
+pub use error::{NewWasmPluginError, TransformError};
+
+fn default_fuel() -> u64 {
+    1_000_000_000
+}
+
+fn default_memory_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+fn default_timeout_millis() -> u64 {
+    10_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// A host directory the module can access
+pub struct DirCapability {
+    /// Path on the host
+    pub host_path: PathBuf,
+    /// Path seen by the module
+    pub guest_path: String,
+    /// Forbid creating, writing and removing files
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+/// What the module can access. Nothing by default
+pub struct Capabilities {
+    /// Directories opened for the module
+    #[serde(default)]
+    pub dirs: Vec<DirCapability>,
+    /// Allow outgoing TCP and UDP connections and name lookups
+    #[serde(default)]
+    pub network: bool,
+    /// Environment variables set for the module
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Forward stdout and stderr of the module to the server
+    #[serde(default)]
+    pub stdio: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Limits for every call into the module
+pub struct Limits {
+    /// Fuel for a single call. Roughly one unit per instruction
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,
+    /// Maximum size of each linear memory
+    #[serde(default = "default_memory_bytes")]
+    pub memory_bytes: usize,
+    /// Wall time for a single call, including the instantiation
+    #[serde(default = "default_timeout_millis")]
+    pub timeout_millis: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            fuel: default_fuel(),
+            memory_bytes: default_memory_bytes(),
+            timeout_millis: default_timeout_millis(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Configuration for the Wasm plugin
+pub struct WasmPluginConfig {
+    /// Path to the WASI component
+    ///
+    /// Plugins implement the `sink` world, pipeline stages the `transform` world
+    /// of `plugins/wasm_plugin/wit/plugin.wit`.
+    pub module_path: PathBuf,
+    #[serde(default)]
+    pub capabilities: Capabilities,
+    #[serde(default)]
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Metadata for the Wasm plugin
+pub struct WasmPluginMeta {
+    /// Name of the plugin
+    pub name: String,
+    /// Group of the plugin
+    pub group: String,
+}
+
+/// The Wasm plugin
+///
+/// Based on [`wasmtime`]. Every call runs in a fresh instance,
+/// so a module can't keep state between pushes or affect other pushes.
+pub struct WasmPlugin {
+    /// Meta information for the plugin
+    meta: WasmPluginMeta,
+    /// Compiled module
+    runtime: Runtime,
+    /// Linked `sink` world
+    sink_pre: SinkPre<HostState>,
+}
+
+impl WasmPlugin {
+    pub fn new(meta: WasmPluginMeta, config: WasmPluginConfig) -> Result<Self, NewWasmPluginError> {
+        let runtime = Runtime::new(config)?;
+        let sink_pre = SinkPre::new(runtime.instance_pre()?).map_err(NewWasmPluginError::world)?;
+
+        Ok(Self {
+            meta,
+            runtime,
+            sink_pre,
+        })
+    }
+
+    async fn instantiate(&self) -> Result<(Store<HostState>, Sink), CallError> {
+        let mut store = self.runtime.store()?;
+        let sink = self
+            .sink_pre
+            .instantiate_async(&mut store)
+            .await
+            .map_err(CallError::instantiate)?;
+
+        Ok((store, sink))
+    }
+}
+
+/// A pipeline stage running the `transform` world of a module
+pub struct WasmTransform {
+    /// Compiled module
+    runtime: Runtime,
+    /// Linked `transform` world
+    transform_pre: TransformPre<HostState>,
+}
+
+impl WasmTransform {
+    pub fn new(config: WasmPluginConfig) -> Result<Self, NewWasmPluginError> {
+        let runtime = Runtime::new(config)?;
+        let transform_pre =
+            TransformPre::new(runtime.instance_pre()?).map_err(NewWasmPluginError::world)?;
+
+        Ok(Self {
+            runtime,
+            transform_pre,
+        })
+    }
+
+    async fn instantiate(&self) -> Result<(Store<HostState>, Transform), CallError> {
+        let mut store = self.runtime.store()?;
+        let transform = self
+            .transform_pre
+            .instantiate_async(&mut store)
+            .await
+            .map_err(CallError::instantiate)?;
+
+        Ok((store, transform))
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    error::{CallError, NewWasmPluginError, StoreError},
+    Capabilities, Limits, WasmPluginConfig,
+};
+use std::{future::Future, time::Duration};
+use wasmtime::{
+    component::{Component, InstancePre, Linker, ResourceTable},
+    Config, Engine, Store, StoreLimits, StoreLimitsBuilder,
+};
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiView};
+
+/// Fuel consumed between yields to the async runtime,
+/// so a busy module does not block other pushes and can be timed out
+const FUEL_YIELD_INTERVAL: u64 = 10_000;
+
+/// Data of a store, one per call
+pub(crate) struct HostState {
+    wasi: WasiCtx,
+    table: ResourceTable,
+    limits: StoreLimits,
+}
+
+impl WasiView for HostState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+/// Compiled module with its capabilities and limits
+pub(crate) struct Runtime {
+    engine: Engine,
+    component: Component,
+    linker: Linker<HostState>,
+    capabilities: Capabilities,
+    limits: Limits,
+}
+
+impl Runtime {
+    pub(crate) fn new(config: WasmPluginConfig) -> Result<Self, NewWasmPluginError> {
+        let mut engine_config = Config::new();
+        engine_config
+            .async_support(true)
+            .consume_fuel(true)
+            .wasm_component_model(true);
+
+        let engine = Engine::new(&engine_config)
+            .map_err(|error| NewWasmPluginError::Engine(error.into()))?;
+
+        let component = Component::from_file(&engine, &config.module_path).map_err(|error| {
+            NewWasmPluginError::Module {
+                path: config.module_path.clone(),
+                error: error.into(),
+            }
+        })?;
+
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::add_to_linker_async(&mut linker)
+            .map_err(|error| NewWasmPluginError::Link(error.into()))?;
+
+        Ok(Self {
+            engine,
+            component,
+            linker,
+            capabilities: config.capabilities,
+            limits: config.limits,
+        })
+    }
+
+    /// Links the imports of the module. Fails if it imports more than WASI
+    pub(crate) fn instance_pre(&self) -> Result<InstancePre<HostState>, NewWasmPluginError> {
+        self.linker
+            .instantiate_pre(&self.component)
+            .map_err(|error| NewWasmPluginError::Link(error.into()))
+    }
+
+    /// Runs a call into the module, cancelled after the wall time limit.
+    ///
+    /// The module yields every [`FUEL_YIELD_INTERVAL`] units of fuel, so a busy module is cancelled promptly.
+    pub(crate) async fn call<T>(
+        &self,
+        call: impl Future<Output = Result<T, CallError>>,
+    ) -> Result<T, CallError> {
+        let timeout = Duration::from_millis(self.limits.timeout_millis);
+
+        tokio::time::timeout(timeout, call)
+            .await
+            .map_err(|_| CallError::Timeout(timeout))?
+    }
+
+    /// Creates a store with the capabilities and a fresh fuel and memory budget
+    pub(crate) fn store(&self) -> Result<Store<HostState>, StoreError> {
+        let mut builder = WasiCtxBuilder::new();
+
+        for dir in self.capabilities.dirs.iter() {
+            let (dir_perms, file_perms) = if dir.read_only {
+                (DirPerms::READ, FilePerms::READ)
+            } else {
+                (DirPerms::all(), FilePerms::all())
+            };
+
+            builder
+                .preopened_dir(&dir.host_path, &dir.guest_path, dir_perms, file_perms)
+                .map_err(|error| StoreError::Dir {
+                    path: dir.host_path.clone(),
+                    error: error.into(),
+                })?;
+        }
+
+        if self.capabilities.network {
+            builder.inherit_network().allow_ip_name_lookup(true);
+        }
+
+        for (name, value) in self.capabilities.env.iter() {
+            builder.env(name, value);
+        }
+
+        if self.capabilities.stdio {
+            builder.inherit_stdout().inherit_stderr();
+        }
+
+        let state = HostState {
+            wasi: builder.build(),
+            table: ResourceTable::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.limits.memory_bytes)
+                .build(),
+        };
+
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(self.limits.fuel)
+            .map_err(|error| StoreError::Fuel(error.into()))?;
+        store
+            .fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))
+            .map_err(|error| StoreError::Fuel(error.into()))?;
+
+        Ok(store)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{collections::BTreeMap, io::Write};
+    use tempfile::NamedTempFile;
+    use wasmtime::component::{ComponentNamedList, Lift};
+
+    /// Spins forever, grows its memory and counts the environment variables it sees
+    const MODULE: &str = r#"
+        (component
+            (import "wasi:cli/environment@0.2.0" (instance $environment
+                (export "get-environment" (func (result (list (tuple string string)))))
+            ))
+
+            (core module $memory
+                (memory (export "memory") 1)
+                (global $next (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr
+                        (i32.and
+                            (i32.add (global.get $next) (i32.sub (local.get 2) (i32.const 1)))
+                            (i32.sub (i32.const 0) (local.get 2))))
+                    (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+                    (local.get $ptr))
+            )
+            (core instance $memory (instantiate $memory))
+
+            (core func $get-environment
+                (canon lower (func $environment "get-environment")
+                    (memory $memory "memory")
+                    (realloc (func $memory "realloc"))))
+
+            (core module $main
+                (import "host" "memory" (memory 1))
+                (import "host" "get-environment" (func $get-environment (param i32)))
+                (func (export "spin") (loop $spin (br $spin)))
+                (func (export "grow") (result i32) (memory.grow (i32.const 2)))
+                (func (export "env-count") (result i32)
+                    (call $get-environment (i32.const 0))
+                    (i32.load offset=4 (i32.const 0)))
+            )
+            (core instance $main (instantiate $main
+                (with "host" (instance
+                    (export "memory" (memory $memory "memory"))
+                    (export "get-environment" (func $get-environment))))))
+
+            (func (export "spin") (canon lift (core func $main "spin")))
+            (func (export "grow") (result s32) (canon lift (core func $main "grow")))
+            (func (export "env-count") (result u32) (canon lift (core func $main "env-count")))
+        )
+    "#;
+
+    async fn call<R>(limits: Limits, capabilities: Capabilities, name: &str) -> Result<R, CallError>
+    where
+        R: ComponentNamedList + Lift + Send + Sync + 'static,
+    {
+        let mut module = NamedTempFile::new().expect("Failed to create module file.");
+        module
+            .write_all(MODULE.as_bytes())
+            .expect("Failed to write module.");
+
+        let runtime = Runtime::new(WasmPluginConfig {
+            module_path: module.path().to_path_buf(),
+            capabilities,
+            limits,
+        })
+        .expect("Failed to create runtime.");
+        let instance_pre = runtime.instance_pre().expect("Failed to link module.");
+
+        runtime
+            .call(async {
+                let mut store = runtime.store()?;
+                let instance = instance_pre
+                    .instantiate_async(&mut store)
+                    .await
+                    .map_err(CallError::instantiate)?;
+
+                instance
+                    .get_typed_func::<(), R>(&mut store, name)
+                    .expect("Failed to get export.")
+                    .call_async(&mut store, ())
+                    .await
+                    .map_err(CallError::trap)
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn runs_out_of_fuel() {
+        let limits = Limits {
+            fuel: 1_000_000,
+            ..Default::default()
+        };
+
+        let error = call::<()>(limits, Capabilities::default(), "spin")
+            .await
+            .expect_err("Spinning should run out of fuel.");
+
+        assert!(matches!(error, CallError::Trap(_)), "{error}");
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let limits = Limits {
+            fuel: u64::MAX,
+            timeout_millis: 100,
+            ..Default::default()
+        };
+
+        let error = call::<()>(limits, Capabilities::default(), "spin")
+            .await
+            .expect_err("Spinning should time out.");
+
+        assert!(
+            matches!(error, CallError::Timeout(timeout) if timeout == Duration::from_millis(100)),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn cannot_grow_memory_over_the_limit() {
+        let (previous_pages,) = call::<(i32,)>(Limits::default(), Capabilities::default(), "grow")
+            .await
+            .expect("Failed to grow memory.");
+        assert_eq!(previous_pages, 1);
+
+        let limits = Limits {
+            memory_bytes: 2 * 64 * 1024,
+            ..Default::default()
+        };
+
+        let (previous_pages,) = call::<(i32,)>(limits, Capabilities::default(), "grow")
+            .await
+            .expect("Failed to grow memory.");
+        assert_eq!(previous_pages, -1);
+    }
+
+    #[tokio::test]
+    async fn sees_only_the_granted_environment() {
+        // The server has environment variables, like `PATH`
+        let (count,) = call::<(u32,)>(Limits::default(), Capabilities::default(), "env-count")
+            .await
+            .expect("Failed to count environment variables.");
+        assert_eq!(count, 0);
+
+        let capabilities = Capabilities {
+            env: BTreeMap::from([(String::from("TEAM"), String::from("a"))]),
+            ..Default::default()
+        };
+
+        let (count,) = call::<(u32,)>(Limits::default(), capabilities, "env-count")
+            .await
+            .expect("Failed to count environment variables.");
+        assert_eq!(count, 1);
+    }
+}
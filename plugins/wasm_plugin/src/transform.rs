@@ -0,0 +1,28 @@
+use crate::{bindings::types, error::CallError, TransformError, WasmTransform};
+use models::AlertmanagerPush;
+
+impl WasmTransform {
+    /// Runs the `transform` export of the module on the push
+    pub async fn transform(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<AlertmanagerPush, TransformError> {
+        let transformed = self
+            .runtime
+            .call(async {
+                let (mut store, transform) = self.instantiate().await?;
+
+                transform
+                    .call_transform(
+                        &mut store,
+                        &types::AlertmanagerPush::from(alertmanager_push),
+                    )
+                    .await
+                    .map_err(CallError::trap)
+            })
+            .await?
+            .map_err(TransformError::Module)?;
+
+        Ok(transformed.try_into()?)
+    }
+}
@@ -0,0 +1,88 @@
+use formatter::{FormatError, NewFormatterError};
+use http_client::NewHttpClientError;
+use jinja_renderer::{NewJinjaRendererError, RenderError};
+use push_definitions::{ErrorClass, PushError};
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug)]
+pub enum NewWebhookPluginError {
+    #[error("Failed to create template: {0}")]
+    Template(
+        #[source]
+        #[from]
+        NewJinjaRendererError,
+    ),
+    #[error("Invalid header name: {0}")]
+    HeaderName(
+        #[source]
+        #[from]
+        reqwest::header::InvalidHeaderName,
+    ),
+    #[error("Failed to create formatter: {0}")]
+    Formatter(
+        #[source]
+        #[from]
+        NewFormatterError,
+    ),
+    #[error("Failed to create http client: {0}")]
+    HttpClient(
+        #[source]
+        #[from]
+        NewHttpClientError,
+    ),
+}
+
+#[derive(ThisError, Debug)]
+pub enum InternalPushError {
+    #[error("Failed to render template: {0}")]
+    Render(
+        #[source]
+        #[from]
+        RenderError,
+    ),
+    #[error("Invalid URL: {0}")]
+    Url(
+        #[source]
+        #[from]
+        url::ParseError,
+    ),
+    #[error("Failed to format: {0}")]
+    Format(
+        #[source]
+        #[from]
+        FormatError,
+    ),
+    #[error("Reqwest error: {0}")]
+    Reqwest(
+        #[source]
+        #[from]
+        reqwest::Error,
+    ),
+    #[error("Got error response: status code: {status_code}, body: {body}")]
+    ErrorResponse {
+        status_code: reqwest::StatusCode,
+        body: String,
+    },
+}
+
+impl InternalPushError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InternalPushError::Reqwest(error) if http_client::is_transient_error(error) => {
+                ErrorClass::Transient
+            }
+            InternalPushError::ErrorResponse { status_code, .. }
+                if http_client::is_transient_status(*status_code) =>
+            {
+                ErrorClass::Transient
+            }
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
+impl From<InternalPushError> for PushError {
+    fn from(error: InternalPushError) -> Self {
+        Self::new(error.class(), error)
+    }
+}
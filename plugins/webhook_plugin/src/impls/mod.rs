@@ -0,0 +1,2 @@
+mod plugin;
+mod push;
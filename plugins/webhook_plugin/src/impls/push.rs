@@ -0,0 +1,82 @@
+use crate::{error::InternalPushError, WebhookPlugin};
+use async_trait::async_trait;
+use models::AlertmanagerPush;
+use plugins_definitions::Plugin;
+use push_definitions::{InitializeError, Push, PushError, PushOutcome};
+use reqwest::header::CONTENT_TYPE;
+use url::Url;
+
+impl WebhookPlugin {
+    async fn send(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), InternalPushError> {
+        let url = Url::parse(self.url.render(alertmanager_push)?.trim())?;
+        let body = self.formatter.format(alertmanager_push)?;
+
+        let mut request = self
+            .client
+            .request(self.config.method.into(), url)
+            .header(CONTENT_TYPE, &self.content_type)
+            .body(body);
+
+        for (name, value) in self.headers.iter() {
+            request = request.header(name.clone(), value.render(alertmanager_push)?);
+        }
+
+        let response = self.client.send(request).await?;
+
+        let status_code = response.status();
+        if !self
+            .config
+            .success_status
+            .iter()
+            .any(|pattern| pattern.is_match(status_code.as_u16()))
+        {
+            let body = response.text().await?;
+
+            return Err(InternalPushError::ErrorResponse { status_code, body });
+        }
+
+        Ok(())
+    }
+
+    async fn push_alert_with_internal_error(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, InternalPushError> {
+        self.send(alertmanager_push).await?;
+        Ok(PushOutcome::Pushed)
+    }
+}
+
+/// Keeps the default `shutdown`: requests are sent right away and not buffered,
+/// and idle connections are closed when the client is dropped.
+///
+/// Either the client or the server retries, see `retries_itself`.
+#[async_trait]
+impl Push for WebhookPlugin {
+    #[tracing::instrument(name = "push_initialize", skip(self), fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn initialize(&mut self) -> Result<(), InitializeError> {
+        tracing::trace!("Initializing.");
+
+        tracing::trace!("Successfully initialized.");
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "push_alert", skip_all, fields(name = %self.name(), group = %self.group(), type_ = %self.type_()))]
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError> {
+        tracing::trace!("Pushing.");
+
+        let outcome = self
+            .push_alert_with_internal_error(alertmanager_push)
+            .await?;
+
+        tracing::trace!("Successfully pushed.");
+        Ok(outcome)
+    }
+
+    fn retries_itself(&self) -> bool {
+        self.client.retries()
+    }
+}
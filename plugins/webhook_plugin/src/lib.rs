@@ -0,0 +1,295 @@
+use error::NewWebhookPluginError;
+use formatter::{Formatter, FormatterConfig};
+use http_client::{HttpClient, HttpClientConfig};
+use jinja_renderer::JinjaRenderer;
+use reqwest::header::HeaderName;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+
+mod error;
+mod impls;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    #[default]
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl From<HttpMethod> for reqwest::Method {
+    fn from(method: HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => reqwest::Method::GET,
+            HttpMethod::Post => reqwest::Method::POST,
+            HttpMethod::Put => reqwest::Method::PUT,
+            HttpMethod::Patch => reqwest::Method::PATCH,
+            HttpMethod::Delete => reqwest::Method::DELETE,
+        }
+    }
+}
+
+/// Status code pattern like `204` or `2xx`
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde_with::SerializeDisplay, serde_with::DeserializeFromStr,
+)]
+pub struct StatusPattern {
+    pattern: String,
+}
+
+impl StatusPattern {
+    pub fn is_match(&self, status_code: u16) -> bool {
+        let status_code = status_code.to_string();
+
+        status_code.len() == self.pattern.len()
+            && self
+                .pattern
+                .chars()
+                .zip(status_code.chars())
+                .all(|(pattern, digit)| pattern == 'x' || pattern == digit)
+    }
+}
+
+impl std::fmt::Display for StatusPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+impl FromStr for StatusPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s.to_ascii_lowercase();
+
+        if pattern.len() != 3 || !pattern.chars().all(|c| c.is_ascii_digit() || c == 'x') {
+            return Err(format!(
+                "{} is not a status code pattern like 204 or 2xx",
+                s
+            ));
+        }
+
+        Ok(Self { pattern })
+    }
+}
+
+impl JsonSchema for StatusPattern {
+    fn schema_name() -> String {
+        "StatusPattern".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+fn default_success_status() -> Vec<StatusPattern> {
+    vec![StatusPattern {
+        pattern: "2xx".to_string(),
+    }]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Configuration for the Webhook plugin
+pub struct WebhookPluginConfig {
+    /// Jinja template for the URL, rendered with `push`
+    pub url: String,
+    #[serde(default)]
+    pub method: HttpMethod,
+    /// Jinja templates for the header values, rendered with `push`
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Formatting configuration for the body
+    pub formatter_config: FormatterConfig,
+    /// Overrides the content type derived from the format
+    pub content_type: Option<String>,
+    /// Status codes treated as success. Defaults to `2xx`
+    #[serde(default = "default_success_status")]
+    pub success_status: Vec<StatusPattern>,
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Metadata for the Webhook plugin
+pub struct WebhookPluginMeta {
+    /// Name of the plugin
+    pub name: String,
+    /// Group of the plugin
+    pub group: String,
+}
+
+/// The Webhook plugin
+///
+/// Sends the formatted push to an arbitrary HTTP endpoint.
+pub struct WebhookPlugin {
+    /// Meta information for the plugin
+    meta: WebhookPluginMeta,
+    /// Configuration for the plugin
+    config: WebhookPluginConfig,
+    /// Renders the URL
+    url: JinjaRenderer,
+    /// Render the header values
+    headers: Vec<(HeaderName, JinjaRenderer)>,
+    /// Formats the body
+    formatter: Formatter,
+    /// Content type of the body
+    content_type: String,
+    /// Client used to send the requests
+    client: HttpClient,
+}
+
+impl WebhookPlugin {
+    pub async fn new(
+        meta: WebhookPluginMeta,
+        config: WebhookPluginConfig,
+    ) -> Result<Self, NewWebhookPluginError> {
+        let formatter = Formatter::new(config.formatter_config.clone()).await?;
+        let extensions = formatter.jinja_extensions();
+
+        let url =
+            JinjaRenderer::new_from_str_with_extensions(config.url.clone(), extensions).await?;
+
+        let mut headers = vec![];
+        for (name, value) in config.headers.iter() {
+            let name = HeaderName::from_bytes(name.as_bytes())?;
+            let value =
+                JinjaRenderer::new_from_str_with_extensions(value.clone(), extensions).await?;
+            headers.push((name, value));
+        }
+
+        let content_type = config
+            .content_type
+            .clone()
+            .unwrap_or_else(|| formatter.content_type().to_string());
+
+        let client = HttpClient::new(config.http_client.clone()).await?;
+
+        Ok(Self {
+            meta,
+            config,
+            url,
+            headers,
+            formatter,
+            content_type,
+            client,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::{
+        extract::Path,
+        http::{HeaderMap, StatusCode},
+        routing::put,
+        Router,
+    };
+    use models::{Alert, AlertmanagerPush, Status};
+    use push_definitions::{Delivery, Push, PushOutcome};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn status_pattern() {
+        let pattern = StatusPattern::from_str("2xx").unwrap();
+        assert!(pattern.is_match(200));
+        assert!(pattern.is_match(204));
+        assert!(!pattern.is_match(302));
+
+        let pattern = StatusPattern::from_str("409").unwrap();
+        assert!(pattern.is_match(409));
+        assert!(!pattern.is_match(400));
+
+        assert!(StatusPattern::from_str("2x").is_err());
+        assert!(StatusPattern::from_str("abc").is_err());
+    }
+
+    #[tokio::test]
+    async fn push_to_mock_server() {
+        type Received = Arc<Mutex<Vec<(String, String, String, String)>>>;
+
+        let received: Received = Default::default();
+        let received_c = Arc::clone(&received);
+
+        let app = Router::new().route(
+            "/alerts/:receiver",
+            put(
+                move |Path(receiver): Path<String>, headers: HeaderMap, body: String| async move {
+                    received_c.lock().unwrap().push((
+                        receiver,
+                        headers["content-type"].to_str().unwrap().to_string(),
+                        headers["x-alertname"].to_str().unwrap().to_string(),
+                        body,
+                    ));
+
+                    if headers["x-alertname"] == "Conflict" {
+                        StatusCode::CONFLICT
+                    } else {
+                        StatusCode::CREATED
+                    }
+                },
+            ),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config: WebhookPluginConfig = serde_yaml::from_str(&format!(
+            r#"
+            url: "http://{addr}/alerts/{{{{ push.receiver }}}}"
+            method: PUT
+            headers:
+              x-alertname: "{{{{ push.commonLabels.alertname }}}}"
+            formatter_config:
+              format_type:
+                type: Json
+            success_status: [2xx, "409"]
+            "#
+        ))
+        .unwrap();
+
+        let plugin = WebhookPlugin::new(
+            WebhookPluginMeta {
+                name: "webhook".to_string(),
+                group: "default".to_string(),
+            },
+            config,
+        )
+        .await
+        .unwrap();
+
+        let alert = |alertname: &str| Alert {
+            status: Status::Firing,
+            labels: [("alertname".to_string(), alertname.to_string())].into(),
+            ..Default::default()
+        };
+
+        let push = AlertmanagerPush {
+            receiver: "team-a".to_string(),
+            status: Status::Firing,
+            alerts: vec![alert("DiskFull"), alert("Conflict")],
+            ..Default::default()
+        };
+
+        for push in Delivery::PerAlert.split(&push).iter() {
+            let outcome = plugin.push_alert(push).await.unwrap();
+            assert_eq!(outcome, PushOutcome::Pushed);
+        }
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].0, "team-a");
+        assert_eq!(received[0].1, "application/json");
+        assert_eq!(received[0].2, "DiskFull");
+        assert_eq!(received[1].2, "Conflict");
+
+        let body: AlertmanagerPush = serde_json::from_str(&received[0].3).unwrap();
+        assert_eq!(body.alerts, vec![alert("DiskFull")]);
+    }
+}
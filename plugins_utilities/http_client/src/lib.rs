@@ -0,0 +1,461 @@
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Certificate, Client, Identity, Method, Proxy, Request, RequestBuilder, Response, StatusCode,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use signature::SignatureConfig;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use thiserror::Error as ThisError;
+use url::Url;
+
+pub mod signature;
+
+#[derive(ThisError, Debug)]
+pub enum NewHttpClientError {
+    #[error("Failed to read {}: {error}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("Invalid header name: {0}")]
+    HeaderName(
+        #[source]
+        #[from]
+        reqwest::header::InvalidHeaderName,
+    ),
+    #[error("Invalid header value: {0}")]
+    HeaderValue(
+        #[source]
+        #[from]
+        reqwest::header::InvalidHeaderValue,
+    ),
+    #[error("Failed to build client: {0}")]
+    Reqwest(
+        #[source]
+        #[from]
+        reqwest::Error,
+    ),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthConfig {
+    /// `Authorization: Bearer <token>`
+    Bearer { token: String },
+    /// `Authorization: Basic <username:password>`
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClientCertificateConfig {
+    /// PEM encoded certificate chain
+    pub cert_path: PathBuf,
+    /// PEM encoded PKCS#8 private key
+    pub key_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RetryConfig {
+    /// Number of retries after the first attempt
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further retry
+    #[serde(default = "default_backoff_millis")]
+    pub backoff_millis: u64,
+    /// Longest `Retry-After` of a 429 or 503 response that is waited for, longer ones are shortened
+    #[serde(default = "default_max_retry_after_seconds")]
+    pub max_retry_after_seconds: u64,
+}
+
+fn default_backoff_millis() -> u64 {
+    500
+}
+
+fn default_max_retry_after_seconds() -> u64 {
+    60
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            backoff_millis: default_backoff_millis(),
+            max_retry_after_seconds: default_max_retry_after_seconds(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+/// Configuration for outbound HTTP requests
+pub struct HttpClientConfig {
+    /// Timeout for the whole request
+    pub timeout_seconds: Option<u64>,
+    /// Timeout for establishing the connection
+    pub connect_timeout_seconds: Option<u64>,
+    /// Headers sent with every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub auth: Option<AuthConfig>,
+    /// Proxy used for all requests
+    pub proxy: Option<Url>,
+    /// PEM encoded certificates trusted in addition to the system ones
+    #[serde(default)]
+    pub root_certificates: Vec<PathBuf>,
+    pub client_certificate: Option<ClientCertificateConfig>,
+    /// Retries on connection errors and transient responses, see [`is_transient_status`]
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Signs the body of every request
+    pub signature: Option<SignatureConfig>,
+}
+
+/// Pooled HTTP client
+///
+/// Built once from a [`HttpClientConfig`] and shared by all requests of a plugin.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    auth: Option<AuthConfig>,
+    retry: RetryConfig,
+    signature: Option<(HeaderName, SignatureConfig)>,
+}
+
+impl HttpClient {
+    pub async fn new(config: HttpClientConfig) -> Result<Self, NewHttpClientError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in config.headers.iter() {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+
+        let mut builder = Client::builder().default_headers(headers);
+
+        if let Some(timeout_seconds) = config.timeout_seconds {
+            builder = builder.timeout(Duration::from_secs(timeout_seconds));
+        }
+
+        if let Some(connect_timeout_seconds) = config.connect_timeout_seconds {
+            builder = builder.connect_timeout(Duration::from_secs(connect_timeout_seconds));
+        }
+
+        if let Some(proxy) = config.proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+
+        for path in config.root_certificates {
+            let pem = read(path).await?;
+            builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+
+        if let Some(client_certificate) = config.client_certificate {
+            let cert = read(client_certificate.cert_path).await?;
+            let key = read(client_certificate.key_path).await?;
+            builder = builder.identity(Identity::from_pkcs8_pem(&cert, &key)?);
+        }
+
+        let signature = match config.signature {
+            Some(signature) => Some((
+                HeaderName::from_bytes(signature.header.as_bytes())?,
+                signature,
+            )),
+            None => None,
+        };
+
+        Ok(Self {
+            client: builder.build()?,
+            auth: config.auth,
+            retry: config.retry,
+            signature,
+        })
+    }
+
+    /// Starts a request with the configured authentication.
+    pub fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.client.request(method, url);
+
+        match &self.auth {
+            Some(AuthConfig::Bearer { token }) => request.bearer_auth(token),
+            Some(AuthConfig::Basic { username, password }) => {
+                request.basic_auth(username, password.as_ref())
+            }
+            None => request,
+        }
+    }
+
+    pub fn post(&self, url: Url) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    /// Whether requests are retried
+    pub fn retries(&self) -> bool {
+        self.retry.max_retries > 0
+    }
+
+    /// Sends the request, retrying on connection errors and transient responses, see [`is_transient_status`].
+    ///
+    /// Retries wait for the `Retry-After` of 429 and 503 responses if they have one,
+    /// for the exponential backoff otherwise.
+    /// The body is signed once, before the first attempt, if a signature is configured.
+    /// Requests with a streaming body can not be cloned and are sent once.
+    /// The last response is returned as is, so callers still have to check the status.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        let mut request = request.build()?;
+        self.sign(&mut request);
+
+        let mut attempt = 0;
+
+        loop {
+            let retry_request = if attempt < self.retry.max_retries {
+                request.try_clone()
+            } else {
+                None
+            };
+
+            let Some(retry_request) = retry_request else {
+                return self.client.execute(request).await;
+            };
+
+            let retry_after = match self.client.execute(request).await {
+                Ok(response) if !is_transient_status(response.status()) => return Ok(response),
+                Ok(response) => {
+                    tracing::warn!(status = %response.status(), attempt, "Transient response. Retrying.");
+                    self.retry_after(&response)
+                }
+                Err(error) if error.is_connect() || error.is_timeout() => {
+                    tracing::warn!(%error, attempt, "Request failed. Retrying.");
+                    None
+                }
+                Err(error) => return Err(error),
+            };
+
+            let delay = retry_after.unwrap_or_else(|| {
+                Duration::from_millis(
+                    self.retry
+                        .backoff_millis
+                        .saturating_mul(1 << attempt.min(16)),
+                )
+            });
+            tokio::time::sleep(delay).await;
+
+            attempt += 1;
+            request = retry_request;
+        }
+    }
+
+    /// The `Retry-After` of a 429 or 503 response, in seconds or as an HTTP date
+    fn retry_after(&self, response: &Response) -> Option<Duration> {
+        if response.status() != StatusCode::TOO_MANY_REQUESTS
+            && response.status() != StatusCode::SERVICE_UNAVAILABLE
+        {
+            return None;
+        }
+
+        let value = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)?
+            .to_str()
+            .ok()?
+            .trim();
+
+        let retry_after = match value.parse::<u64>() {
+            Ok(seconds) => Duration::from_secs(seconds),
+            Err(_) => httpdate::parse_http_date(value)
+                .ok()?
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        };
+
+        Some(retry_after.min(Duration::from_secs(self.retry.max_retry_after_seconds)))
+    }
+
+    fn sign(&self, request: &mut Request) {
+        let Some((header, signature)) = &self.signature else {
+            return;
+        };
+
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+
+        match HeaderValue::from_str(&signature.sign(body)) {
+            Ok(value) => {
+                request.headers_mut().insert(header.clone(), value);
+            }
+            Err(error) => tracing::error!(%error, "Failed to sign request."),
+        }
+    }
+}
+
+/// Whether the request may succeed when sent again: 5xx, `408 Request Timeout` and `429 Too Many Requests`
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Whether the request may succeed when sent again.
+/// Invalid requests and redirect loops fail again
+pub fn is_transient_error(error: &reqwest::Error) -> bool {
+    !error.is_builder() && !error.is_redirect()
+}
+
+impl Default for HttpClient {
+    /// Client without timeouts, headers, authentication or retries
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+            auth: None,
+            retry: RetryConfig::default(),
+            signature: None,
+        }
+    }
+}
+
+async fn read(path: PathBuf) -> Result<Vec<u8>, NewHttpClientError> {
+    tokio::fs::read(&path)
+        .await
+        .map_err(|error| NewHttpClientError::Io { path, error })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::{
+        http::{HeaderMap as AxumHeaderMap, StatusCode},
+        response::IntoResponse,
+        routing::post,
+        Router,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[tokio::test]
+    async fn retries_server_errors_and_sends_auth() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_c = Arc::clone(&calls);
+
+        let app = Router::new().route(
+            "/",
+            post(move |headers: AxumHeaderMap, body: String| async move {
+                let call = calls_c.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(headers["authorization"], "Bearer secret");
+                assert_eq!(headers["x-team"], "ops");
+
+                let signature = headers
+                    .get("x-signature")
+                    .map(|value| value.to_str().unwrap());
+                if call < 3 {
+                    let config: SignatureConfig =
+                        serde_yaml::from_str("{header: x-signature, secret: hook}").unwrap();
+                    assert_eq!(config.verify(signature, body.as_bytes()), Ok(()));
+                } else {
+                    assert_eq!(signature, None);
+                }
+
+                if call < 2 {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config: HttpClientConfig = serde_yaml::from_str(
+            r#"
+            timeout_seconds: 5
+            headers:
+              x-team: ops
+            auth:
+              type: bearer
+              token: secret
+            retry:
+              max_retries: 3
+              backoff_millis: 1
+            signature:
+              header: x-signature
+              secret: hook
+            "#,
+        )
+        .unwrap();
+
+        let client = HttpClient::new(config).await.unwrap();
+        let response = client
+            .send(client.post(url.clone()).body("{}"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let client = HttpClient::new(HttpClientConfig {
+            auth: Some(AuthConfig::Bearer {
+                token: "secret".to_string(),
+            }),
+            headers: [("x-team".to_string(), "ops".to_string())].into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let response = client.send(client.post(url).body("{}")).await.unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn retries_transient_responses_after_retry_after() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_c = Arc::clone(&calls);
+
+        let app = Router::new().route(
+            "/",
+            post(move || async move {
+                match calls_c.fetch_add(1, Ordering::SeqCst) {
+                    0 => (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "1")]).into_response(),
+                    1 => StatusCode::REQUEST_TIMEOUT.into_response(),
+                    _ => StatusCode::OK.into_response(),
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = HttpClient::new(HttpClientConfig {
+            retry: RetryConfig {
+                max_retries: 2,
+                backoff_millis: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let started = std::time::Instant::now();
+        let response = client.send(client.post(url).body("{}")).await.unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+}
@@ -1,6 +1,13 @@
+use crate::{
+    error::FilterParseError,
+    escape::{Quoted, QuotedGlob, QuotedList, RegexLiteral},
+};
+use models::{Alert, AlertmanagerPush};
 use plugins_definitions::PluginMeta;
-use regex::Regex;
-use std::fmt::Display;
+use regex::{Error as RegexError, Regex};
+use schemars::JsonSchema;
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
 #[derive(Debug, Clone)]
 pub enum Expr {
@@ -30,16 +37,20 @@ pub enum SingleOp {
     In(Vec<String>),
     NotIn(Vec<String>),
     Matches(Regex),
+    Like(Glob),
+    NotLike(Glob),
 }
 
 impl Display for SingleOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            SingleOp::Is(v) => write!(f, "is {}", v),
-            SingleOp::IsNot(v) => write!(f, "is not {}", v),
-            SingleOp::In(vs) => write!(f, "in {:?}", vs),
-            SingleOp::NotIn(vs) => write!(f, "not in {:?}", vs),
-            SingleOp::Matches(re) => write!(f, "matches {}", re.as_str()),
+            SingleOp::Is(v) => write!(f, "is {}", Quoted(v)),
+            SingleOp::IsNot(v) => write!(f, "is not {}", Quoted(v)),
+            SingleOp::In(vs) => write!(f, "in [{}]", QuotedList(vs)),
+            SingleOp::NotIn(vs) => write!(f, "not in [{}]", QuotedList(vs)),
+            SingleOp::Matches(re) => write!(f, "matches {}", RegexLiteral(re)),
+            SingleOp::Like(glob) => write!(f, "like {}", QuotedGlob(glob.as_str())),
+            SingleOp::NotLike(glob) => write!(f, "not like {}", QuotedGlob(glob.as_str())),
         }
     }
 }
@@ -59,6 +70,118 @@ impl Display for MultipleOp {
     }
 }
 
+/// Expression evaluated against an alert and the push it belongs to
+#[derive(Debug, Clone)]
+pub enum AlertExpr {
+    Receiver(SingleOp),
+    Status(SingleOp),
+    Label(String, SingleOp),
+    Annotation(String, SingleOp),
+    GroupLabel(String, SingleOp),
+    CommonLabel(String, SingleOp),
+    CommonAnnotation(String, SingleOp),
+    HasLabel(String),
+    HasAnnotation(String),
+    HasGroupLabel(String),
+    HasCommonLabel(String),
+    HasCommonAnnotation(String),
+    MultipleOp(Box<AlertExpr>, MultipleOp, Box<AlertExpr>),
+    Not(Box<AlertExpr>),
+}
+
+impl Display for AlertExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertExpr::Receiver(op) => write!(f, "(receiver {})", op),
+            AlertExpr::Status(op) => write!(f, "(status {})", op),
+            AlertExpr::Label(name, op) => write!(f, "(label {} {})", Quoted(name), op),
+            AlertExpr::Annotation(name, op) => {
+                write!(f, "(annotation {} {})", Quoted(name), op)
+            }
+            AlertExpr::GroupLabel(name, op) => {
+                write!(f, "(group_label {} {})", Quoted(name), op)
+            }
+            AlertExpr::CommonLabel(name, op) => {
+                write!(f, "(common_label {} {})", Quoted(name), op)
+            }
+            AlertExpr::CommonAnnotation(name, op) => {
+                write!(f, "(common_annotation {} {})", Quoted(name), op)
+            }
+            AlertExpr::HasLabel(name) => write!(f, "(has label {})", Quoted(name)),
+            AlertExpr::HasAnnotation(name) => write!(f, "(has annotation {})", Quoted(name)),
+            AlertExpr::HasGroupLabel(name) => write!(f, "(has group_label {})", Quoted(name)),
+            AlertExpr::HasCommonLabel(name) => write!(f, "(has common_label {})", Quoted(name)),
+            AlertExpr::HasCommonAnnotation(name) => {
+                write!(f, "(has common_annotation {})", Quoted(name))
+            }
+            AlertExpr::MultipleOp(l, op, r) => write!(f, "({} {} {})", l, op, r),
+            AlertExpr::Not(expr) => write!(f, "(not {})", expr),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = FilterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::parse_expr(s).map(|expr| *expr)
+    }
+}
+
+impl FromStr for AlertExpr {
+    type Err = FilterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::parse_alert_expr(s).map(|expr| *expr)
+    }
+}
+
+impl Serialize for Expr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(DeError::custom)
+    }
+}
+
+impl JsonSchema for Expr {
+    fn schema_name() -> String {
+        "PluginFilterExpression".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl Serialize for AlertExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AlertExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(DeError::custom)
+    }
+}
+
+impl JsonSchema for AlertExpr {
+    fn schema_name() -> String {
+        "AlertFilterExpression".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
 impl Expr {
     pub fn is_match(&self, meta: &PluginMeta) -> bool {
         match self {
@@ -95,6 +218,53 @@ impl Expr {
     }
 }
 
+impl AlertExpr {
+    /// Evaluates the expression against an alert of the given push.
+    ///
+    /// Missing labels and annotations are treated as empty strings.
+    pub fn is_match(&self, push: &AlertmanagerPush, alert: &Alert) -> bool {
+        match self {
+            AlertExpr::Receiver(op) => op.is_match(&push.receiver),
+            AlertExpr::Status(op) => op.is_match(&alert.status.to_string()),
+            AlertExpr::Label(name, op) => Self::is_entry_match(&alert.labels, name, op),
+            AlertExpr::Annotation(name, op) => Self::is_entry_match(&alert.annotations, name, op),
+            AlertExpr::GroupLabel(name, op) => Self::is_entry_match(&push.group_labels, name, op),
+            AlertExpr::CommonLabel(name, op) => Self::is_entry_match(&push.common_labels, name, op),
+            AlertExpr::CommonAnnotation(name, op) => {
+                Self::is_entry_match(&push.common_annotations, name, op)
+            }
+            AlertExpr::HasLabel(name) => alert.labels.contains_key(name),
+            AlertExpr::HasAnnotation(name) => alert.annotations.contains_key(name),
+            AlertExpr::HasGroupLabel(name) => push.group_labels.contains_key(name),
+            AlertExpr::HasCommonLabel(name) => push.common_labels.contains_key(name),
+            AlertExpr::HasCommonAnnotation(name) => push.common_annotations.contains_key(name),
+            AlertExpr::MultipleOp(l, op, r) => match op {
+                MultipleOp::And => l.is_match(push, alert) && r.is_match(push, alert),
+                MultipleOp::Or => l.is_match(push, alert) || r.is_match(push, alert),
+            },
+            AlertExpr::Not(expr) => !expr.is_match(push, alert),
+        }
+    }
+
+    /// Removes all alerts from the push that do not match the expression.
+    ///
+    /// See [`AlertmanagerPush::retain_alerts`] for how `truncated_alerts` and `status` are updated.
+    pub fn retain_matching(&self, push: &mut AlertmanagerPush) {
+        let alerts = std::mem::take(&mut push.alerts);
+        let alerts_count = alerts.len();
+        push.alerts = alerts
+            .into_iter()
+            .filter(|alert| self.is_match(push, alert))
+            .collect();
+
+        push.count_removed_alerts(alerts_count);
+    }
+
+    fn is_entry_match(map: &BTreeMap<String, String>, name: &str, op: &SingleOp) -> bool {
+        op.is_match(map.get(name).map(String::as_str).unwrap_or_default())
+    }
+}
+
 impl SingleOp {
     pub fn is_match(&self, value: &str) -> bool {
         match self {
@@ -103,13 +273,67 @@ impl SingleOp {
             SingleOp::In(vs) => vs.contains(&value.to_string()),
             SingleOp::NotIn(vs) => !vs.contains(&value.to_string()),
             SingleOp::Matches(re) => re.is_match(value),
+            SingleOp::Like(glob) => glob.is_match(value),
+            SingleOp::NotLike(glob) => !glob.is_match(value),
         }
     }
 }
 
+/// Shell-style wildcard pattern
+///
+/// `*` matches any sequence of characters and `?` matches a single character.
+/// A backslash matches the next character literally, so `\*` matches `*`.
+/// The whole value must match.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: String,
+    regex: Regex,
+}
+
+impl Glob {
+    pub fn new(pattern: impl Into<String>) -> Result<Self, RegexError> {
+        let pattern = pattern.into();
+
+        let literal = |c: char| regex::escape(c.encode_utf8(&mut [0; 4]));
+
+        let mut regex = String::from("^");
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                // A trailing backslash matches itself
+                '\\' => regex.push_str(&literal(chars.next().unwrap_or('\\'))),
+                c => regex.push_str(&literal(c)),
+            }
+        }
+        regex.push('$');
+
+        Ok(Self {
+            regex: Regex::new(&regex)?,
+            pattern,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
+}
+
+impl Display for Glob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::filter;
+    use crate::{alert_filter, filter};
+    use models::{Alert, AlertmanagerPush, Status};
 
     #[test]
     fn parse() {
@@ -238,4 +462,242 @@ mod test {
 
         assert_eq!(expr_1.is_match(&meta), expr_2.is_match(&meta));
     }
+
+    #[test]
+    fn parse_alert_filter() {
+        let push = AlertmanagerPush {
+            receiver: "team-db".to_string(),
+            status: Status::Firing,
+            common_labels: [("team".to_string(), "dba".to_string())].into(),
+            alerts: vec![
+                Alert {
+                    status: Status::Firing,
+                    labels: [("severity".to_string(), "critical".to_string())].into(),
+                    annotations: [("runbook_url".to_string(), "http://runbook".to_string())].into(),
+                    ..Default::default()
+                },
+                Alert {
+                    status: Status::Resolved,
+                    labels: [("severity".to_string(), "warning".to_string())].into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let firing = &push.alerts[0];
+        let resolved = &push.alerts[1];
+
+        let expr = alert_filter::AlertExprParser::new()
+            .parse("receiver is team-db")
+            .unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&push, firing));
+        assert!(expr.is_match(&push, resolved));
+
+        let expr = alert_filter::AlertExprParser::new()
+            .parse("status is firing")
+            .unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&push, firing));
+        assert!(!expr.is_match(&push, resolved));
+
+        let expr = alert_filter::AlertExprParser::new()
+            .parse("label severity in [critical, page]")
+            .unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&push, firing));
+        assert!(!expr.is_match(&push, resolved));
+
+        let expr = alert_filter::AlertExprParser::new()
+            .parse("common_label team matches /db.*/")
+            .unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&push, firing));
+
+        let expr = alert_filter::AlertExprParser::new()
+            .parse("has annotation runbook_url")
+            .unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&push, firing));
+        assert!(!expr.is_match(&push, resolved));
+
+        let expr = alert_filter::AlertExprParser::new()
+            .parse(
+                "label missing is not some_value and not (status is resolved || has label missing)",
+            )
+            .unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&push, firing));
+        assert!(!expr.is_match(&push, resolved));
+
+        let mut filtered_push = push.clone();
+        alert_filter::AlertExprParser::new()
+            .parse("label severity is warning")
+            .unwrap()
+            .retain_matching(&mut filtered_push);
+
+        assert_eq!(filtered_push.alerts, vec![resolved.clone()]);
+        assert_eq!(filtered_push.status, Status::Resolved);
+        assert_eq!(filtered_push.truncated_alerts, 1);
+
+        assert!(filter::ExprParser::new()
+            .parse("label severity is warning")
+            .is_err());
+    }
+
+    #[test]
+    fn parse_quoted_strings_globs_and_escaped_regexes() {
+        let meta = plugins_definitions::PluginMeta {
+            name: "postgres.prod: \"main\"",
+            group: "group",
+            type_: "type",
+        };
+
+        let expr = crate::parse_expr(r#"name is "postgres.prod: \"main\"""#).unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&meta));
+
+        let expr =
+            crate::parse_expr(r#"name in ["a b", postgres_1, "postgres.prod: \"main\""]"#).unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&meta));
+
+        let expr = crate::parse_expr("name like postgres*").unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&meta));
+
+        let expr =
+            crate::parse_expr(r#"name like "postgres.pro?:*" and type not like t*x"#).unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&meta));
+
+        let expr = crate::parse_expr("name like postgres").unwrap();
+
+        assert!(!expr.is_match(&meta));
+
+        let meta = plugins_definitions::PluginMeta {
+            name: "backup*?",
+            group: r"C:\backups",
+            type_: "type",
+        };
+
+        let expr = crate::parse_expr(r#"name like "backup\*\?" and group like "C:\\*""#).unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&meta));
+
+        let expr = crate::parse_expr(r#"name like "backup\*\?""#).unwrap();
+
+        assert!(!expr.is_match(&plugins_definitions::PluginMeta {
+            name: "backups?",
+            ..meta
+        }));
+
+        let meta = plugins_definitions::PluginMeta {
+            name: "http://host/path",
+            group: "group",
+            type_: "type",
+        };
+
+        let expr = crate::parse_expr(r"name matches /^http:\/\/host\/.*$/").unwrap();
+        println!("{}", expr);
+
+        assert!(expr.is_match(&meta));
+
+        let expr = crate::parse_alert_expr(
+            r#"label "alert.name" like "High*" and has annotation "runbook url""#,
+        )
+        .unwrap();
+        println!("{}", expr);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let exprs = [
+            "!(not (not name is some_name and not group is some_group_name or type is a_type))",
+            "name in [plugin_SoS, plug-in] and not (group in [group990_ss, group_defaults] or type in [wrtier, reader])",
+            r#"name is "is" or name is "a \"quoted\" name\n" or group not in ["a", "b c"]"#,
+            r"name matches /^http:\/\/host\/.*$/ and type not like post*",
+            r#"name like "db.*-?" || name is not "name""#,
+            r#"name like "literal\*\?\\" and group like "\"*\"""#,
+        ];
+
+        for input in exprs {
+            let expr = crate::parse_expr(input).unwrap();
+            let displayed = expr.to_string();
+            println!("{}", displayed);
+
+            let reparsed = crate::parse_expr(&displayed).unwrap();
+            assert_eq!(reparsed.to_string(), displayed);
+        }
+
+        let alert_exprs = [
+            "receiver is team-db and status is firing",
+            r#"label severity in [critical, page] and common_label "has" matches /db.*\// or has annotation runbook_url"#,
+            r#"not (has common_annotation "a b" || group_label x like "y*")"#,
+        ];
+
+        for input in alert_exprs {
+            let expr = crate::parse_alert_expr(input).unwrap();
+            let displayed = expr.to_string();
+            println!("{}", displayed);
+
+            let reparsed = crate::parse_alert_expr(&displayed).unwrap();
+            assert_eq!(reparsed.to_string(), displayed);
+        }
+    }
+
+    #[test]
+    fn serde() {
+        #[derive(serde::Deserialize, serde::Serialize)]
+        struct Config {
+            targets: super::Expr,
+            alerts: super::AlertExpr,
+        }
+
+        let config: Config = serde_yaml::from_str(
+            r#"
+            targets: name like "postgres_*" and group is default
+            alerts: status is firing
+            "#,
+        )
+        .unwrap();
+
+        let meta = plugins_definitions::PluginMeta {
+            name: "postgres_1",
+            group: "default",
+            type_: "postgres",
+        };
+
+        assert!(config.targets.is_match(&meta));
+
+        let json = serde_json::to_string(&config).unwrap();
+        println!("{}", json);
+
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert!(config.targets.is_match(&meta));
+
+        let error = serde_yaml::from_str::<Config>(
+            r#"
+            targets: name iz postgres
+            alerts: status is firing
+            "#,
+        )
+        .err()
+        .unwrap();
+        println!("{}", error);
+
+        assert!(error.to_string().contains("column 6"));
+    }
 }
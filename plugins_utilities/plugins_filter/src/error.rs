@@ -0,0 +1,163 @@
+use lalrpop_util::{lexer::Token, ParseError};
+use regex::Error as RegexError;
+use thiserror::Error as ThisError;
+
+#[derive(ThisError, Debug, Clone)]
+pub enum UserErrorKind {
+    #[error("Invalid regex: {0}")]
+    Regex(
+        #[source]
+        #[from]
+        RegexError,
+    ),
+    #[error("Invalid escape sequence: \\{0}")]
+    InvalidEscape(char),
+}
+
+/// Error raised by the grammar actions
+///
+/// Carries the span of the offending token, since lalrpop does not attach one to user errors.
+#[derive(ThisError, Debug, Clone)]
+#[error("{kind}")]
+pub struct UserError {
+    pub kind: UserErrorKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl UserError {
+    pub(crate) fn new(kind: impl Into<UserErrorKind>, start: usize, end: usize) -> Self {
+        Self {
+            kind: kind.into(),
+            start,
+            end,
+        }
+    }
+}
+
+/// Error returned when a filter expression can not be parsed
+#[derive(ThisError, Debug, Clone)]
+#[error("{message} at column {column}\n{snippet}")]
+pub struct FilterParseError {
+    /// What went wrong
+    pub message: String,
+    /// Column (1-based, in characters) of the offending token
+    pub column: usize,
+    /// The offending line with a caret underlining the offending token
+    pub snippet: String,
+}
+
+impl FilterParseError {
+    pub(crate) fn new(input: &str, error: ParseError<usize, Token<'_>, UserError>) -> Self {
+        let (message, start, end) = match error {
+            ParseError::InvalidToken { location } => {
+                (String::from("Invalid token"), location, location)
+            }
+            ParseError::UnrecognizedEof { location, expected } => (
+                format!(
+                    "Unexpected end of input, expected one of {}",
+                    Self::format_expected(&expected)
+                ),
+                location,
+                location,
+            ),
+            ParseError::UnrecognizedToken {
+                token: (start, token, end),
+                expected,
+            } => (
+                format!(
+                    "Unexpected token \"{}\", expected one of {}",
+                    token,
+                    Self::format_expected(&expected)
+                ),
+                start,
+                end,
+            ),
+            ParseError::ExtraToken {
+                token: (start, token, end),
+            } => (format!("Unexpected token \"{}\"", token), start, end),
+            ParseError::User { error } => (error.to_string(), error.start, error.end),
+        };
+
+        let line_start = input[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = input[start..]
+            .find('\n')
+            .map(|i| start + i)
+            .unwrap_or(input.len());
+
+        let column = input[line_start..start].chars().count() + 1;
+        let width = input[start..end.min(line_end)].chars().count().max(1);
+
+        let snippet = format!(
+            "{}\n{}{}",
+            &input[line_start..line_end],
+            " ".repeat(column - 1),
+            "^".repeat(width)
+        );
+
+        Self {
+            message,
+            column,
+            snippet,
+        }
+    }
+
+    /// Regex terminals are not meaningful to users, so they are collapsed into `value`.
+    fn format_expected(expected: &[String]) -> String {
+        let mut formatted: Vec<&str> = expected
+            .iter()
+            .map(|token| {
+                if token.starts_with("r#") {
+                    "value"
+                } else {
+                    token.as_str()
+                }
+            })
+            .collect();
+
+        formatted.dedup();
+        formatted.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parse_alert_expr, parse_expr};
+
+    #[test]
+    fn error_position() {
+        let error = parse_expr("name is postgres and group iz default").unwrap_err();
+        println!("{}", error);
+
+        assert_eq!(error.column, 28);
+        assert_eq!(
+            error.snippet,
+            "name is postgres and group iz default\n                           ^^"
+        );
+
+        let error = parse_expr("name matches /(/").unwrap_err();
+        println!("{}", error);
+
+        assert_eq!(error.column, 14);
+        assert!(error.message.starts_with("Invalid regex"));
+        assert_eq!(error.snippet, "name matches /(/\n             ^^^");
+
+        let error = parse_expr(r#"name is "po\stgres""#).unwrap_err();
+        println!("{}", error);
+
+        assert_eq!(error.column, 9);
+        assert_eq!(error.message, "Invalid escape sequence: \\s");
+
+        let error = parse_alert_expr("label severity in [critical,").unwrap_err();
+        println!("{}", error);
+
+        assert_eq!(error.column, 29);
+        assert!(error.message.starts_with("Unexpected end of input"));
+
+        let error = parse_expr("name is ä @").unwrap_err();
+        println!("{}", error);
+
+        assert_eq!(error.column, 9);
+        assert_eq!(error.snippet, "name is ä @\n        ^");
+    }
+}
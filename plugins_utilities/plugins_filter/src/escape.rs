@@ -0,0 +1,174 @@
+use crate::error::UserErrorKind;
+use regex::Regex;
+use std::fmt::{Display, Write};
+
+/// Resolves the escape sequences of a double-quoted string literal
+///
+/// The surrounding quotes must already be stripped.
+pub(crate) fn unescape_string(s: &str) -> Result<String, UserErrorKind> {
+    unescape(s, &[])
+}
+
+/// Resolves the escape sequences of a double-quoted glob pattern
+///
+/// `\*`, `\?` and `\\` are kept, so [`Glob`](crate::ast::Glob) matches them literally.
+/// The surrounding quotes must already be stripped.
+pub(crate) fn unescape_glob(s: &str) -> Result<String, UserErrorKind> {
+    unescape(s, GLOB_ESCAPES)
+}
+
+/// Characters a backslash escapes in a glob pattern
+pub(crate) const GLOB_ESCAPES: &[char] = &['*', '?', '\\'];
+
+/// Resolves escape sequences, keeping the backslash in front of the `kept` characters
+fn unescape(s: &str, kept: &[char]) -> Result<String, UserErrorKind> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some(c) if kept.contains(&c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            Some('"') => unescaped.push('"'),
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => return Err(UserErrorKind::InvalidEscape(c)),
+            // The lexer never produces a literal ending with a lone backslash
+            None => return Err(UserErrorKind::InvalidEscape(' ')),
+        }
+    }
+
+    Ok(unescaped)
+}
+
+/// Resolves `\/` in a regex literal
+///
+/// The surrounding slashes must already be stripped. All other escapes are left to the regex engine.
+pub(crate) fn unescape_regex(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('/') => unescaped.push('/'),
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Displays a string as a double-quoted literal that [`unescape_string`] resolves to the original
+pub(crate) struct Quoted<'a>(pub &'a str);
+
+impl Display for Quoted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_char('"')?;
+
+        for c in self.0.chars() {
+            write_escaped(f, c)?;
+        }
+
+        f.write_char('"')
+    }
+}
+
+/// Displays a glob pattern as a double-quoted literal that [`unescape_glob`] resolves to the original
+pub(crate) struct QuotedGlob<'a>(pub &'a str);
+
+impl Display for QuotedGlob<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_char('"')?;
+
+        let mut chars = self.0.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(c) if GLOB_ESCAPES.contains(&c) => {
+                        f.write_char('\\')?;
+                        f.write_char(c)?;
+                    }
+                    // Any other escaped character matches itself
+                    Some(c) => write_escaped(f, c)?,
+                    None => f.write_str("\\\\")?,
+                },
+                c => write_escaped(f, c)?,
+            }
+        }
+
+        f.write_char('"')
+    }
+}
+
+fn write_escaped(f: &mut std::fmt::Formatter<'_>, c: char) -> std::fmt::Result {
+    match c {
+        '"' => f.write_str("\\\""),
+        '\\' => f.write_str("\\\\"),
+        '\n' => f.write_str("\\n"),
+        '\t' => f.write_str("\\t"),
+        '\r' => f.write_str("\\r"),
+        c => f.write_char(c),
+    }
+}
+
+/// Displays strings as comma separated [`Quoted`] literals
+pub(crate) struct QuotedList<'a>(pub &'a [String]);
+
+impl Display for QuotedList<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, s) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", Quoted(s))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Displays a regex as a `/`-delimited literal that [`unescape_regex`] resolves to an equivalent regex
+pub(crate) struct RegexLiteral<'a>(pub &'a Regex);
+
+impl Display for RegexLiteral<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_char('/')?;
+
+        let mut chars = self.0.as_str().chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '/' => f.write_str("\\/")?,
+                '\\' => match chars.next() {
+                    // `\/` and `/` are the same regex
+                    Some('/') => f.write_str("\\/")?,
+                    Some(c) => {
+                        f.write_char('\\')?;
+                        f.write_char(c)?;
+                    }
+                    None => f.write_char('\\')?,
+                },
+                c => f.write_char(c)?,
+            }
+        }
+
+        f.write_char('/')
+    }
+}
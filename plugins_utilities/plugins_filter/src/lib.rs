@@ -1,4 +1,23 @@
+use ast::{AlertExpr, Expr};
+use error::FilterParseError;
 use lalrpop_util::lalrpop_mod;
 
 pub mod ast;
+pub mod error;
+mod escape;
 lalrpop_mod!(pub filter);
+lalrpop_mod!(pub alert_filter);
+
+/// Parses a plugin filter expression
+pub fn parse_expr(input: &str) -> Result<Box<Expr>, FilterParseError> {
+    filter::ExprParser::new()
+        .parse(input)
+        .map_err(|error| FilterParseError::new(input, error))
+}
+
+/// Parses an alert filter expression
+pub fn parse_alert_expr(input: &str) -> Result<Box<AlertExpr>, FilterParseError> {
+    alert_filter::AlertExprParser::new()
+        .parse(input)
+        .map_err(|error| FilterParseError::new(input, error))
+}
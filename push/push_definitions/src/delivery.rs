@@ -0,0 +1,248 @@
+use models::{Alert, AlertmanagerPush, Status};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "DeliveryConfig", into = "DeliveryConfig")]
+/// How a push is handed to a plugin
+///
+/// Configured as `as_is`, `per_alert` or `regroup_by: [labels]`.
+pub enum Delivery {
+    /// The push as Alertmanager grouped it
+    #[default]
+    AsIs,
+    /// One push per alert
+    PerAlert,
+    /// One push per distinct combination of the label values.
+    /// Missing labels count as empty values and are left out of the group labels and key
+    RegroupBy(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum DeliveryMode {
+    AsIs,
+    PerAlert,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum DeliveryConfig {
+    Mode(DeliveryMode),
+    RegroupBy { regroup_by: Vec<String> },
+}
+
+impl From<DeliveryConfig> for Delivery {
+    fn from(config: DeliveryConfig) -> Self {
+        match config {
+            DeliveryConfig::Mode(DeliveryMode::AsIs) => Delivery::AsIs,
+            DeliveryConfig::Mode(DeliveryMode::PerAlert) => Delivery::PerAlert,
+            DeliveryConfig::RegroupBy { regroup_by } => Delivery::RegroupBy(regroup_by),
+        }
+    }
+}
+
+impl From<Delivery> for DeliveryConfig {
+    fn from(delivery: Delivery) -> Self {
+        match delivery {
+            Delivery::AsIs => DeliveryConfig::Mode(DeliveryMode::AsIs),
+            Delivery::PerAlert => DeliveryConfig::Mode(DeliveryMode::PerAlert),
+            Delivery::RegroupBy(regroup_by) => DeliveryConfig::RegroupBy { regroup_by },
+        }
+    }
+}
+
+impl JsonSchema for Delivery {
+    fn schema_name() -> String {
+        "Delivery".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        DeliveryConfig::json_schema(gen)
+    }
+}
+
+impl Delivery {
+    /// Splits the push into the pushes to deliver.
+    ///
+    /// Sub-pushes get their own group key, derived from the key of the push, group labels, common labels, common annotations and status.
+    /// They are not truncated, since the truncated alerts cannot be assigned to a sub-push.
+    pub fn split(&self, push: &AlertmanagerPush) -> Vec<AlertmanagerPush> {
+        match self {
+            Delivery::AsIs => vec![push.clone()],
+            Delivery::PerAlert => push
+                .alerts
+                .iter()
+                .map(|alert| AlertmanagerPush {
+                    group_key: format!("{}:{}", push.group_key, alert.fingerprint),
+                    truncated_alerts: 0,
+                    status: alert.status.clone(),
+                    common_labels: alert.labels.clone(),
+                    common_annotations: alert.annotations.clone(),
+                    alerts: vec![alert.clone()],
+                    ..push.clone()
+                })
+                .collect(),
+            Delivery::RegroupBy(labels) => {
+                // Keeps the order in which the groups first appear
+                let mut groups: Vec<(BTreeMap<String, String>, Vec<Alert>)> = vec![];
+
+                for alert in push.alerts.iter() {
+                    let group_labels = labels
+                        .iter()
+                        .map(|label| {
+                            let value = alert.labels.get(label).cloned().unwrap_or_default();
+                            (label.clone(), value)
+                        })
+                        .collect::<BTreeMap<_, _>>();
+
+                    match groups
+                        .iter_mut()
+                        .find(|(labels, _)| *labels == group_labels)
+                    {
+                        Some((_, alerts)) => alerts.push(alert.clone()),
+                        None => groups.push((group_labels, vec![alert.clone()])),
+                    }
+                }
+
+                groups
+                    .into_iter()
+                    .map(|(group_labels, alerts)| regrouped(push, group_labels, alerts))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn regrouped(
+    push: &AlertmanagerPush,
+    group_labels: BTreeMap<String, String>,
+    alerts: Vec<Alert>,
+) -> AlertmanagerPush {
+    // Like Alertmanager, missing labels are left out of both the group labels and the key
+    let group_labels = group_labels
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect::<BTreeMap<_, _>>();
+
+    let group_key = format!(
+        "{}:{{{}}}",
+        push.group_key,
+        group_labels
+            .iter()
+            .map(|(name, value)| format!("{}={:?}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let status = match alerts.iter().any(|alert| alert.status == Status::Firing) {
+        true => Status::Firing,
+        false => Status::Resolved,
+    };
+
+    AlertmanagerPush {
+        group_key,
+        truncated_alerts: 0,
+        status,
+        common_labels: common(alerts.iter().map(|alert| &alert.labels)),
+        common_annotations: common(alerts.iter().map(|alert| &alert.annotations)),
+        group_labels,
+        alerts,
+        ..push.clone()
+    }
+}
+
+/// Pairs present in all maps
+fn common<'a>(
+    mut maps: impl Iterator<Item = &'a BTreeMap<String, String>>,
+) -> BTreeMap<String, String> {
+    let Some(first) = maps.next() else {
+        return BTreeMap::new();
+    };
+
+    let mut common = first.clone();
+    for map in maps {
+        common.retain(|name, value| map.get(name) == Some(value));
+    }
+
+    common
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn alert(team: Option<&str>, instance: &str, status: Status) -> Alert {
+        let mut labels = BTreeMap::from([
+            ("alertname".to_string(), "Disk".to_string()),
+            ("instance".to_string(), instance.to_string()),
+        ]);
+        if let Some(team) = team {
+            labels.insert("team".to_string(), team.to_string());
+        }
+
+        Alert {
+            status,
+            labels,
+            fingerprint: instance.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn split() {
+        let push = AlertmanagerPush {
+            group_key: "{}:{alertname=\"Disk\"}".to_string(),
+            truncated_alerts: 2,
+            status: Status::Firing,
+            group_labels: BTreeMap::from([("alertname".to_string(), "Disk".to_string())]),
+            alerts: vec![
+                alert(Some("a"), "db-1", Status::Resolved),
+                alert(Some("b"), "db-2", Status::Firing),
+                alert(Some("a"), "db-3", Status::Firing),
+                alert(None, "db-4", Status::Resolved),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(Delivery::AsIs.split(&push), vec![push.clone()]);
+
+        let per_alert = Delivery::PerAlert.split(&push);
+        assert_eq!(per_alert.len(), 4);
+        assert_eq!(per_alert[0].status, Status::Resolved);
+        assert_eq!(per_alert[0].common_labels, push.alerts[0].labels);
+        assert_eq!(per_alert[0].group_key, "{}:{alertname=\"Disk\"}:db-1");
+        assert_eq!(per_alert[0].truncated_alerts, 0);
+
+        let regrouped = Delivery::RegroupBy(vec!["team".to_string()]).split(&push);
+        assert_eq!(regrouped.len(), 3);
+
+        assert_eq!(
+            regrouped[0].group_key,
+            "{}:{alertname=\"Disk\"}:{team=\"a\"}"
+        );
+        assert_eq!(regrouped[0].status, Status::Firing);
+        assert_eq!(
+            regrouped[0].alerts,
+            vec![push.alerts[0].clone(), push.alerts[2].clone()]
+        );
+        assert_eq!(
+            regrouped[0].group_labels,
+            BTreeMap::from([("team".to_string(), "a".to_string())])
+        );
+        assert_eq!(
+            regrouped[0].common_labels,
+            BTreeMap::from([
+                ("alertname".to_string(), "Disk".to_string()),
+                ("team".to_string(), "a".to_string()),
+            ])
+        );
+
+        assert_eq!(regrouped[1].alerts, vec![push.alerts[1].clone()]);
+
+        assert_eq!(regrouped[2].group_key, "{}:{alertname=\"Disk\"}:{}");
+        assert_eq!(regrouped[2].status, Status::Resolved);
+        assert!(regrouped[2].group_labels.is_empty());
+    }
+}
@@ -2,6 +2,12 @@ use async_trait::async_trait;
 use models::AlertmanagerPush;
 use thiserror::Error as ThisError;
 
+mod delivery;
+mod outcome;
+
+pub use delivery::Delivery;
+pub use outcome::{AlertOutcome, AlertResult, ErrorClass, PushOutcome};
+
 #[derive(ThisError, Debug)]
 #[error("Plugin initialization failed: {error}")]
 pub struct InitializeError {
@@ -13,6 +19,47 @@ pub struct InitializeError {
 #[derive(ThisError, Debug)]
 #[error("Push failed: {error}")]
 pub struct PushError {
+    #[source]
+    pub error: Box<dyn std::error::Error + Send + Sync>,
+    pub class: ErrorClass,
+}
+
+impl PushError {
+    pub fn new(
+        class: ErrorClass,
+        error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self {
+            error: error.into(),
+            class,
+        }
+    }
+
+    /// Results for a batch that failed as a whole, with the error for every push
+    pub fn for_batch(self, len: usize) -> Vec<Result<PushOutcome, PushError>> {
+        let message = self.error.to_string();
+        let class = self.class;
+
+        let mut results = Vec::with_capacity(len);
+        if len > 0 {
+            results.push(Err(self));
+        }
+        results.extend((1..len).map(|_| Err(PushError::new(class, message.clone()))));
+
+        results
+    }
+}
+
+/// Unclassified errors are assumed to be permanent, so they are not retried
+impl From<Box<dyn std::error::Error + Send + Sync>> for PushError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self::new(ErrorClass::Permanent, error)
+    }
+}
+
+#[derive(ThisError, Debug)]
+#[error("Plugin shutdown failed: {error}")]
+pub struct ShutdownError {
     #[from]
     #[source]
     pub error: Box<dyn std::error::Error + Send + Sync>,
@@ -23,5 +70,44 @@ pub trait Push: Send + Sync + 'static {
     /// Initialize on startup
     async fn initialize(&mut self) -> Result<(), InitializeError>;
 
-    async fn push_alert(&self, alertmanager_push: &AlertmanagerPush) -> Result<(), PushError>;
+    /// Push the alerts
+    ///
+    /// Returns an error if the push failed as a whole.
+    /// Failures of single alerts are reported per alert in [`PushOutcome::Alerts`],
+    /// which is returned even if every alert failed.
+    async fn push_alert(
+        &self,
+        alertmanager_push: &AlertmanagerPush,
+    ) -> Result<PushOutcome, PushError>;
+
+    /// Push many pushes at once, like replays or migrations
+    ///
+    /// Returns the result of every push, in order.
+    /// Loops over [`Push::push_alert`] unless the plugin can do better, like in a single transaction.
+    async fn push_alerts(
+        &self,
+        alertmanager_pushes: &[AlertmanagerPush],
+    ) -> Vec<Result<PushOutcome, PushError>> {
+        let mut results = Vec::with_capacity(alertmanager_pushes.len());
+
+        for alertmanager_push in alertmanager_pushes {
+            results.push(self.push_alert(alertmanager_push).await);
+        }
+
+        results
+    }
+
+    /// Whether the plugin retries transient failures itself.
+    ///
+    /// The server does not retry the pushes of such plugins, so the retries do not multiply.
+    fn retries_itself(&self) -> bool {
+        false
+    }
+
+    /// Flush buffers and release resources on shutdown
+    ///
+    /// Called once, after the server stopped accepting pushes.
+    async fn shutdown(&self) -> Result<(), ShutdownError> {
+        Ok(())
+    }
 }
@@ -0,0 +1,163 @@
+use crate::PushError;
+use models::AlertmanagerPush;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+/// Whether a failed push may succeed when retried
+pub enum ErrorClass {
+    /// Temporary, like an unreachable server or a lost connection. Worth retrying
+    #[default]
+    Transient,
+    /// Fails again when retried, like a template error or a rejected request
+    Permanent,
+}
+
+impl ErrorClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::Transient => "transient",
+            ErrorClass::Permanent => "permanent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// What happened to a single alert
+pub enum AlertOutcome {
+    Pushed,
+    /// Intentionally not pushed
+    Skipped {
+        reason: String,
+    },
+    Failed {
+        class: ErrorClass,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertResult {
+    /// Fingerprint of the alert
+    pub fingerprint: String,
+    pub outcome: AlertOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// What a plugin did with a push
+pub enum PushOutcome {
+    /// All alerts were pushed
+    Pushed,
+    /// Nothing was pushed on purpose, like a duplicate or a filtered out push
+    Skipped { reason: String },
+    /// Outcome of every alert, if they differ
+    Alerts(Vec<AlertResult>),
+}
+
+impl PushOutcome {
+    /// Collapses the results to [`PushOutcome::Pushed`] or [`PushOutcome::Skipped`] if they all agree
+    pub fn from_alerts(results: Vec<AlertResult>) -> Self {
+        if results
+            .iter()
+            .all(|result| result.outcome == AlertOutcome::Pushed)
+        {
+            return PushOutcome::Pushed;
+        }
+
+        let mut outcomes = results.iter().map(|result| &result.outcome);
+        if let Some(AlertOutcome::Skipped { reason }) = outcomes.next() {
+            if outcomes.all(|outcome| matches!(outcome, AlertOutcome::Skipped { reason: other } if other == reason))
+            {
+                return PushOutcome::Skipped {
+                    reason: reason.clone(),
+                };
+            }
+        }
+
+        PushOutcome::Alerts(results)
+    }
+
+    /// Applies the outcome to every alert of the push
+    pub fn for_alerts(&self, alertmanager_push: &AlertmanagerPush) -> Vec<AlertResult> {
+        match self {
+            PushOutcome::Alerts(results) => results.clone(),
+            PushOutcome::Pushed => AlertResult::all(alertmanager_push, AlertOutcome::Pushed),
+            PushOutcome::Skipped { reason } => AlertResult::all(
+                alertmanager_push,
+                AlertOutcome::Skipped {
+                    reason: reason.clone(),
+                },
+            ),
+        }
+    }
+}
+
+impl AlertResult {
+    /// The same outcome for every alert of the push
+    pub fn all(alertmanager_push: &AlertmanagerPush, outcome: AlertOutcome) -> Vec<Self> {
+        alertmanager_push
+            .alerts
+            .iter()
+            .map(|alert| AlertResult {
+                fingerprint: alert.fingerprint.clone(),
+                outcome: outcome.clone(),
+            })
+            .collect()
+    }
+}
+
+impl From<&PushError> for AlertOutcome {
+    fn from(error: &PushError) -> Self {
+        AlertOutcome::Failed {
+            class: error.class,
+            message: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn result(fingerprint: &str, outcome: AlertOutcome) -> AlertResult {
+        AlertResult {
+            fingerprint: fingerprint.to_string(),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn collapse_alert_results() {
+        let skipped = |reason: &str| AlertOutcome::Skipped {
+            reason: reason.to_string(),
+        };
+
+        assert_eq!(
+            PushOutcome::from_alerts(vec![
+                result("a", AlertOutcome::Pushed),
+                result("b", AlertOutcome::Pushed)
+            ]),
+            PushOutcome::Pushed
+        );
+        assert_eq!(
+            PushOutcome::from_alerts(vec![
+                result("a", skipped("duplicate")),
+                result("b", skipped("duplicate"))
+            ]),
+            PushOutcome::Skipped {
+                reason: "duplicate".to_string()
+            }
+        );
+
+        let mixed = vec![
+            result("a", skipped("duplicate")),
+            result("b", skipped("filtered")),
+            result("c", AlertOutcome::Pushed),
+        ];
+        assert_eq!(
+            PushOutcome::from_alerts(mixed.clone()),
+            PushOutcome::Alerts(mixed)
+        );
+    }
+}